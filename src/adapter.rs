@@ -0,0 +1,151 @@
+use std::{any::TypeId, marker::PhantomData, rc::Rc};
+
+use crate::{
+    swap::FnTask, DataBase, DbKey, ExecutionGraphBuilder, KeyType, MissingDependency, Task,
+    TaskInput, TaskOutput,
+};
+
+/// The task added by [`ExecutionGraphBuilder::add_adapter`], converting the
+/// value of `A` into a value of `B`. There is at most one adapter per pair of
+/// keys. Private, so that it is only ever added along with its conversion.
+pub(crate) struct Adapt<A, B>(PhantomData<fn(A) -> B>);
+
+/// A task input holding a clone of the value of `A`.
+pub struct AdaptFrom<A: DbKey>(pub A::Value);
+
+impl<A: DbKey> DbKey for AdaptFrom<A> {
+    type Value = AdaptFrom<A>;
+}
+
+impl<Db: DataBase, A: DbKey> TaskInput<Db> for AdaptFrom<A>
+where
+    A::Value: Clone,
+{
+    fn from_db(db: &Db) -> Self {
+        AdaptFrom(db.get::<A>().unwrap().clone())
+    }
+
+    fn dep_types() -> Vec<KeyType> {
+        vec![KeyType::of::<A>()]
+    }
+}
+
+/// A task output writing its value to `B`.
+pub struct AdaptTo<B: DbKey>(pub B::Value);
+
+impl<B: DbKey> DbKey for AdaptTo<B> {
+    type Value = AdaptTo<B>;
+}
+
+impl<Db: DataBase, B: DbKey> TaskOutput<Db> for AdaptTo<B>
+where
+    B::Value: Clone,
+{
+    fn to_db(&self, db: &mut Db) {
+        db.put::<B>(self.0.clone());
+    }
+
+    fn out_types() -> Vec<KeyType> {
+        vec![KeyType::of::<B>()]
+    }
+}
+
+impl<Db: DataBase, A: DbKey, B: DbKey> Task<Db> for Adapt<A, B>
+where
+    A::Value: Clone,
+    B::Value: Clone,
+{
+    type Input = AdaptFrom<A>;
+    type Output = AdaptTo<B>;
+
+    fn execute(_input: Self::Input) -> Self::Output {
+        // Only `add_adapter` adds this task, installing the conversion as its
+        // runner.
+        unreachable!("adapters only run the conversion given to add_adapter")
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraphBuilder<Db, Ctx> {
+    /// Adds a task writing `convert` of the value of `A` to `B`, to connect a
+    /// producer of `A` to consumers expecting `B` without a full task
+    /// implementation.
+    pub fn add_adapter<A: DbKey, B: DbKey>(
+        &mut self,
+        convert: impl Fn(&A::Value) -> B::Value + 'static,
+    ) -> Result<&mut Self, MissingDependency>
+    where
+        A::Value: Clone,
+        B::Value: Clone,
+    {
+        self.add_task::<Adapt<A, B>>()?;
+        self.graph.runners.insert(
+            TypeId::of::<Adapt<A, B>>(),
+            Rc::new(FnTask::new(move |input: AdaptFrom<A>| {
+                AdaptTo::<B>(convert(&input.0))
+            })),
+        );
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemoryDb;
+
+    struct Celsius;
+
+    impl DbKey for Celsius {
+        type Value = f64;
+    }
+
+    struct Fahrenheit;
+
+    impl DbKey for Fahrenheit {
+        type Value = f64;
+    }
+
+    struct Label;
+
+    impl DbKey for Label {
+        type Value = String;
+    }
+
+    #[test]
+    fn test_adapters() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_input::<Celsius>(100.0)
+            .unwrap()
+            .add_adapter::<Celsius, Fahrenheit>(|c| c * 9.0 / 5.0 + 32.0)
+            .unwrap()
+            .add_adapter::<Fahrenheit, Label>(|f| format!("{f}°F"))
+            .unwrap();
+        let mut graph = builder.build();
+        graph.execute_all().unwrap();
+        assert_eq!(graph.db().get::<Fahrenheit>(), Some(&212.0));
+        assert_eq!(graph.db().get::<Label>().unwrap(), "212°F");
+    }
+
+    #[test]
+    fn test_restored_adapter_keeps_conversion() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_input::<Celsius>(0.0)
+            .unwrap()
+            .add_adapter::<Celsius, Fahrenheit>(|c| c * 9.0 / 5.0 + 32.0)
+            .unwrap();
+        let mut graph = builder.build();
+        graph
+            .swap_task::<Adapt<Celsius, Fahrenheit>>(Box::new(FnTask::new(
+                |input: AdaptFrom<Celsius>| AdaptTo::<Fahrenheit>(input.0),
+            )))
+            .unwrap();
+        graph.execute_all().unwrap();
+        assert_eq!(graph.db().get::<Fahrenheit>(), Some(&0.0));
+
+        graph.restore_task::<Adapt<Celsius, Fahrenheit>>().unwrap();
+        graph.execute_all().unwrap();
+        assert_eq!(graph.db().get::<Fahrenheit>(), Some(&32.0));
+    }
+}
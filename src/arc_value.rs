@@ -0,0 +1,94 @@
+use std::{marker::PhantomData, sync::Arc};
+
+use crate::DbKey;
+
+/// The key of `K`'s value behind an [`Arc`], used by
+/// [`DataBase::get_shared`](crate::DataBase::get_shared) and
+/// [`DataBase::put_shared`](crate::DataBase::put_shared).
+///
+/// Reading an `ArcValue` clones the `Arc`, not the value, so large
+/// immutable outputs can be read by many tasks without deep copies. It is a
+/// key of its own: tasks depend on `ArcValue<K>`, not on `K`.
+pub struct ArcValue<K>(PhantomData<fn() -> K>);
+
+impl<K: DbKey> DbKey for ArcValue<K> {
+    type Value = Arc<K::Value>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        DataBase, ExecutionGraphBuilder, InMemoryDb, KeyType, Task, TaskInput, TaskOutput,
+    };
+
+    struct Corpus;
+
+    impl DbKey for Corpus {
+        type Value = Vec<String>;
+    }
+
+    struct CorpusIn(Arc<Vec<String>>);
+
+    impl DbKey for CorpusIn {
+        type Value = CorpusIn;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for CorpusIn {
+        fn from_db(db: &Db) -> Self {
+            CorpusIn(db.get_shared::<Corpus>().unwrap())
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<ArcValue<Corpus>>()]
+        }
+    }
+
+    struct Seen;
+
+    impl DbKey for Seen {
+        type Value = Arc<Vec<String>>;
+    }
+
+    struct SeenOut(Arc<Vec<String>>);
+
+    impl DbKey for SeenOut {
+        type Value = SeenOut;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for SeenOut {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<Seen>(self.0.clone());
+        }
+
+        fn out_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Seen>()]
+        }
+    }
+
+    struct Inspect;
+
+    impl Task<InMemoryDb> for Inspect {
+        type Input = CorpusIn;
+        type Output = SeenOut;
+
+        fn execute(input: Self::Input) -> Self::Output {
+            SeenOut(input.0)
+        }
+    }
+
+    #[test]
+    fn test_shared_values_are_not_cloned() {
+        let corpus = Arc::new(vec!["a".to_string(); 1000]);
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_input::<ArcValue<Corpus>>(corpus.clone())
+            .unwrap()
+            .add_task::<Inspect>()
+            .unwrap();
+        let mut graph = builder.build();
+        graph.execute_all().unwrap();
+        assert!(Arc::ptr_eq(graph.db().get::<Seen>().unwrap(), &corpus));
+        assert!(graph.db().get::<Corpus>().is_none());
+    }
+}
@@ -0,0 +1,58 @@
+use std::{env, fs, process::ExitCode};
+
+use computation_graph::describe::GraphDescription;
+
+const USAGE: &str = "usage: cg <command> <description-file>
+
+commands:
+  topology   print every node with the nodes it feeds
+  roots      print nodes without incoming edges
+  leaves     print nodes without outgoing edges
+  summary    print node and edge counts";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let [command, path] = args.as_slice() else {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    };
+
+    let desc = match fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|s| s.parse::<GraphDescription>().map_err(|e| e.to_string()))
+    {
+        Ok(desc) => desc,
+        Err(e) => {
+            eprintln!("cg: {path}: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match command.as_str() {
+        "topology" => {
+            for (i, name) in desc.nodes.iter().enumerate() {
+                let feeds: Vec<String> = desc.successors(i).map(|n| n.to_string()).collect();
+                println!("{i:>4} {name} -> [{}]", feeds.join(", "));
+            }
+        }
+        "roots" => print_nodes(&desc, desc.roots()),
+        "leaves" => print_nodes(&desc, desc.leaves()),
+        "summary" => {
+            println!("nodes: {}", desc.nodes.len());
+            println!("edges: {}", desc.edges.len());
+            println!("roots: {}", desc.roots().len());
+            println!("leaves: {}", desc.leaves().len());
+        }
+        _ => {
+            eprintln!("cg: unknown command `{command}`\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+fn print_nodes(desc: &GraphDescription, nodes: Vec<usize>) {
+    for i in nodes {
+        println!("{i:>4} {}", desc.nodes[i]);
+    }
+}
@@ -0,0 +1,229 @@
+use std::{
+    any::{type_name, Any, TypeId},
+    marker::PhantomData,
+    rc::Rc,
+};
+
+use crate::{
+    swap::DynTask, DataBase, DbKey, ExecutionGraphBuilder, KeyType, MissingDependency, Task,
+    TaskInput, TaskOutput,
+};
+
+/// The input of a [`BorrowingTask`], made of references into the database
+/// that live as long as `'db`.
+pub trait BorrowedTaskInput<'db, Db: DataBase>: Sized {
+    fn from_db(db: &'db Db) -> Self;
+    fn dep_types() -> Vec<KeyType> {
+        vec![]
+    }
+}
+
+/// A reference to the value of `K`.
+pub struct Ref<'db, K: DbKey>(pub &'db K::Value);
+
+impl<'db, Db: DataBase, K: DbKey> BorrowedTaskInput<'db, Db> for Ref<'db, K> {
+    fn from_db(db: &'db Db) -> Self {
+        Ref(db.get::<K>().unwrap())
+    }
+
+    fn dep_types() -> Vec<KeyType> {
+        vec![KeyType::of::<K>()]
+    }
+}
+
+impl<'db, Db: DataBase, A, B> BorrowedTaskInput<'db, Db> for (A, B)
+where
+    A: BorrowedTaskInput<'db, Db>,
+    B: BorrowedTaskInput<'db, Db>,
+{
+    fn from_db(db: &'db Db) -> Self {
+        (A::from_db(db), B::from_db(db))
+    }
+
+    fn dep_types() -> Vec<KeyType> {
+        let mut deps = A::dep_types();
+        deps.extend(B::dep_types());
+        deps
+    }
+}
+
+impl<'db, Db: DataBase, A, B, C> BorrowedTaskInput<'db, Db> for (A, B, C)
+where
+    A: BorrowedTaskInput<'db, Db>,
+    B: BorrowedTaskInput<'db, Db>,
+    C: BorrowedTaskInput<'db, Db>,
+{
+    fn from_db(db: &'db Db) -> Self {
+        (A::from_db(db), B::from_db(db), C::from_db(db))
+    }
+
+    fn dep_types() -> Vec<KeyType> {
+        let mut deps = A::dep_types();
+        deps.extend(B::dep_types());
+        deps.extend(C::dep_types());
+        deps
+    }
+}
+
+/// A task that reads its dependencies by reference instead of cloning them
+/// out of the database, added with
+/// [`ExecutionGraphBuilder::add_borrowing_task`]. The references cannot
+/// outlive `execute`, so the database is only written once they are gone.
+pub trait BorrowingTask<Db: DataBase>: 'static {
+    type Input<'db>: BorrowedTaskInput<'db, Db>
+    where
+        Db: 'db;
+    type Output: TaskOutput<Db>;
+
+    fn execute(input: Self::Input<'_>) -> Self::Output;
+}
+
+/// The task registered in the graph for a [`BorrowingTask`] `T`.
+pub struct Borrowing<T>(PhantomData<fn() -> T>);
+
+/// The dependencies of a [`BorrowingTask`] `T`, which are only read when it
+/// runs.
+pub struct BorrowedDeps<T>(PhantomData<fn() -> T>);
+
+impl<T: 'static> DbKey for BorrowedDeps<T> {
+    type Value = BorrowedDeps<T>;
+}
+
+impl<Db: DataBase, T: BorrowingTask<Db>> TaskInput<Db> for BorrowedDeps<T> {
+    fn from_db(_db: &Db) -> Self {
+        BorrowedDeps(PhantomData)
+    }
+
+    fn dep_types() -> Vec<KeyType> {
+        dep_types::<Db, T>()
+    }
+}
+
+fn dep_types<Db: DataBase, T: BorrowingTask<Db>>() -> Vec<KeyType> {
+    fn of<'db, Db: DataBase + 'db, T: BorrowingTask<Db>>(
+        _db: PhantomData<&'db Db>,
+    ) -> Vec<KeyType> {
+        <T::Input<'db> as BorrowedTaskInput<'db, Db>>::dep_types()
+    }
+    of::<Db, T>(PhantomData)
+}
+
+impl<Db: DataBase, T: BorrowingTask<Db>> Task<Db> for Borrowing<T> {
+    type Input = BorrowedDeps<T>;
+    type Output = T::Output;
+
+    fn execute(_input: Self::Input) -> Self::Output {
+        // `add_borrowing_task` installs the borrowing runner as the task's
+        // runner.
+        unreachable!("borrowing tasks only run through add_borrowing_task")
+    }
+
+    fn name() -> &'static str {
+        crate::short_type_name(type_name::<T>())
+    }
+}
+
+struct Runner<T>(PhantomData<fn() -> T>);
+
+impl<Db: DataBase, T: BorrowingTask<Db>> DynTask<Db> for Runner<T> {
+    fn execute(&self, db: &Db) -> Box<dyn Any> {
+        Box::new(T::execute(T::Input::from_db(db)))
+    }
+
+    fn output_type(&self) -> TypeId {
+        TypeId::of::<T::Output>()
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraphBuilder<Db, Ctx> {
+    /// Adds the [`BorrowingTask`] `T`, registered as [`Borrowing<T>`].
+    pub fn add_borrowing_task<T: BorrowingTask<Db>>(
+        &mut self,
+    ) -> Result<&mut Self, MissingDependency> {
+        self.add_task::<Borrowing<T>>()?;
+        self.graph.runners.insert(
+            TypeId::of::<Borrowing<T>>(),
+            Rc::new(Runner::<T>(PhantomData)),
+        );
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemoryDb;
+
+    struct Document;
+
+    impl DbKey for Document {
+        type Value = String;
+    }
+
+    struct Needle;
+
+    impl DbKey for Needle {
+        type Value = String;
+    }
+
+    struct Matches;
+
+    impl DbKey for Matches {
+        type Value = usize;
+    }
+
+    struct MatchesOut(usize);
+
+    impl DbKey for MatchesOut {
+        type Value = MatchesOut;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for MatchesOut {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<Matches>(self.0);
+        }
+
+        fn out_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Matches>()]
+        }
+    }
+
+    struct Count;
+
+    impl BorrowingTask<InMemoryDb> for Count {
+        type Input<'db> = (Ref<'db, Document>, Ref<'db, Needle>);
+        type Output = MatchesOut;
+
+        fn execute((document, needle): Self::Input<'_>) -> Self::Output {
+            MatchesOut(document.0.matches(needle.0.as_str()).count())
+        }
+    }
+
+    #[test]
+    fn test_borrowing_task() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_input::<Document>("to be or not to be".to_string())
+            .unwrap()
+            .add_input::<Needle>("be".to_string())
+            .unwrap()
+            .add_borrowing_task::<Count>()
+            .unwrap();
+        let mut graph = builder.build();
+        graph.execute_all().unwrap();
+        assert_eq!(graph.db().get::<Matches>(), Some(&2));
+        assert!(graph.task_by_name("Count").is_some());
+
+        graph.set_input::<Needle>("o".to_string()).unwrap();
+        graph.execute::<Borrowing<Count>>().unwrap();
+        assert_eq!(graph.db().get::<Matches>(), Some(&4));
+    }
+
+    #[test]
+    fn test_borrowing_task_missing_dependency() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder.add_input::<Document>(String::new()).unwrap();
+        let err = builder.add_borrowing_task::<Count>().err().unwrap();
+        assert_eq!(err.key, type_name::<Needle>());
+    }
+}
@@ -0,0 +1,397 @@
+use std::{any::TypeId, collections::HashMap, rc::Rc, time::Duration};
+
+use crate::{
+    retention::Evictable, DataBase, DbKey, ExecutionGraph, ExecutionGraphBuilder, KeyType,
+    TaskInput, TaskWithContext, ValueId,
+};
+
+/// What the graph knows about a stored value that may be evicted.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct NodeStats {
+    pub key: ValueId,
+    /// Estimated size of the value.
+    pub bytes: usize,
+    /// When the value was last read or written by a task. Larger is more
+    /// recent; only the order is meaningful.
+    pub last_used: u64,
+    /// How long the task writing the value took the last time it ran.
+    pub cost: Duration,
+}
+
+/// Decides which values to drop once the graph holds more than its
+/// [`max_resident_bytes`](ExecutionGraphBuilder::max_resident_bytes), set
+/// with [`ExecutionGraphBuilder::eviction_policy`].
+pub trait EvictionPolicy {
+    /// Chooses among `candidates` the values to evict to free `needed`
+    /// bytes. Keys that are not candidates are ignored, and choosing too
+    /// little leaves the graph over budget until the next task runs.
+    fn choose_victims(&self, candidates: &[NodeStats], needed: usize) -> Vec<ValueId>;
+}
+
+/// The built-in eviction policies, which evict values in order until enough
+/// bytes are freed.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum EvictionOrder {
+    /// Values last read or written longest ago first.
+    #[default]
+    LeastRecentlyUsed,
+    /// Largest values first.
+    LargestFirst,
+    /// Values whose writing task ran fastest last time first.
+    CheapestFirst,
+}
+
+/// A value dropped by the graph, reported to observers registered with
+/// [`ExecutionGraphBuilder::on_eviction`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Eviction {
+    pub key: KeyType,
+    /// Estimated size of the dropped value.
+    pub bytes: usize,
+}
+
+impl EvictionPolicy for EvictionOrder {
+    fn choose_victims(&self, candidates: &[NodeStats], needed: usize) -> Vec<ValueId> {
+        let mut candidates = candidates.to_vec();
+        match self {
+            EvictionOrder::LeastRecentlyUsed => candidates.sort_by_key(|c| c.last_used),
+            EvictionOrder::LargestFirst => candidates.sort_by_key(|c| std::cmp::Reverse(c.bytes)),
+            EvictionOrder::CheapestFirst => candidates.sort_by_key(|c| c.cost),
+        }
+        let mut freed = 0;
+        let mut victims = Vec::new();
+        for candidate in candidates {
+            if freed >= needed {
+                break;
+            }
+            freed += candidate.bytes;
+            victims.push(candidate.key);
+        }
+        victims
+    }
+}
+
+type SizeFn<Db> = Rc<dyn Fn(&Db) -> Option<usize>>;
+type Observer = Rc<dyn Fn(&Eviction)>;
+
+pub(crate) struct MemoryBudget<Db> {
+    max_resident_bytes: Option<usize>,
+    policy: Rc<dyn EvictionPolicy>,
+    sizes: HashMap<TypeId, SizeFn<Db>>,
+    observers: Vec<Observer>,
+    last_used: HashMap<TypeId, u64>,
+    tick: u64,
+}
+
+impl<Db> MemoryBudget<Db> {
+    pub(crate) fn new() -> Self {
+        MemoryBudget {
+            max_resident_bytes: None,
+            policy: Rc::new(EvictionOrder::default()),
+            sizes: HashMap::new(),
+            observers: Vec::new(),
+            last_used: HashMap::new(),
+            tick: 0,
+        }
+    }
+}
+
+impl<Db> MemoryBudget<Db> {
+    pub(crate) fn forget(&mut self, key: TypeId) {
+        self.sizes.remove(&key);
+        self.last_used.remove(&key);
+    }
+
+    /// Copies the configuration, but not the usage history.
+    pub(crate) fn without_usage(&self) -> Self {
+        MemoryBudget {
+            max_resident_bytes: self.max_resident_bytes,
+            policy: self.policy.clone(),
+            sizes: self.sizes.clone(),
+            observers: self.observers.clone(),
+            last_used: HashMap::new(),
+            tick: 0,
+        }
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraphBuilder<Db, Ctx> {
+    /// After every task, evicts values marked
+    /// [`Evictable`](crate::retention::Retention::Evictable) until the
+    /// estimated size of the stored values is at most `bytes`.
+    pub fn max_resident_bytes(&mut self, bytes: usize) -> &mut Self {
+        self.graph.memory.max_resident_bytes = Some(bytes);
+        self
+    }
+
+    /// Sets the policy choosing which values to evict. Defaults to
+    /// [`EvictionOrder::LeastRecentlyUsed`].
+    pub fn eviction_policy(&mut self, policy: impl EvictionPolicy + 'static) -> &mut Self {
+        self.graph.memory.policy = Rc::new(policy);
+        self
+    }
+
+    /// Registers how to estimate the size of values of `K`, in bytes. Keys
+    /// with an estimate count towards the budget whether evictable or not.
+    /// Evictable keys without one count with their shallow size only.
+    pub fn estimate_size<K: DbKey>(
+        &mut self,
+        size: impl Fn(&K::Value) -> usize + 'static,
+    ) -> &mut Self {
+        self.graph.memory.sizes.insert(
+            TypeId::of::<K>(),
+            Rc::new(move |db| db.get::<K>().map(&size)),
+        );
+        self
+    }
+
+    /// Registers `observer` to be called with every value the graph evicts.
+    pub fn on_eviction(&mut self, observer: impl Fn(&Eviction) + 'static) -> &mut Self {
+        self.graph.memory.observers.push(Rc::new(observer));
+        self
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraph<Db, Ctx> {
+    /// Estimated size of the stored values of keys with a size estimate or
+    /// marked evictable.
+    pub fn resident_bytes(&self) -> usize {
+        self.with_db(|db| self.resident_bytes_in(db))
+    }
+
+    fn resident_bytes_in(&self, db: &Db) -> usize {
+        let estimated: usize = self.memory.sizes.values().filter_map(|size| size(db)).sum();
+        let shallow: usize = self
+            .evictable
+            .iter()
+            .filter(|e| !self.memory.sizes.contains_key(&e.key.id))
+            .filter_map(|e| (e.size)(db))
+            .sum();
+        estimated + shallow
+    }
+
+    pub(crate) fn size_of(&self, evictable: &Evictable<Db>) -> Option<usize> {
+        match self.memory.sizes.get(&evictable.key.id) {
+            Some(size) => size(&self.db),
+            None => (evictable.size)(&self.db),
+        }
+    }
+
+    pub(crate) fn notify_eviction(&self, key: KeyType, bytes: usize) {
+        let eviction = Eviction { key, bytes };
+        for observer in &self.memory.observers {
+            observer(&eviction);
+        }
+    }
+
+    /// Records that `T` read its dependencies and wrote its outputs.
+    pub(crate) fn touch<T: TaskWithContext<Db, Ctx>>(&mut self) {
+        self.memory.tick += 1;
+        let tick = self.memory.tick;
+        let used = T::Input::dep_types()
+            .into_iter()
+            .map(|key| key.id)
+            .chain(Self::written::<T>());
+        for id in used {
+            self.memory.last_used.insert(id, tick);
+        }
+    }
+
+    /// Evicts the values chosen by the eviction policy if the graph is over
+    /// its budget.
+    pub(crate) fn enforce_budget(&mut self) {
+        let Some(max) = self.memory.max_resident_bytes else {
+            return;
+        };
+        let resident = self.resident_bytes_in(&self.db);
+        if resident <= max {
+            return;
+        }
+        let candidates: Vec<NodeStats> = self
+            .evictable
+            .iter()
+            .filter_map(|evictable| {
+                let writer = &self.entries[self.writer_of(evictable.key)?];
+                Some(NodeStats {
+                    key: evictable.key,
+                    bytes: self.size_of(evictable)?,
+                    last_used: self
+                        .memory
+                        .last_used
+                        .get(&evictable.key.id)
+                        .copied()
+                        .unwrap_or(0),
+                    cost: self
+                        .records
+                        .get(&writer.id)
+                        .map_or(Duration::ZERO, |record| record.duration),
+                })
+            })
+            .collect();
+        let victims = self
+            .memory
+            .policy
+            .choose_victims(&candidates, resident - max);
+        for victim in victims {
+            if !candidates.iter().any(|c| c.key == victim) {
+                continue;
+            }
+            if let Some(evictable) = self.evictable.iter().find(|e| e.key == victim).cloned() {
+                self.evict_value(&evictable);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+    use crate::{retention::Retention, InMemoryDb, Task, TaskOutput};
+
+    struct Text;
+
+    impl DbKey for Text {
+        type Value = String;
+    }
+
+    macro_rules! stage {
+        ($task:ident, $out:ident, $key:ident, $len:expr) => {
+            struct $key;
+
+            impl DbKey for $key {
+                type Value = Vec<u8>;
+            }
+
+            struct $out(Vec<u8>);
+
+            impl DbKey for $out {
+                type Value = $out;
+            }
+
+            impl<Db: DataBase> TaskOutput<Db> for $out {
+                fn to_db(&self, db: &mut Db) {
+                    db.put::<$key>(self.0.clone());
+                }
+
+                fn out_types() -> Vec<KeyType> {
+                    vec![KeyType::of::<$key>()]
+                }
+            }
+
+            struct $task;
+
+            impl Task<InMemoryDb> for $task {
+                type Input = TextIn;
+                type Output = $out;
+
+                fn execute(_input: Self::Input) -> Self::Output {
+                    $out(vec![0; $len])
+                }
+            }
+        };
+    }
+
+    struct TextIn;
+
+    impl DbKey for TextIn {
+        type Value = TextIn;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for TextIn {
+        fn from_db(_db: &Db) -> Self {
+            TextIn
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Text>()]
+        }
+    }
+
+    stage!(Tokenize, TokensOut, Tokens, 100);
+    stage!(Index, IndexOut, Postings, 400);
+    stage!(Embed, EmbedOut, Embeddings, 300);
+
+    fn builder(order: EvictionOrder) -> ExecutionGraphBuilder<InMemoryDb> {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_input::<Text>("text".to_string())
+            .unwrap()
+            .add_task::<Tokenize>()
+            .unwrap()
+            .add_task::<Index>()
+            .unwrap()
+            .add_task::<Embed>()
+            .unwrap()
+            .retain::<Tokens>(Retention::Evictable)
+            .retain::<Postings>(Retention::Evictable)
+            .retain::<Embeddings>(Retention::Evictable)
+            .estimate_size::<Tokens>(Vec::len)
+            .estimate_size::<Postings>(Vec::len)
+            .estimate_size::<Embeddings>(Vec::len)
+            .max_resident_bytes(500)
+            .eviction_policy(order);
+        builder
+    }
+
+    #[test]
+    fn test_least_recently_used_is_evicted() {
+        let evictions = Rc::new(RefCell::new(Vec::new()));
+        let mut builder = builder(EvictionOrder::LeastRecentlyUsed);
+        let log = evictions.clone();
+        builder.on_eviction(move |eviction| log.borrow_mut().push(*eviction));
+        let mut graph = builder.build();
+        graph.execute_all().unwrap();
+
+        assert_eq!(
+            *evictions.borrow(),
+            vec![
+                Eviction {
+                    key: KeyType::of::<Tokens>(),
+                    bytes: 100,
+                },
+                Eviction {
+                    key: KeyType::of::<Postings>(),
+                    bytes: 400,
+                },
+            ]
+        );
+        assert_eq!(graph.resident_bytes(), 300);
+    }
+
+    #[test]
+    fn test_largest_is_evicted() {
+        let mut graph = builder(EvictionOrder::LargestFirst).build();
+        graph.execute_all().unwrap();
+        assert!(graph.is_evicted::<Postings>());
+        assert!(!graph.is_evicted::<Tokens>());
+        assert_eq!(graph.resident_bytes(), 400);
+    }
+
+    /// Never evicts the postings, which serve interactive queries.
+    struct KeepPostings;
+
+    impl EvictionPolicy for KeepPostings {
+        fn choose_victims(&self, candidates: &[NodeStats], needed: usize) -> Vec<ValueId> {
+            let candidates: Vec<NodeStats> = candidates
+                .iter()
+                .filter(|c| c.key != KeyType::of::<Postings>())
+                .copied()
+                .collect();
+            EvictionOrder::LargestFirst.choose_victims(&candidates, needed)
+        }
+    }
+
+    #[test]
+    fn test_custom_policy() {
+        let mut builder = builder(EvictionOrder::LargestFirst);
+        builder.eviction_policy(KeepPostings);
+        let mut graph = builder.build();
+        graph.execute_all().unwrap();
+        assert!(!graph.is_evicted::<Postings>());
+        assert!(graph.is_evicted::<Embeddings>());
+        assert!(!graph.is_evicted::<Tokens>());
+        assert_eq!(graph.resident_bytes(), 500);
+    }
+}
@@ -0,0 +1,289 @@
+use std::{
+    any::{Any, TypeId},
+    cell::OnceCell,
+    collections::HashMap,
+};
+
+use crate::{fingerprint::Fingerprint, DataBase, DbKey, InMemoryDb, KeyType};
+
+type Encode = Box<dyn Fn(&dyn Any) -> Vec<u8>>;
+type Decode = Box<dyn Fn(&[u8]) -> Box<dyn Any>>;
+
+struct Codec {
+    encode: Encode,
+    decode: Decode,
+}
+
+struct Blob {
+    bytes: Box<[u8]>,
+    refs: usize,
+}
+
+struct Stored {
+    hash: u64,
+    loaded: OnceCell<Box<dyn Any>>,
+}
+
+/// A database that stores values of keys registered with
+/// [`CasDb::content_addressed`] as blobs named by the hash of their encoding,
+/// with each key pointing at a blob. Keys holding equal values, whichever
+/// task or run wrote them, share one blob.
+///
+/// Values are decoded from their blob when first read, and the decoded copy
+/// kept until the key is written or removed. Values of other keys are kept
+/// in memory as they are.
+pub struct CasDb {
+    memory: InMemoryDb,
+    stored: HashMap<TypeId, Stored>,
+    blobs: HashMap<u64, Blob>,
+    codecs: HashMap<TypeId, Codec>,
+}
+
+impl CasDb {
+    pub fn new() -> Self {
+        CasDb {
+            memory: InMemoryDb::new(),
+            stored: HashMap::new(),
+            blobs: HashMap::new(),
+            codecs: HashMap::new(),
+        }
+    }
+
+    /// Stores values of `K` by content, using `encode` and `decode` to turn
+    /// them into blobs and back.
+    pub fn content_addressed<K: DbKey>(
+        mut self,
+        encode: fn(&K::Value) -> Vec<u8>,
+        decode: fn(&[u8]) -> K::Value,
+    ) -> Self {
+        self.codecs.insert(
+            TypeId::of::<K>(),
+            Codec {
+                encode: Box::new(move |value| encode(value.downcast_ref::<K::Value>().unwrap())),
+                decode: Box::new(move |bytes| Box::new(decode(bytes))),
+            },
+        );
+        self
+    }
+
+    /// The hash of the blob holding the value of `K`, if `K` is stored by
+    /// content.
+    pub fn hash_of<K: DbKey>(&self) -> Option<u64> {
+        self.stored
+            .get(&TypeId::of::<K>())
+            .map(|stored| stored.hash)
+    }
+
+    /// The encoded value named `hash`.
+    pub fn blob(&self, hash: u64) -> Option<&[u8]> {
+        self.blobs.get(&hash).map(|blob| &*blob.bytes)
+    }
+
+    /// Number of distinct blobs stored.
+    pub fn blob_count(&self) -> usize {
+        self.blobs.len()
+    }
+
+    /// Stores `bytes`, or takes another reference to an equal blob, and
+    /// returns its hash. Distinct blobs whose hashes collide take the next
+    /// free hash.
+    fn intern(&mut self, bytes: Vec<u8>) -> u64 {
+        let mut hash = bytes.fingerprint();
+        loop {
+            match self.blobs.get_mut(&hash) {
+                Some(blob) if *blob.bytes == *bytes => {
+                    blob.refs += 1;
+                    return hash;
+                }
+                Some(_) => hash = hash.wrapping_add(1),
+                None => {
+                    let bytes = bytes.into();
+                    self.blobs.insert(hash, Blob { bytes, refs: 1 });
+                    return hash;
+                }
+            }
+        }
+    }
+
+    fn release(&mut self, hash: u64) {
+        let blob = self.blobs.get_mut(&hash).unwrap();
+        blob.refs -= 1;
+        if blob.refs == 0 {
+            self.blobs.remove(&hash);
+        }
+    }
+
+    fn take<K: DbKey>(&mut self) -> Option<K::Value> {
+        let value = self.take_key(KeyType::of::<K>())?;
+        value.downcast::<K::Value>().ok().map(|v| *v)
+    }
+
+    fn take_key(&mut self, key: KeyType) -> Option<Box<dyn Any>> {
+        let Some(mut stored) = self.stored.remove(&key.id) else {
+            return self.memory.remove_key(key);
+        };
+        let value = match stored.loaded.take() {
+            Some(value) => value,
+            None => (self.codecs[&key.id].decode)(&self.blobs[&stored.hash].bytes),
+        };
+        self.release(stored.hash);
+        Some(value)
+    }
+}
+
+impl Default for CasDb {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DataBase for CasDb {
+    fn get<K: DbKey>(&self) -> Option<&K::Value> {
+        let id = TypeId::of::<K>();
+        match self.stored.get(&id) {
+            Some(stored) => stored
+                .loaded
+                .get_or_init(|| (self.codecs[&id].decode)(&self.blobs[&stored.hash].bytes))
+                .downcast_ref::<K::Value>(),
+            None => self.memory.get::<K>(),
+        }
+    }
+
+    fn put<K: DbKey>(&mut self, value: K::Value) -> Option<K::Value> {
+        let previous = self.take::<K>();
+        let id = TypeId::of::<K>();
+        match self.codecs.get(&id) {
+            Some(codec) => {
+                let bytes = (codec.encode)(&value);
+                let hash = self.intern(bytes);
+                self.stored.insert(
+                    id,
+                    Stored {
+                        hash,
+                        loaded: OnceCell::new(),
+                    },
+                );
+            }
+            None => {
+                self.memory.put::<K>(value);
+            }
+        }
+        previous
+    }
+
+    fn remove<K: DbKey>(&mut self) -> Option<K::Value> {
+        self.take::<K>()
+    }
+
+    fn contains(&self, key: KeyType) -> Option<bool> {
+        match self.stored.contains_key(&key.id) {
+            true => Some(true),
+            false => self.memory.contains(key),
+        }
+    }
+
+    fn remove_key(&mut self, key: KeyType) -> Option<Box<dyn Any>> {
+        self.take_key(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExecutionGraphBuilder, KeyType, Task, TaskInput, TaskOutput};
+
+    struct Source;
+
+    impl DbKey for Source {
+        type Value = String;
+    }
+
+    struct Formatted;
+
+    impl DbKey for Formatted {
+        type Value = String;
+    }
+
+    struct SourceIn(String);
+
+    impl DbKey for SourceIn {
+        type Value = SourceIn;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for SourceIn {
+        fn from_db(db: &Db) -> Self {
+            SourceIn(db.get::<Source>().unwrap().clone())
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Source>()]
+        }
+    }
+
+    struct FormattedOut(String);
+
+    impl DbKey for FormattedOut {
+        type Value = FormattedOut;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for FormattedOut {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<Formatted>(self.0.clone());
+        }
+
+        fn out_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Formatted>()]
+        }
+    }
+
+    struct Format;
+
+    impl Task<CasDb> for Format {
+        type Input = SourceIn;
+        type Output = FormattedOut;
+
+        fn execute(input: Self::Input) -> Self::Output {
+            FormattedOut(input.0.trim().to_string())
+        }
+    }
+
+    fn encode(s: &String) -> Vec<u8> {
+        s.as_bytes().to_vec()
+    }
+
+    fn decode(bytes: &[u8]) -> String {
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn test_equal_values_share_a_blob() {
+        let db = CasDb::new()
+            .content_addressed::<Source>(encode, decode)
+            .content_addressed::<Formatted>(encode, decode);
+        let mut builder = ExecutionGraphBuilder::new(db);
+        builder
+            .add_input::<Source>("fn main() {}".to_string())
+            .unwrap()
+            .add_task::<Format>()
+            .unwrap();
+        let mut graph = builder.build();
+        graph.execute_all().unwrap();
+
+        let db = graph.db();
+        assert_eq!(
+            db.get::<Formatted>().map(String::as_str),
+            Some("fn main() {}")
+        );
+        assert_eq!(db.hash_of::<Source>(), db.hash_of::<Formatted>());
+        assert_eq!(db.blob_count(), 1);
+        let hash = db.hash_of::<Source>().unwrap();
+        assert_eq!(db.blob(hash), Some(&b"fn main() {}"[..]));
+
+        graph
+            .set_input::<Source>(" fn main() {}\n".to_string())
+            .unwrap();
+        assert_eq!(graph.db().blob_count(), 2);
+        graph.execute_all().unwrap();
+        assert_eq!(graph.db().blob_count(), 2);
+    }
+}
@@ -0,0 +1,162 @@
+use std::{
+    cell::Cell,
+    rc::Rc,
+    time::{Duration, SystemTime},
+};
+
+use crate::{DataBase, DbKey, ExecutionGraph, ExecutionGraphBuilder, KeyType};
+
+pub trait Clock: 'static {
+    fn now(&self) -> SystemTime;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// A clock that only moves when told to. Clones share the same time, so a test
+/// can keep one and hand the other to the graph.
+#[derive(Clone)]
+pub struct MockClock {
+    now: Rc<Cell<SystemTime>>,
+}
+
+impl MockClock {
+    pub fn new(now: SystemTime) -> Self {
+        MockClock {
+            now: Rc::new(Cell::new(now)),
+        }
+    }
+
+    pub fn set(&self, now: SystemTime) {
+        self.now.set(now);
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.now.set(self.now.get() + by);
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> SystemTime {
+        self.now.get()
+    }
+}
+
+/// The time at the start of the current run, refreshed by
+/// [`ExecutionGraph::execute_all`] when the graph has a clock, and by
+/// [`ExecutionGraph::execute`] outside of a run.
+pub struct NowKey;
+
+impl DbKey for NowKey {
+    type Value = SystemTime;
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraphBuilder<Db, Ctx> {
+    /// Installs `clock` and registers [`NowKey`] as an input, so tasks can
+    /// depend on the current time.
+    pub fn with_clock(&mut self, clock: impl Clock) -> &mut Self {
+        if self
+            .graph
+            .contains_node(&KeyType::of::<NowKey>().id)
+            .is_none()
+        {
+            self.graph.register_input::<NowKey>("with_clock");
+        }
+        self.graph.clock = Some(Rc::new(clock));
+        self.graph.with_shared_db(|graph| graph.refresh_now());
+        self
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraph<Db, Ctx> {
+    pub(crate) fn refresh_now(&mut self) {
+        if let Some(clock) = &self.clock {
+            self.db.put::<NowKey>(clock.now());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemoryDb, Task, TaskInput, TaskOutput};
+
+    struct Elapsed(Duration);
+
+    impl DbKey for Elapsed {
+        type Value = Elapsed;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for Elapsed {
+        fn from_db(db: &Db) -> Self {
+            let now = *db.get::<NowKey>().unwrap();
+            Elapsed(now.duration_since(SystemTime::UNIX_EPOCH).unwrap())
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<NowKey>()]
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Age(u64);
+
+    impl DbKey for Age {
+        type Value = Age;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for Age {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<Age>(Age(self.0));
+        }
+    }
+
+    struct ComputeAge;
+
+    impl Task<InMemoryDb> for ComputeAge {
+        type Input = Elapsed;
+        type Output = Age;
+
+        fn execute(input: Self::Input) -> Self::Output {
+            Age(input.0.as_secs())
+        }
+    }
+
+    #[test]
+    fn test_now_refreshed_per_run() {
+        let clock = MockClock::new(SystemTime::UNIX_EPOCH + Duration::from_secs(10));
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .with_clock(clock.clone())
+            .add_task::<ComputeAge>()
+            .unwrap();
+        let mut graph = builder.build();
+
+        graph.execute_all().unwrap();
+        assert_eq!(graph.db().get::<Age>(), Some(&Age(10)));
+
+        clock.advance(Duration::from_secs(5));
+        graph.execute_all().unwrap();
+        assert_eq!(graph.db().get::<Age>(), Some(&Age(15)));
+    }
+
+    #[test]
+    fn test_now_set_outside_runs() {
+        let clock = MockClock::new(SystemTime::UNIX_EPOCH + Duration::from_secs(10));
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .with_clock(clock.clone())
+            .add_task::<ComputeAge>()
+            .unwrap();
+        let mut graph = builder.build();
+        assert_eq!(graph.execute::<ComputeAge>().unwrap(), Age(10));
+
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(graph.execute::<ComputeAge>().unwrap(), Age(15));
+    }
+}
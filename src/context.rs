@@ -0,0 +1,120 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    ops::Deref,
+};
+
+use crate::{idempotency::SideEffects, spawn::Spawner, DataBase, ExecutionGraph, TaskWithContext};
+
+/// What a [`TaskWithContext`] sees besides its input: the graph's shared
+/// context (through `Deref`), the task's scratch space, the queue of
+/// sub-tasks it [spawns](Self::spawn) and the
+/// [side effects](Self::side_effect) it committed.
+pub struct TaskContext<'a, Ctx> {
+    pub(crate) ctx: &'a Ctx,
+    pub(crate) scratch: &'a mut Scratch,
+    pub(crate) spawner: &'a mut Spawner,
+    pub(crate) side_effects: &'a mut SideEffects,
+}
+
+impl<Ctx> TaskContext<'_, Ctx> {
+    pub fn context(&self) -> &Ctx {
+        self.ctx
+    }
+
+    pub fn scratch(&mut self) -> &mut Scratch {
+        self.scratch
+    }
+}
+
+impl<Ctx> Deref for TaskContext<'_, Ctx> {
+    type Target = Ctx;
+
+    fn deref(&self) -> &Ctx {
+        self.ctx
+    }
+}
+
+/// Per-task storage that survives across executions of the same task, for
+/// things like compiled regexes or warmed connections that do not belong in
+/// the shared database. Values are stored by type.
+#[derive(Default)]
+pub struct Scratch {
+    values: HashMap<TypeId, Box<dyn Any>>,
+}
+
+impl Scratch {
+    pub fn get<S: 'static>(&self) -> Option<&S> {
+        self.values
+            .get(&TypeId::of::<S>())
+            .and_then(|v| v.downcast_ref::<S>())
+    }
+
+    pub fn get_or_insert_with<S: 'static>(&mut self, init: impl FnOnce() -> S) -> &mut S {
+        self.values
+            .entry(TypeId::of::<S>())
+            .or_insert_with(|| Box::new(init()))
+            .downcast_mut::<S>()
+            .unwrap()
+    }
+
+    pub fn get_or_default<S: Default + 'static>(&mut self) -> &mut S {
+        self.get_or_insert_with(S::default)
+    }
+
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraph<Db, Ctx> {
+    /// Drops everything `T` stored in its scratch space.
+    pub fn clear_scratch<T: TaskWithContext<Db, Ctx>>(&mut self) -> &mut Self {
+        self.scratch.remove(&TypeId::of::<T>());
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DbKey, ExecutionGraphBuilder, InMemoryDb, TaskOutput};
+
+    #[derive(Debug, PartialEq)]
+    struct Calls(u32);
+
+    impl DbKey for Calls {
+        type Value = Calls;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for Calls {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<Calls>(Calls(self.0));
+        }
+    }
+
+    struct CountCalls;
+
+    impl TaskWithContext<InMemoryDb, ()> for CountCalls {
+        type Input = ();
+        type Output = Calls;
+
+        fn execute(_input: Self::Input, ctx: &mut TaskContext<'_, ()>) -> Self::Output {
+            let calls = ctx.scratch().get_or_default::<u32>();
+            *calls += 1;
+            Calls(*calls)
+        }
+    }
+
+    #[test]
+    fn test_scratch_survives_reexecution() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder.add_task::<CountCalls>().unwrap();
+        let mut graph = builder.build();
+        assert_eq!(graph.execute::<CountCalls>().unwrap(), Calls(1));
+        assert_eq!(graph.execute::<CountCalls>().unwrap(), Calls(2));
+
+        graph.clear_scratch::<CountCalls>();
+        assert_eq!(graph.execute::<CountCalls>().unwrap(), Calls(1));
+    }
+}
@@ -0,0 +1,219 @@
+use std::time::Duration;
+
+use crate::{DataBase, ExecutionGraph, TaskId};
+
+/// The chain of tasks bounding the runtime of a graph, returned by
+/// [`ExecutionGraph::critical_path`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CriticalPath {
+    /// The tasks of the path, each reading an output of the one before.
+    pub tasks: Vec<TaskId>,
+    /// The sum of the costs of `tasks`, the shortest a run could take with
+    /// unlimited parallelism.
+    pub length: Duration,
+    /// For every task of the graph, in the order it was added, how much
+    /// longer it could take without lengthening the path. Zero on the path.
+    pub slack: Vec<(TaskId, Duration)>,
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraph<Db, Ctx> {
+    /// The critical path by the durations of the tasks' last runs. Tasks that
+    /// never ran cost nothing.
+    pub fn critical_path(&self) -> CriticalPath {
+        self.critical_path_with(|task| self.records.get(&task.id).map(|record| record.duration))
+    }
+
+    /// The critical path by the costs `cost` estimates, e.g. from task
+    /// annotations. Tasks without an estimate cost nothing.
+    pub fn critical_path_with(&self, cost: impl Fn(TaskId) -> Option<Duration>) -> CriticalPath {
+        let Schedule {
+            costs,
+            preds,
+            finish,
+            via,
+        } = self.schedule(cost);
+        let length = finish.iter().copied().max().unwrap_or_default();
+
+        let mut latest = vec![length; costs.len()];
+        for i in (0..costs.len()).rev() {
+            for &p in &preds[i] {
+                latest[p] = latest[p].min(latest[i].saturating_sub(costs[i]));
+            }
+        }
+
+        let mut tasks = Vec::new();
+        let mut at = (0..costs.len()).max_by_key(|&i| finish[i]);
+        while let Some(i) = at {
+            tasks.push(self.entries[i].task_id());
+            at = via[i];
+        }
+        tasks.reverse();
+        CriticalPath {
+            tasks,
+            length,
+            slack: self
+                .entries
+                .iter()
+                .enumerate()
+                .map(|(i, entry)| (entry.task_id(), latest[i].saturating_sub(finish[i])))
+                .collect(),
+        }
+    }
+
+    /// When each task would finish if it started as soon as the tasks it
+    /// reads from finished, with unlimited parallelism.
+    pub(crate) fn schedule(&self, cost: impl Fn(TaskId) -> Option<Duration>) -> Schedule {
+        let costs: Vec<Duration> = self
+            .entries
+            .iter()
+            .map(|entry| cost(entry.task_id()).unwrap_or_default())
+            .collect();
+        // Tasks only read outputs of tasks added before them, except through
+        // lazy edges, which do not order a run.
+        let preds: Vec<Vec<usize>> = self
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                let mut preds: Vec<usize> = entry
+                    .deps
+                    .iter()
+                    .filter_map(|key| self.writer_of(*key))
+                    .filter(|&p| p < i)
+                    .collect();
+                preds.sort_unstable();
+                preds.dedup();
+                preds
+            })
+            .collect();
+
+        let mut finish = vec![Duration::ZERO; costs.len()];
+        let mut via = vec![None; costs.len()];
+        for i in 0..costs.len() {
+            let before = preds[i].iter().copied().max_by_key(|&p| finish[p]);
+            via[i] = before;
+            finish[i] = before.map_or(Duration::ZERO, |p| finish[p]) + costs[i];
+        }
+        Schedule {
+            costs,
+            preds,
+            finish,
+            via,
+        }
+    }
+}
+
+/// Per task, by position in the graph's entries.
+pub(crate) struct Schedule {
+    pub(crate) costs: Vec<Duration>,
+    pub(crate) preds: Vec<Vec<usize>>,
+    pub(crate) finish: Vec<Duration>,
+    /// The predecessor finishing last, which the task waits for.
+    pub(crate) via: Vec<Option<usize>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DbKey, ExecutionGraphBuilder, InMemoryDb, KeyType, Task, TaskInput, TaskOutput};
+
+    macro_rules! key {
+        ($name:ident) => {
+            struct $name;
+
+            impl DbKey for $name {
+                type Value = $name;
+            }
+
+            impl<Db: DataBase> TaskOutput<Db> for $name {
+                fn to_db(&self, db: &mut Db) {
+                    db.put::<$name>($name);
+                }
+            }
+        };
+    }
+
+    key!(Parsed);
+    key!(Config);
+    key!(Built);
+
+    struct BuildIn;
+
+    impl DbKey for BuildIn {
+        type Value = BuildIn;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for BuildIn {
+        fn from_db(_db: &Db) -> Self {
+            BuildIn
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Parsed>(), KeyType::of::<Config>()]
+        }
+    }
+
+    struct Parse;
+
+    impl Task<InMemoryDb> for Parse {
+        type Input = ();
+        type Output = Parsed;
+
+        fn execute(_input: Self::Input) -> Self::Output {
+            Parsed
+        }
+    }
+
+    struct Configure;
+
+    impl Task<InMemoryDb> for Configure {
+        type Input = ();
+        type Output = Config;
+
+        fn execute(_input: Self::Input) -> Self::Output {
+            Config
+        }
+    }
+
+    struct Build;
+
+    impl Task<InMemoryDb> for Build {
+        type Input = BuildIn;
+        type Output = Built;
+
+        fn execute(_input: Self::Input) -> Self::Output {
+            Built
+        }
+    }
+
+    #[test]
+    fn test_critical_path() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_task::<Parse>()
+            .unwrap()
+            .add_task::<Configure>()
+            .unwrap()
+            .add_task::<Build>()
+            .unwrap();
+        let graph = builder.build();
+        let path = graph.critical_path_with(|task| match task.name() {
+            "Parse" => Some(Duration::from_millis(30)),
+            "Configure" => Some(Duration::from_millis(10)),
+            "Build" => Some(Duration::from_millis(5)),
+            _ => None,
+        });
+        let names: Vec<_> = path.tasks.iter().map(TaskId::name).collect();
+        assert_eq!(names, ["Parse", "Build"]);
+        assert_eq!(path.length, Duration::from_millis(35));
+        let slack: Vec<_> = path.slack.iter().map(|(t, s)| (t.name(), *s)).collect();
+        assert_eq!(
+            slack,
+            [
+                ("Parse", Duration::ZERO),
+                ("Configure", Duration::from_millis(20)),
+                ("Build", Duration::ZERO),
+            ]
+        );
+    }
+}
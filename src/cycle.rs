@@ -0,0 +1,171 @@
+use std::any::TypeId;
+
+use crate::{DataBase, DbKey, ExecutionGraph, ExecutionGraphBuilder, KeyType, TaskWithContext};
+
+impl<Db: DataBase, Ctx> ExecutionGraphBuilder<Db, Ctx> {
+    /// Lets `T` read `K` before the task writing `K` is added. `T` then runs
+    /// before that task and reads the value `K` had at the end of the
+    /// previous run, or `initial` on the first run.
+    ///
+    /// Must be called before `T` is added. Other tasks reading `K` are
+    /// unaffected: without a lazy edge, a cycle fails with a
+    /// [`MissingDependency`](crate::MissingDependency), and tasks that must
+    /// agree within a run belong in a
+    /// [`FixpointGroup`](crate::fixpoint::FixpointGroup).
+    pub fn lazy_edge<K: DbKey, T: TaskWithContext<Db, Ctx>>(
+        &mut self,
+        initial: K::Value,
+    ) -> &mut Self {
+        self.graph
+            .lazy_edges
+            .insert((TypeId::of::<K>(), TypeId::of::<T>()));
+        if self.graph.contains_node(&TypeId::of::<K>()).is_none() {
+            self.graph
+                .with_shared_db(|graph| graph.db.put::<K>(initial));
+        }
+        self
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraph<Db, Ctx> {
+    /// Whether `task` reads the previous value of `key`.
+    pub(crate) fn is_lazy_edge(&self, key: KeyType, task: TypeId) -> bool {
+        self.lazy_edges.contains(&(key.id, task))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemoryDb, Task, TaskInput, TaskOutput};
+
+    struct Delta;
+
+    impl DbKey for Delta {
+        type Value = u32;
+    }
+
+    struct Total;
+
+    impl DbKey for Total {
+        type Value = u32;
+    }
+
+    struct Candidate;
+
+    impl DbKey for Candidate {
+        type Value = u32;
+    }
+
+    struct NextIn(u32, u32);
+
+    impl DbKey for NextIn {
+        type Value = NextIn;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for NextIn {
+        fn from_db(db: &Db) -> Self {
+            NextIn(*db.get::<Total>().unwrap(), *db.get::<Delta>().unwrap())
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Total>(), KeyType::of::<Delta>()]
+        }
+    }
+
+    struct CandidateOut(u32);
+
+    impl DbKey for CandidateOut {
+        type Value = CandidateOut;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for CandidateOut {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<Candidate>(self.0);
+        }
+
+        fn out_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Candidate>()]
+        }
+    }
+
+    struct Next;
+
+    impl Task<InMemoryDb> for Next {
+        type Input = NextIn;
+        type Output = CandidateOut;
+
+        fn execute(input: Self::Input) -> Self::Output {
+            CandidateOut(input.0 + input.1)
+        }
+    }
+
+    struct CandidateIn(u32);
+
+    impl DbKey for CandidateIn {
+        type Value = CandidateIn;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for CandidateIn {
+        fn from_db(db: &Db) -> Self {
+            CandidateIn(*db.get::<Candidate>().unwrap())
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Candidate>()]
+        }
+    }
+
+    struct TotalOut(u32);
+
+    impl DbKey for TotalOut {
+        type Value = TotalOut;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for TotalOut {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<Total>(self.0);
+        }
+
+        fn out_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Total>()]
+        }
+    }
+
+    struct Commit;
+
+    impl Task<InMemoryDb> for Commit {
+        type Input = CandidateIn;
+        type Output = TotalOut;
+
+        fn execute(input: Self::Input) -> Self::Output {
+            TotalOut(input.0)
+        }
+    }
+
+    #[test]
+    fn test_lazy_edge_reads_previous_run() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_input::<Delta>(5)
+            .unwrap()
+            .lazy_edge::<Total, Next>(100)
+            .add_task::<Next>()
+            .unwrap()
+            .add_task::<Commit>()
+            .unwrap();
+        let mut graph = builder.build();
+        graph.execute_all().unwrap();
+        assert_eq!(graph.db().get::<Total>(), Some(&105));
+        graph.execute_all().unwrap();
+        assert_eq!(graph.db().get::<Total>(), Some(&110));
+    }
+
+    #[test]
+    fn test_cycle_without_lazy_edge() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder.add_input::<Delta>(5).unwrap();
+        let err = builder.add_task::<Next>().err().unwrap();
+        assert_eq!(err.key, std::any::type_name::<Total>());
+    }
+}
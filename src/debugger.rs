@@ -0,0 +1,234 @@
+use crate::{history::VersionedDb, DataBase, DbKey, ExecutionGraph, KeyType, TaskId};
+
+/// One task of a recorded run, between two revisions of the database.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Step {
+    pub task: TaskId,
+    pub ok: bool,
+    /// The revision the task started from.
+    pub before: u64,
+    /// The revision once it finished, output written.
+    pub after: u64,
+}
+
+/// Steps through the last [`execute_all`](ExecutionGraph::execute_all) task
+/// by task, reading the values of kept keys as they were before and after
+/// each. Returned by [`ExecutionGraph::debugger`].
+pub struct RunDebugger<'g, Db: DataBase, Ctx> {
+    graph: &'g ExecutionGraph<VersionedDb<Db>, Ctx>,
+    steps: Vec<Step>,
+    at: usize,
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraph<VersionedDb<Db>, Ctx> {
+    /// A debugger over the last run, positioned at its first task. Values
+    /// are only available for keys the database
+    /// [keeps](VersionedDb::keep), and only as far back as it keeps them.
+    pub fn debugger(&self) -> RunDebugger<'_, Db, Ctx> {
+        let mut ran: Vec<_> = self
+            .entries
+            .iter()
+            .filter_map(|entry| {
+                let record = self.records.get(&entry.id)?;
+                (record.run == self.run).then_some((entry.task_id(), record))
+            })
+            .collect();
+        ran.sort_by_key(|(_, record)| record.started);
+        let mut before = self.run_revision.unwrap_or_default();
+        let steps = ran
+            .into_iter()
+            .map(|(task, record)| {
+                let after = record.revision.unwrap_or(before);
+                let step = Step {
+                    task,
+                    ok: record.ok,
+                    before,
+                    after,
+                };
+                before = after;
+                step
+            })
+            .collect();
+        RunDebugger {
+            graph: self,
+            steps,
+            at: 0,
+        }
+    }
+}
+
+impl<Db: DataBase, Ctx> RunDebugger<'_, Db, Ctx> {
+    /// Every task of the run, in the order they ran.
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    pub fn step(&self) -> Option<&Step> {
+        self.steps.get(self.at)
+    }
+
+    /// Moves to the next task, if any.
+    pub fn step_forward(&mut self) -> Option<&Step> {
+        if self.at + 1 < self.steps.len() {
+            self.at += 1;
+            return self.step();
+        }
+        None
+    }
+
+    /// Moves to the previous task, if any.
+    pub fn step_back(&mut self) -> Option<&Step> {
+        self.at = self.at.checked_sub(1)?;
+        self.step()
+    }
+
+    /// The value of `K` before the current task ran.
+    pub fn before<K: DbKey>(&self) -> Option<&K::Value> {
+        self.graph.db.get_at::<K>(self.step()?.before)
+    }
+
+    /// The value of `K` after the current task ran.
+    pub fn after<K: DbKey>(&self) -> Option<&K::Value> {
+        self.graph.db.get_at::<K>(self.step()?.after)
+    }
+
+    /// The kept keys the current task wrote or removed.
+    pub fn written(&self) -> Vec<KeyType> {
+        let Some(step) = self.step() else {
+            return Vec::new();
+        };
+        self.graph
+            .revision_log()
+            .into_iter()
+            .filter(|(revision, _)| (step.before + 1..=step.after).contains(revision))
+            .map(|(_, key)| key)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExecutionGraphBuilder, InMemoryDb, Task, TaskInput, TaskOutput};
+
+    struct Celsius;
+
+    impl DbKey for Celsius {
+        type Value = f64;
+    }
+
+    struct Fahrenheit;
+
+    impl DbKey for Fahrenheit {
+        type Value = f64;
+    }
+
+    struct Label;
+
+    impl DbKey for Label {
+        type Value = String;
+    }
+
+    macro_rules! input {
+        ($name:ident, $key:ty, $value:ty) => {
+            struct $name($value);
+
+            impl DbKey for $name {
+                type Value = $name;
+            }
+
+            impl<Db: DataBase> TaskInput<Db> for $name {
+                fn from_db(db: &Db) -> Self {
+                    $name(db.get::<$key>().unwrap().clone())
+                }
+
+                fn dep_types() -> Vec<KeyType> {
+                    vec![KeyType::of::<$key>()]
+                }
+            }
+        };
+    }
+
+    macro_rules! output {
+        ($name:ident, $key:ty, $value:ty) => {
+            struct $name($value);
+
+            impl DbKey for $name {
+                type Value = $name;
+            }
+
+            impl<Db: DataBase> TaskOutput<Db> for $name {
+                fn to_db(&self, db: &mut Db) {
+                    db.put::<$key>(self.0.clone());
+                }
+
+                fn out_types() -> Vec<KeyType> {
+                    vec![KeyType::of::<$key>()]
+                }
+            }
+        };
+    }
+
+    input!(CelsiusIn, Celsius, f64);
+    output!(FahrenheitOut, Fahrenheit, f64);
+    input!(FahrenheitIn, Fahrenheit, f64);
+    output!(LabelOut, Label, String);
+
+    struct Convert;
+
+    impl Task<VersionedDb> for Convert {
+        type Input = CelsiusIn;
+        type Output = FahrenheitOut;
+
+        fn execute(input: Self::Input) -> Self::Output {
+            FahrenheitOut(input.0 * 9.0 / 5.0 + 32.0)
+        }
+    }
+
+    struct Describe;
+
+    impl Task<VersionedDb> for Describe {
+        type Input = FahrenheitIn;
+        type Output = LabelOut;
+
+        fn execute(input: Self::Input) -> Self::Output {
+            LabelOut(format!("{}°F", input.0))
+        }
+    }
+
+    #[test]
+    fn test_step_through_run() {
+        let db = VersionedDb::new(InMemoryDb::new())
+            .keep::<Fahrenheit>(2)
+            .keep::<Label>(2);
+        let mut builder = ExecutionGraphBuilder::new(db);
+        builder
+            .add_input::<Celsius>(0.0)
+            .unwrap()
+            .add_task::<Convert>()
+            .unwrap()
+            .add_task::<Describe>()
+            .unwrap();
+        let mut graph = builder.build();
+        graph.execute_all().unwrap();
+        graph.set_input::<Celsius>(100.0).unwrap();
+        graph.execute_all().unwrap();
+
+        let mut debugger = graph.debugger();
+        let names: Vec<_> = debugger.steps().iter().map(|s| s.task.name()).collect();
+        assert_eq!(names, ["Convert", "Describe"]);
+        assert_eq!(debugger.before::<Fahrenheit>(), Some(&32.0));
+        assert_eq!(debugger.after::<Fahrenheit>(), Some(&212.0));
+        assert_eq!(debugger.after::<Label>().map(String::as_str), Some("32°F"));
+        assert_eq!(debugger.written(), [KeyType::of::<Fahrenheit>()]);
+
+        assert_eq!(
+            debugger.step_forward().map(|s| s.task.name()),
+            Some("Describe")
+        );
+        assert_eq!(debugger.after::<Label>().map(String::as_str), Some("212°F"));
+        assert!(debugger.step_forward().is_none());
+        assert!(debugger.step_back().is_some());
+        assert!(debugger.step_back().is_none());
+    }
+}
@@ -0,0 +1,251 @@
+use std::{fmt, str::FromStr};
+
+use crate::{DataBase, ExecutionGraph};
+
+/// A serializable snapshot of a graph's topology.
+///
+/// The text form has one `node <index> <name>` line per value node followed by
+/// one `edge <from> <to>` line per edge, so it can be read by tools without a
+/// Rust toolchain (see the `cg` binary). Task annotations follow as
+/// `meta <node> <key> <value>` lines, attached to the task's output node,
+/// with backslashes and whitespace in keys and values escaped as `\\`,
+/// `\s`, `\t`, `\n` and `\r`.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct GraphDescription {
+    pub nodes: Vec<String>,
+    pub edges: Vec<(usize, usize)>,
+    pub annotations: Vec<(usize, String, String)>,
+}
+
+impl GraphDescription {
+    pub fn roots(&self) -> Vec<usize> {
+        (0..self.nodes.len())
+            .filter(|n| !self.edges.iter().any(|(_, to)| to == n))
+            .collect()
+    }
+
+    pub fn leaves(&self) -> Vec<usize> {
+        (0..self.nodes.len())
+            .filter(|n| !self.edges.iter().any(|(from, _)| from == n))
+            .collect()
+    }
+
+    pub fn successors(&self, node: usize) -> impl Iterator<Item = usize> + '_ {
+        self.edges
+            .iter()
+            .filter(move |(from, _)| *from == node)
+            .map(|(_, to)| *to)
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraph<Db, Ctx> {
+    pub fn describe(&self) -> GraphDescription {
+        GraphDescription {
+            nodes: self
+                .tasks
+                .node_indices()
+                .map(|i| self.name_of(&self.tasks[i]))
+                .collect(),
+            edges: self
+                .tasks
+                .raw_edges()
+                .iter()
+                .map(|e| (e.source().index(), e.target().index()))
+                .collect(),
+            annotations: self
+                .entries
+                .iter()
+                .flat_map(|entry| {
+                    let node = entry.output_node.index();
+                    self.metadata
+                        .get(&entry.id)
+                        .into_iter()
+                        .flatten()
+                        .map(move |(key, value)| (node, key.clone(), value.clone()))
+                })
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for GraphDescription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, name) in self.nodes.iter().enumerate() {
+            writeln!(f, "node {i} {name}")?;
+        }
+        for (from, to) in &self.edges {
+            writeln!(f, "edge {from} {to}")?;
+        }
+        for (node, key, value) in &self.annotations {
+            writeln!(f, "meta {node} {} {}", escape(key), escape(value))?;
+        }
+        Ok(())
+    }
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ' ' => escaped.push_str("\\s"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape(s: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        unescaped.push(match chars.next()? {
+            '\\' => '\\',
+            's' => ' ',
+            't' => '\t',
+            'n' => '\n',
+            'r' => '\r',
+            _ => return None,
+        });
+    }
+    Some(unescaped)
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ParseDescriptionError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseDescriptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseDescriptionError {}
+
+impl FromStr for GraphDescription {
+    type Err = ParseDescriptionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut desc = GraphDescription::default();
+        for (n, line) in s.lines().enumerate() {
+            let err = |message: String| ParseDescriptionError {
+                line: n + 1,
+                message,
+            };
+            let index = |s: Option<&str>| {
+                s.and_then(|s| s.parse::<usize>().ok())
+                    .ok_or_else(|| err("expected a node index".to_string()))
+            };
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let mut parts = line.splitn(3, ' ');
+            match parts.next() {
+                Some("node") => {
+                    let i = index(parts.next())?;
+                    if i != desc.nodes.len() {
+                        return Err(err(format!(
+                            "expected node {}, found {i}",
+                            desc.nodes.len()
+                        )));
+                    }
+                    desc.nodes
+                        .push(parts.next().unwrap_or_default().to_string());
+                }
+                Some("edge") => {
+                    let from = index(parts.next())?;
+                    let to = index(parts.next())?;
+                    if from >= desc.nodes.len() || to >= desc.nodes.len() {
+                        return Err(err(format!(
+                            "edge {from} -> {to} refers to an unknown node"
+                        )));
+                    }
+                    desc.edges.push((from, to));
+                }
+                Some("meta") => {
+                    let node = index(parts.next())?;
+                    if node >= desc.nodes.len() {
+                        return Err(err(format!("annotation refers to unknown node {node}")));
+                    }
+                    let (key, value) = parts
+                        .next()
+                        .and_then(|rest| rest.split_once(' '))
+                        .ok_or_else(|| err("expected an annotation key and value".to_string()))?;
+                    let (Some(key), Some(value)) = (unescape(key), unescape(value)) else {
+                        return Err(err("invalid escape in annotation".to_string()));
+                    };
+                    desc.annotations.push((node, key, value));
+                }
+                Some(other) => return Err(err(format!("unknown entry `{other}`"))),
+                None => unreachable!(),
+            }
+        }
+        Ok(desc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExecutionGraphBuilder, InMemoryDb, Task};
+
+    struct Noop;
+
+    impl Task<InMemoryDb> for Noop {
+        type Input = ();
+        type Output = ();
+
+        fn execute(_input: Self::Input) -> Self::Output {}
+    }
+
+    #[test]
+    fn test_description_round_trip() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder.add_task::<Noop>().unwrap();
+        let desc = builder.build().describe();
+        assert_eq!(desc.nodes, vec!["()", "()"]);
+
+        let parsed: GraphDescription = desc.to_string().parse().unwrap();
+        assert_eq!(parsed, desc);
+        assert_eq!(parsed.roots(), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_annotation_escaping() {
+        let desc = GraphDescription {
+            nodes: vec!["A".to_string()],
+            edges: Vec::new(),
+            annotations: vec![(
+                0,
+                "owner team".to_string(),
+                "a\\b \nmeta 0 x y ".to_string(),
+            )],
+        };
+        assert_eq!(desc.to_string().lines().count(), 2);
+        let parsed: GraphDescription = desc.to_string().parse().unwrap();
+        assert_eq!(parsed, desc);
+        assert!("node 0 A\nmeta 0 k \\q"
+            .parse::<GraphDescription>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_description_parse_errors() {
+        let err = "node 0 A\nedge 0 3\n"
+            .parse::<GraphDescription>()
+            .unwrap_err();
+        assert_eq!(err.line, 2);
+        assert!("node 1 A".parse::<GraphDescription>().is_err());
+    }
+}
@@ -0,0 +1,509 @@
+use std::{
+    any::{type_name, TypeId},
+    collections::HashSet,
+    rc::Rc,
+};
+
+use petgraph::graph::NodeIndex;
+
+use crate::{
+    panic::catch_task_panic, AddTasksError, DataBase, ExecutionError, ExecutionGraph,
+    ExecutionGraphBuilder, KeyType, MissingDependency, MissingOutput, TaskEntry, TaskInput,
+    TaskOutput, TaskWithContext,
+};
+
+/// The implementation of a task made with [`TaskDescriptor::new`].
+pub(crate) type Executable<Db> = Rc<dyn Fn(&mut Db)>;
+
+/// A type-erased task: its name, the keys it reads and writes, and how to run
+/// it. Added in bulk with [`ExecutionGraphBuilder::add_tasks`].
+pub struct TaskDescriptor<Db: DataBase, Ctx = ()> {
+    pub(crate) entry: TaskEntry<Db, Ctx>,
+    type_name: &'static str,
+    outs: Vec<KeyType>,
+    wire_input: fn(&mut Db),
+    execute: Option<Executable<Db>>,
+}
+
+impl<Db: DataBase, Ctx> TaskDescriptor<Db, Ctx> {
+    pub fn of<T: TaskWithContext<Db, Ctx>>() -> Self {
+        let outs = T::Output::out_types();
+        TaskDescriptor {
+            entry: TaskEntry {
+                id: TypeId::of::<T>(),
+                name: T::name(),
+                input: KeyType::of::<T::Input>(),
+                output: KeyType::of::<T::Output>(),
+                // Set once the task is wired into a graph.
+                input_node: NodeIndex::end(),
+                output_node: NodeIndex::end(),
+                deps: T::Input::dep_types(),
+                writes: std::iter::once(KeyType::of::<T::Output>())
+                    .chain(outs.iter().copied())
+                    .collect(),
+                run: |graph, _| graph.execute::<T>().map(|_| ()),
+                // `to_db` may store the output itself, or only the keys it
+                // declares.
+                has_output: |db, _| {
+                    let outs = T::Output::out_types();
+                    db.get::<T::Output>().is_some()
+                        || (!outs.is_empty()
+                            && outs.into_iter().all(|key| db.contains(key) == Some(true)))
+                },
+                invalidate: |db, _| {
+                    db.remove::<T::Output>();
+                    for key in T::Output::out_types() {
+                        db.remove_key(key);
+                    }
+                },
+            },
+            type_name: type_name::<T>(),
+            outs,
+            wire_input: |db| {
+                let input = T::Input::from_db(db);
+                db.put::<T::Input>(input);
+            },
+            execute: None,
+        }
+    }
+
+    /// A task named `name` that runs `execute` over the database, reading
+    /// `deps` and writing every key of `outs`, for tasks only known at run
+    /// time, e.g. generated from a pipeline description. The first key of
+    /// `outs` is the task's output, and identifies it in the graph.
+    ///
+    /// Its outputs are checked to be written, and a panic in `execute` is
+    /// caught with [`catch_panics`](ExecutionGraph::catch_panics), but the
+    /// other per-task settings of typed tasks do not apply to it.
+    ///
+    /// # Panics
+    ///
+    /// If `outs` is empty.
+    pub fn new(
+        name: &'static str,
+        deps: Vec<KeyType>,
+        outs: Vec<KeyType>,
+        execute: Box<dyn Fn(&mut Db)>,
+    ) -> Self {
+        let (&output, rest) = outs
+            .split_first()
+            .unwrap_or_else(|| panic!("task `{name}` must write at least one key"));
+        TaskDescriptor {
+            entry: TaskEntry {
+                id: output.id,
+                name,
+                input: output,
+                output,
+                input_node: NodeIndex::end(),
+                output_node: NodeIndex::end(),
+                deps,
+                writes: outs.clone(),
+                run: |graph, id| graph.run_executable(id),
+                has_output: |db, writes| writes.iter().all(|&key| db.contains(key) == Some(true)),
+                invalidate: |db, writes| {
+                    for &key in writes {
+                        db.remove_key(key);
+                    }
+                },
+            },
+            type_name: name,
+            outs: rest.to_vec(),
+            wire_input: |_| {},
+            execute: Some(Rc::from(execute)),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.entry.name
+    }
+
+    /// The keys the task reads.
+    pub fn deps(&self) -> &[KeyType] {
+        &self.entry.deps
+    }
+
+    /// The keys the task writes besides its output.
+    pub fn outs(&self) -> &[KeyType] {
+        &self.outs
+    }
+
+    /// Runs the task in `graph`, like [`ExecutionGraph::execute`].
+    pub fn execute(&self, graph: &mut ExecutionGraph<Db, Ctx>) -> Result<(), ExecutionError> {
+        (self.entry.run)(graph, self.entry.id)
+    }
+
+    /// The keys the task writes that must not be in the graph yet.
+    fn new_keys(&self) -> impl Iterator<Item = KeyType> + '_ {
+        let output = self.execute.is_some().then_some(self.entry.output);
+        output.into_iter().chain(self.outs.iter().copied())
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraphBuilder<Db, Ctx> {
+    /// Adds every task of `tasks`, in order, like
+    /// [`add_task`](Self::add_task). A task may depend on tasks earlier in
+    /// `tasks`.
+    ///
+    /// The whole batch is checked before any task is added, so on error the
+    /// builder is left unchanged.
+    pub fn add_tasks(
+        &mut self,
+        tasks: impl IntoIterator<Item = TaskDescriptor<Db, Ctx>>,
+    ) -> Result<&mut Self, AddTasksError> {
+        let tasks: Vec<_> = tasks.into_iter().collect();
+        let graph = &self.graph;
+        let mut produced = HashSet::new();
+        for task in &tasks {
+            let entry = &task.entry;
+            for key in &entry.deps {
+                if graph.contains_node(&key.id).is_none()
+                    && !graph.is_lazy_edge(*key, entry.id)
+                    && !produced.contains(&key.id)
+                {
+                    return Err(graph.missing_dependency(*key, task.type_name).into());
+                }
+            }
+            for key in task.new_keys() {
+                if graph.contains_node(&key.id).is_some() || !produced.insert(key.id) {
+                    return Err(AddTasksError::DuplicateOutput {
+                        task: task.type_name,
+                        key: key.name,
+                    });
+                }
+            }
+            produced.extend([entry.input.id, entry.output.id]);
+        }
+        for task in tasks {
+            let entry = self.graph.wire_descriptor(task, &[])?;
+            self.graph.entries.push(entry);
+        }
+        Ok(self)
+    }
+
+    /// Adds `task` like [`add_tasks`](Self::add_tasks), for callers that
+    /// report only missing dependencies.
+    ///
+    /// # Panics
+    ///
+    /// If the task writes a key that is already produced.
+    pub(crate) fn add_descriptor(
+        &mut self,
+        task: TaskDescriptor<Db, Ctx>,
+    ) -> Result<&mut Self, MissingDependency> {
+        match self.add_tasks([task]) {
+            Ok(_) => Ok(self),
+            Err(AddTasksError::MissingDependency(e)) => Err(e),
+            Err(e) => panic!("{e}"),
+        }
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraph<Db, Ctx> {
+    /// Adds the nodes and edges of `task` and returns its entry. Keys in
+    /// `cyclic` are already registered and may be written by the task.
+    pub(crate) fn wire_descriptor(
+        &mut self,
+        task: TaskDescriptor<Db, Ctx>,
+        cyclic: &[KeyType],
+    ) -> Result<TaskEntry<Db, Ctx>, AddTasksError> {
+        if let Some(key) = task
+            .new_keys()
+            .find(|key| !cyclic.contains(key) && self.contains_node(&key.id).is_some())
+        {
+            return Err(AddTasksError::DuplicateOutput {
+                task: task.type_name,
+                key: key.name,
+            });
+        }
+        let TaskDescriptor {
+            mut entry,
+            type_name,
+            outs,
+            wire_input,
+            execute,
+        } = task;
+        let deps = entry
+            .deps
+            .iter()
+            .filter_map(|key| match self.contains_node(&key.id) {
+                Some(node) => Some(Ok(node)),
+                None if self.is_lazy_edge(*key, entry.id) => None,
+                None => Some(Err(self.missing_dependency(*key, type_name))),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        entry.input_node = self.register(entry.input);
+        for dep in deps {
+            self.tasks.add_edge(dep, entry.input_node, wire_input);
+        }
+        entry.output_node = match (&execute, self.contains_node(&entry.output.id)) {
+            // The task reads into and writes from the node of its output.
+            (Some(execute), _) => {
+                self.executables.insert(entry.id, execute.clone());
+                entry.input_node
+            }
+            (None, Some(node)) if cyclic.contains(&entry.output) => node,
+            (None, _) => self.register(entry.output),
+        };
+        if execute.is_none() {
+            self.names.insert(entry.id, type_name);
+        }
+        self.producers.insert(entry.output.id, type_name);
+        self.inputs.remove(&entry.output.id);
+        for out_ty in outs {
+            let out_ty_node = match self.contains_node(&out_ty.id) {
+                Some(node) => node,
+                None => self.register(out_ty),
+            };
+            self.producers.insert(out_ty.id, type_name);
+            self.tasks.add_edge(entry.output_node, out_ty_node, |_| {});
+        }
+        self.reachability.invalidate();
+        Ok(entry)
+    }
+
+    /// Runs the task made with [`TaskDescriptor::new`] identified by `id`.
+    fn run_executable(&mut self, id: TypeId) -> Result<(), ExecutionError> {
+        let entry = self
+            .entries
+            .iter()
+            .find(|entry| entry.id == id)
+            .expect("executable tasks run from their entry");
+        let (name, writes) = (entry.name, entry.writes.clone());
+        let missing = entry
+            .deps
+            .iter()
+            .find(|key| self.contains_node(&key.id).is_none() && !self.is_lazy_edge(**key, id));
+        if let Some(&key) = missing {
+            return Err(self.missing_dependency(key, name).into());
+        }
+        let execute = self.executables[&id].clone();
+        self.with_shared_db(|graph| {
+            let result = if graph.catch_panics {
+                catch_task_panic(name, || execute(&mut graph.db)).map_err(ExecutionError::from)
+            } else {
+                execute(&mut graph.db);
+                Ok(())
+            };
+            let result = result.and_then(|()| {
+                match writes
+                    .iter()
+                    .find(|&&key| graph.db.contains(key) == Some(false))
+                {
+                    Some(key) => Err(MissingOutput {
+                        task: name,
+                        key: key.name,
+                    }
+                    .into()),
+                    None => Ok(()),
+                }
+            });
+            match &result {
+                Ok(()) => {
+                    graph.failures.remove(&id);
+                }
+                Err(e) => graph.record_failure(id, e.clone()),
+            }
+            result
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DbKey, InMemoryDb, Task};
+
+    struct Celsius;
+
+    impl DbKey for Celsius {
+        type Value = f64;
+    }
+
+    struct Fahrenheit;
+
+    impl DbKey for Fahrenheit {
+        type Value = f64;
+    }
+
+    struct Report;
+
+    impl DbKey for Report {
+        type Value = String;
+    }
+
+    struct CelsiusIn(f64);
+
+    impl DbKey for CelsiusIn {
+        type Value = CelsiusIn;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for CelsiusIn {
+        fn from_db(db: &Db) -> Self {
+            CelsiusIn(*db.get::<Celsius>().unwrap())
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Celsius>()]
+        }
+    }
+
+    struct FahrenheitOut(f64);
+
+    impl DbKey for FahrenheitOut {
+        type Value = FahrenheitOut;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for FahrenheitOut {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<Fahrenheit>(self.0);
+        }
+
+        fn out_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Fahrenheit>()]
+        }
+    }
+
+    struct Convert;
+
+    impl Task<InMemoryDb> for Convert {
+        type Input = CelsiusIn;
+        type Output = FahrenheitOut;
+
+        fn execute(input: Self::Input) -> Self::Output {
+            FahrenheitOut(input.0 * 9.0 / 5.0 + 32.0)
+        }
+    }
+
+    struct FahrenheitIn(f64);
+
+    impl DbKey for FahrenheitIn {
+        type Value = FahrenheitIn;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for FahrenheitIn {
+        fn from_db(db: &Db) -> Self {
+            FahrenheitIn(*db.get::<Fahrenheit>().unwrap())
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Fahrenheit>()]
+        }
+    }
+
+    struct ReportOut(String);
+
+    impl DbKey for ReportOut {
+        type Value = ReportOut;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for ReportOut {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<Report>(self.0.clone());
+        }
+
+        fn out_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Report>()]
+        }
+    }
+
+    struct Format;
+
+    impl Task<InMemoryDb> for Format {
+        type Input = FahrenheitIn;
+        type Output = ReportOut;
+
+        fn execute(input: Self::Input) -> Self::Output {
+            ReportOut(format!("{}°F", input.0))
+        }
+    }
+
+    #[test]
+    fn test_add_tasks() {
+        let tasks = vec![
+            TaskDescriptor::of::<Convert>(),
+            TaskDescriptor::of::<Format>(),
+        ];
+        assert_eq!(tasks[1].name(), "Format");
+        assert_eq!(tasks[1].deps(), &[KeyType::of::<Fahrenheit>()]);
+        assert_eq!(tasks[1].outs(), &[KeyType::of::<Report>()]);
+
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_input::<Celsius>(100.0)
+            .unwrap()
+            .add_tasks(tasks)
+            .unwrap();
+        let mut graph = builder.build();
+        graph.execute_all().unwrap();
+        assert_eq!(graph.db().get::<Report>().unwrap(), "212°F");
+    }
+
+    #[test]
+    fn test_add_tasks_out_of_order() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder.add_input::<Celsius>(100.0).unwrap();
+        let Err(AddTasksError::MissingDependency(err)) = builder.add_tasks([
+            TaskDescriptor::of::<Format>(),
+            TaskDescriptor::of::<Convert>(),
+        ]) else {
+            panic!("expected a missing dependency");
+        };
+        assert_eq!(err.key, type_name::<Fahrenheit>());
+        assert_eq!(err.task, type_name::<Format>());
+
+        let err = builder
+            .add_tasks([
+                TaskDescriptor::of::<Convert>(),
+                TaskDescriptor::of::<Convert>(),
+            ])
+            .err()
+            .unwrap();
+        assert_eq!(
+            err,
+            AddTasksError::DuplicateOutput {
+                task: type_name::<Convert>(),
+                key: type_name::<Fahrenheit>(),
+            }
+        );
+        // Nothing of a rejected batch is added.
+        assert!(builder.build().task_by_name("Convert").is_none());
+    }
+
+    #[test]
+    fn test_generated_tasks() {
+        let to_fahrenheit = TaskDescriptor::new(
+            "ToFahrenheit",
+            vec![KeyType::of::<Celsius>()],
+            vec![KeyType::of::<Fahrenheit>()],
+            Box::new(|db: &mut InMemoryDb| {
+                let celsius = *db.get::<Celsius>().unwrap();
+                db.put::<Fahrenheit>(celsius * 9.0 / 5.0 + 32.0);
+            }),
+        );
+        let report = TaskDescriptor::new(
+            "Report",
+            vec![KeyType::of::<Fahrenheit>()],
+            vec![KeyType::of::<Report>()],
+            Box::new(|db: &mut InMemoryDb| {
+                let fahrenheit = *db.get::<Fahrenheit>().unwrap();
+                db.put::<Report>(format!("{fahrenheit}°F"));
+            }),
+        );
+        assert_eq!(report.outs(), &[]);
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_input::<Celsius>(100.0)
+            .unwrap()
+            .add_tasks([to_fahrenheit, report])
+            .unwrap();
+        let mut graph = builder.build();
+        graph.execute_all().unwrap();
+        assert_eq!(graph.db().get::<Report>().unwrap(), "212°F");
+
+        graph.set_input::<Celsius>(0.0).unwrap();
+        graph.invalidate_dependents(&[KeyType::of::<Celsius>()]);
+        assert!(!graph.task_by_name("Report").unwrap().has_output());
+        graph.execute_all().unwrap();
+        assert_eq!(graph.db().get::<Report>().unwrap(), "32°F");
+    }
+}
@@ -0,0 +1,163 @@
+use std::fmt::Write as _;
+
+use crate::{DataBase, ExecutionGraph, TaskStatus};
+
+fn color(status: TaskStatus) -> &'static str {
+    match status {
+        TaskStatus::Cached => "palegreen",
+        TaskStatus::Recomputed => "lightblue",
+        TaskStatus::Failed => "lightcoral",
+        TaskStatus::NeverRun => "lightgray",
+    }
+}
+
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraph<Db, Ctx> {
+    /// Renders the graph in Graphviz DOT format.
+    ///
+    /// Each task's output node is colored by its [`TaskStatus`] in the last
+    /// run (green cached, blue recomputed, red failed, gray never run) and
+    /// labelled with the task's name and last duration. Input nodes are left
+    /// unfilled.
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph {\n");
+        for i in self.tasks.node_indices() {
+            let mut label = self.name_of(&self.tasks[i]);
+            let mut fill = None;
+            if let Some(entry) = self.entries.iter().find(|e| e.output_node == i) {
+                let status = self.status_of(entry.id, self.with_db(|db| entry.output_stored(db)));
+                let _ = write!(label, "\n{}", entry.name);
+                if let Some(record) = self.records.get(&entry.id) {
+                    let _ = write!(label, " {:.3}ms", record.duration.as_secs_f64() * 1000.0);
+                }
+                fill = Some(color(status));
+            }
+            let _ = write!(out, "    {} [label=\"{}\"", i.index(), escape(&label));
+            if let Some(fill) = fill {
+                let _ = write!(out, " style=filled fillcolor={}", fill);
+            }
+            out.push_str("];\n");
+        }
+        for edge in self.tasks.raw_edges() {
+            let _ = writeln!(
+                out,
+                "    {} -> {};",
+                edge.source().index(),
+                edge.target().index()
+            );
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DbKey, ExecutionGraphBuilder, InMemoryDb, KeyType, Task, TaskInput, TaskOutput};
+
+    struct Width;
+
+    impl DbKey for Width {
+        type Value = u32;
+    }
+
+    struct Side(u32);
+
+    impl DbKey for Side {
+        type Value = Side;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for Side {
+        fn from_db(db: &Db) -> Self {
+            Side(*db.get::<Width>().unwrap())
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Width>()]
+        }
+    }
+
+    struct Area(u32);
+
+    impl DbKey for Area {
+        type Value = Area;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for Area {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<Area>(Area(self.0));
+        }
+    }
+
+    struct CheckedWidth(u32);
+
+    impl DbKey for CheckedWidth {
+        type Value = CheckedWidth;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for CheckedWidth {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<CheckedWidth>(CheckedWidth(self.0));
+        }
+    }
+
+    struct CheckWidth;
+
+    impl Task<InMemoryDb> for CheckWidth {
+        type Input = Side;
+        type Output = CheckedWidth;
+
+        fn execute(input: Self::Input) -> Self::Output {
+            CheckedWidth(input.0)
+        }
+    }
+
+    struct Square;
+
+    impl Task<InMemoryDb> for Square {
+        type Input = Side;
+        type Output = Area;
+
+        fn execute(input: Self::Input) -> Self::Output {
+            Area(input.0 * input.0)
+        }
+    }
+
+    #[test]
+    fn test_dot_status_colors() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_input::<Width>(3)
+            .unwrap()
+            .add_task::<CheckWidth>()
+            .unwrap()
+            .add_task::<Square>()
+            .unwrap()
+            .on_output::<CheckedWidth>(|width| match width.0 {
+                0..=10 => Ok(()),
+                _ => Err("too wide".to_string()),
+            });
+        let mut graph = builder.build();
+        assert!(graph.to_dot().contains("fillcolor=lightgray"));
+        assert_eq!(graph.task_status::<Square>(), TaskStatus::NeverRun);
+
+        graph.execute_all().unwrap();
+        let dot = graph.to_dot();
+        assert!(dot.contains("fillcolor=lightblue"), "{dot}");
+        assert!(dot.contains("Square "));
+        assert!(dot.contains("0 -> 1;"));
+
+        // A run that stops before `Square` leaves its output cached.
+        graph.set_input::<Width>(30).unwrap();
+        assert!(graph.execute_all().is_err());
+        assert_eq!(graph.task_status::<CheckWidth>(), TaskStatus::Failed);
+        assert_eq!(graph.task_status::<Square>(), TaskStatus::Cached);
+        let dot = graph.to_dot();
+        assert!(dot.contains("fillcolor=lightcoral"), "{dot}");
+        assert!(dot.contains("fillcolor=palegreen"), "{dot}");
+    }
+}
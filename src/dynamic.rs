@@ -0,0 +1,230 @@
+use std::{
+    any::{type_name, Any, TypeId},
+    cell::RefCell,
+    marker::PhantomData,
+    rc::Rc,
+};
+
+use crate::{
+    swap::DynTask, DataBase, DbKey, ExecutionGraph, ExecutionGraphBuilder, KeyType,
+    MissingDependency, Task, TaskInput, TaskOutput,
+};
+
+/// Read access to the database for a [`DynamicTask`], recording every key
+/// read as a dependency of the task.
+pub struct Deps<'db, Db> {
+    db: &'db Db,
+    read: RefCell<Vec<KeyType>>,
+}
+
+impl<'db, Db: DataBase> Deps<'db, Db> {
+    pub fn get<K: DbKey>(&self) -> Option<&'db K::Value> {
+        let key = KeyType::of::<K>();
+        let mut read = self.read.borrow_mut();
+        if !read.contains(&key) {
+            read.push(key);
+        }
+        self.db.get::<K>()
+    }
+}
+
+/// A task that only knows some of its dependencies once it runs, e.g. the
+/// sources listed in a build file. Keys read through [`Deps`] become
+/// dependencies of the task for invalidation and recomputation, like those
+/// of its `Input`, from the first run that reads them on.
+///
+/// Added with [`ExecutionGraphBuilder::add_dynamic_task`].
+pub trait DynamicTask<Db: DataBase>: 'static {
+    type Input: TaskInput<Db>;
+    type Output: TaskOutput<Db>;
+
+    fn execute(input: Self::Input, deps: &Deps<'_, Db>) -> Self::Output;
+}
+
+/// The task registered in the graph for a [`DynamicTask`] `T`.
+pub struct Dynamic<T>(PhantomData<fn() -> T>);
+
+impl<Db: DataBase, T: DynamicTask<Db>> Task<Db> for Dynamic<T> {
+    type Input = T::Input;
+    type Output = T::Output;
+
+    fn execute(_input: Self::Input) -> Self::Output {
+        // `add_dynamic_task` installs the tracking runner as the task's
+        // runner.
+        unreachable!("dynamic tasks only run through add_dynamic_task")
+    }
+
+    fn name() -> &'static str {
+        crate::short_type_name(type_name::<T>())
+    }
+}
+
+struct Runner<T> {
+    read: RefCell<Vec<KeyType>>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<Db: DataBase, T: DynamicTask<Db>> DynTask<Db> for Runner<T> {
+    fn execute(&self, db: &Db) -> Box<dyn Any> {
+        let deps = Deps {
+            db,
+            read: RefCell::new(Vec::new()),
+        };
+        let output = T::execute(T::Input::from_db(db), &deps);
+        *self.read.borrow_mut() = deps.read.into_inner();
+        Box::new(output)
+    }
+
+    fn output_type(&self) -> TypeId {
+        TypeId::of::<T::Output>()
+    }
+
+    fn discovered_deps(&self) -> Vec<KeyType> {
+        self.read.take()
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraphBuilder<Db, Ctx> {
+    /// Adds the [`DynamicTask`] `T`, registered as [`Dynamic<T>`]. Only the
+    /// dependencies of its `Input` are checked here.
+    pub fn add_dynamic_task<T: DynamicTask<Db>>(&mut self) -> Result<&mut Self, MissingDependency> {
+        self.add_task::<Dynamic<T>>()?;
+        self.graph.runners.insert(
+            TypeId::of::<Dynamic<T>>(),
+            Rc::new(Runner::<T> {
+                read: RefCell::new(Vec::new()),
+                _marker: PhantomData,
+            }),
+        );
+        Ok(self)
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraph<Db, Ctx> {
+    /// Adds the keys a task read while running to its dependencies, with an
+    /// edge from each key's node.
+    pub(crate) fn record_dynamic_deps(&mut self, task: TypeId, keys: Vec<KeyType>) {
+        let Some(index) = self.entries.iter().position(|e| e.id == task) else {
+            return;
+        };
+        for key in keys {
+            if self.entries[index].deps.contains(&key) {
+                continue;
+            }
+            self.entries[index].deps.push(key);
+            if let Some(node) = self.contains_node(&key.id) {
+                let input_node = self.entries[index].input_node;
+                self.tasks.add_edge(node, input_node, |_| {});
+                self.reachability.invalidate();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemoryDb;
+
+    struct Manifest;
+
+    impl DbKey for Manifest {
+        type Value = Vec<&'static str>;
+    }
+
+    struct MainRs;
+
+    impl DbKey for MainRs {
+        type Value = String;
+    }
+
+    struct LibRs;
+
+    impl DbKey for LibRs {
+        type Value = String;
+    }
+
+    struct ManifestIn(Vec<&'static str>);
+
+    impl DbKey for ManifestIn {
+        type Value = ManifestIn;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for ManifestIn {
+        fn from_db(db: &Db) -> Self {
+            ManifestIn(db.get::<Manifest>().unwrap().clone())
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Manifest>()]
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Lines(usize);
+
+    impl DbKey for Lines {
+        type Value = Lines;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for Lines {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<Lines>(Lines(self.0));
+        }
+    }
+
+    struct Compile;
+
+    impl DynamicTask<InMemoryDb> for Compile {
+        type Input = ManifestIn;
+        type Output = Lines;
+
+        fn execute(input: Self::Input, deps: &Deps<'_, InMemoryDb>) -> Self::Output {
+            let lines = input
+                .0
+                .iter()
+                .map(|source| match *source {
+                    "main.rs" => deps.get::<MainRs>(),
+                    _ => deps.get::<LibRs>(),
+                })
+                .map(|text| text.map_or(0, |text| text.lines().count()))
+                .sum();
+            Lines(lines)
+        }
+    }
+
+    #[test]
+    fn test_dynamic_dependencies() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_input::<Manifest>(vec!["main.rs"])
+            .unwrap()
+            .add_input::<MainRs>("fn main() {\n}".to_string())
+            .unwrap()
+            .add_input::<LibRs>("pub mod a;".to_string())
+            .unwrap()
+            .add_dynamic_task::<Compile>()
+            .unwrap();
+        let mut graph = builder.build();
+        graph.execute_all().unwrap();
+        assert_eq!(graph.db().get::<Lines>(), Some(&Lines(2)));
+        assert_eq!(
+            graph.task_by_name("Compile").unwrap().deps(),
+            &[KeyType::of::<Manifest>(), KeyType::of::<MainRs>()]
+        );
+
+        // A change to a discovered dependency invalidates the task.
+        graph
+            .set_input::<MainRs>("fn main() {}".to_string())
+            .unwrap();
+        graph.invalidate_dependents(&[KeyType::of::<MainRs>()]);
+        assert!(graph.db().get::<Lines>().is_none());
+
+        graph
+            .set_input::<Manifest>(vec!["main.rs", "lib.rs"])
+            .unwrap();
+        graph.execute_all().unwrap();
+        assert_eq!(graph.db().get::<Lines>(), Some(&Lines(2)));
+        assert_eq!(graph.task_by_name("Compile").unwrap().deps().len(), 3);
+    }
+}
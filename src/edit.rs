@@ -0,0 +1,333 @@
+use std::any::{type_name, TypeId};
+
+use petgraph::graph::NodeIndex;
+
+use crate::{DataBase, ExecutionGraphBuilder, TaskEditError, TaskEntry, TaskWithContext};
+
+impl<Db: DataBase, Ctx> ExecutionGraphBuilder<Db, Ctx> {
+    fn entry_index<T: TaskWithContext<Db, Ctx>>(&self) -> Result<usize, TaskEditError> {
+        self.graph
+            .entries
+            .iter()
+            .position(|e| e.id == TypeId::of::<T>())
+            .ok_or(TaskEditError::UnknownTask {
+                task: type_name::<T>(),
+            })
+    }
+
+    /// Removes task `T` along with the nodes and edges it added. Fails if
+    /// another task still reads one of the keys `T` writes.
+    pub fn remove_task<T: TaskWithContext<Db, Ctx>>(&mut self) -> Result<&mut Self, TaskEditError> {
+        let index = self.entry_index::<T>()?;
+        let writes = self.graph.entries[index].writes.clone();
+        for key in &writes {
+            let dependents: Vec<&'static str> = self
+                .graph
+                .entries
+                .iter()
+                .filter(|e| e.deps.contains(key))
+                .map(|e| self.graph.names[&e.id])
+                .collect();
+            if !dependents.is_empty() {
+                return Err(TaskEditError::InUse {
+                    task: type_name::<T>(),
+                    key: key.name,
+                    dependents,
+                });
+            }
+        }
+
+        let entry = self.graph.entries.remove(index);
+        let mut nodes: Vec<NodeIndex> = self
+            .graph
+            .tasks
+            .neighbors(entry.output_node)
+            .chain([entry.input_node, entry.output_node])
+            .collect();
+        nodes.sort();
+        nodes.dedup();
+        // Removing a node moves the last node into its slot, so go from the
+        // highest index down to keep the remaining indices valid.
+        for node in nodes.into_iter().rev() {
+            self.remove_node(node);
+        }
+        for key in &writes {
+            if self.graph.producers.get(&key.id) == Some(&type_name::<T>()) {
+                self.graph.producers.remove(&key.id);
+            }
+            self.forget_output(key.id);
+        }
+        self.forget_task(TypeId::of::<T>());
+        Ok(self)
+    }
+
+    /// Drops everything registered for task `id`, so that a task of the same
+    /// type added later starts from a clean slate.
+    fn forget_task(&mut self, id: TypeId) {
+        let graph = &mut self.graph;
+        graph.names.remove(&id);
+        graph.metadata.remove(&id);
+        graph.stubs.remove(&id);
+        graph.swapped.remove(&id);
+        graph.runners.remove(&id);
+        graph.executables.remove(&id);
+        graph.fixpoints.remove(&id);
+        graph.scratch.remove(&id);
+        graph.side_effects.remove(&id);
+        graph.terminal.remove(&id);
+        graph.poisoned.remove(&id);
+        graph.failures.remove(&id);
+        graph.records.remove(&id);
+        graph.lazy_edges.retain(|&(_, task)| task != id);
+        graph.offloads.forget(id);
+        graph.errors.forget(id);
+    }
+
+    /// Drops everything registered for key `id` as the output of a removed
+    /// task.
+    fn forget_output(&mut self, id: TypeId) {
+        let graph = &mut self.graph;
+        graph.output_hooks.remove(&id);
+        graph.evictable.retain(|e| e.key.id != id);
+        graph.evicted.remove(&id);
+        graph.memory.forget(id);
+        graph.provenance.forget(id);
+    }
+
+    fn remove_node(&mut self, node: NodeIndex) {
+        let last = NodeIndex::new(self.graph.tasks.node_count() - 1);
+        let (ty, last_ty) = (self.graph.tasks[node], self.graph.tasks[last]);
+        self.graph.tasks.remove_node(node);
+        self.graph.reachability.invalidate();
+        if self.graph.nodes.get(&ty) == Some(&node) {
+            match self
+                .graph
+                .tasks
+                .node_indices()
+                .find(|i| self.graph.tasks[*i] == ty)
+            {
+                Some(other) => self.graph.nodes.insert(ty, other),
+                None => self.graph.nodes.remove(&ty),
+            };
+        }
+        if self.graph.nodes.get(&last_ty) == Some(&last) {
+            self.graph.nodes.insert(last_ty, node);
+        }
+        for entry in &mut self.graph.entries {
+            if entry.input_node == last {
+                entry.input_node = node;
+            }
+            if entry.output_node == last {
+                entry.output_node = node;
+            }
+        }
+    }
+
+    /// Replaces task `Old` with `New`, which reads and writes the same types.
+    /// `New` takes `Old`'s place in the execution order and keeps its edges.
+    pub fn replace_task<Old, New>(&mut self) -> Result<&mut Self, TaskEditError>
+    where
+        Old: TaskWithContext<Db, Ctx>,
+        New: TaskWithContext<Db, Ctx, Input = Old::Input, Output = Old::Output>,
+    {
+        let index = self.entry_index::<Old>()?;
+        let old = &self.graph.entries[index];
+        let entry = TaskEntry {
+            id: TypeId::of::<New>(),
+            name: New::name(),
+            run: |graph, _| graph.execute::<New>().map(|_| ()),
+            ..old.clone()
+        };
+        for key in &entry.writes {
+            self.graph.producers.insert(key.id, type_name::<New>());
+        }
+        self.graph.entries[index] = entry;
+        self.forget_task(TypeId::of::<Old>());
+        self.graph
+            .names
+            .insert(TypeId::of::<New>(), type_name::<New>());
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        swap::FnTask, DbKey, InMemoryDb, KeyType, MissingDependency, Task, TaskInput, TaskOutput,
+    };
+
+    struct Pixels;
+
+    impl DbKey for Pixels {
+        type Value = Vec<u8>;
+    }
+
+    struct Image(Vec<u8>);
+
+    impl DbKey for Image {
+        type Value = Image;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for Image {
+        fn from_db(db: &Db) -> Self {
+            Image(db.get::<Pixels>().unwrap().clone())
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Pixels>()]
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Thumbnail(Vec<u8>, &'static str);
+
+    impl DbKey for Thumbnail {
+        type Value = Thumbnail;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for Thumbnail {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<Thumbnail>(Thumbnail(self.0.clone(), self.1));
+        }
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for Thumbnail {
+        fn from_db(db: &Db) -> Self {
+            let thumbnail = db.get::<Thumbnail>().unwrap();
+            Thumbnail(thumbnail.0.clone(), thumbnail.1)
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Thumbnail>()]
+        }
+    }
+
+    struct CpuResize;
+
+    impl Task<InMemoryDb> for CpuResize {
+        type Input = Image;
+        type Output = Thumbnail;
+
+        fn execute(input: Self::Input) -> Self::Output {
+            Thumbnail(input.0.into_iter().step_by(2).collect(), "cpu")
+        }
+    }
+
+    struct GpuResize;
+
+    impl Task<InMemoryDb> for GpuResize {
+        type Input = Image;
+        type Output = Thumbnail;
+
+        fn execute(input: Self::Input) -> Self::Output {
+            Thumbnail(input.0.into_iter().step_by(2).collect(), "gpu")
+        }
+    }
+
+    struct Upload;
+
+    impl Task<InMemoryDb> for Upload {
+        type Input = Thumbnail;
+        type Output = ();
+
+        fn execute(_input: Self::Input) -> Self::Output {}
+    }
+
+    fn base() -> ExecutionGraphBuilder<InMemoryDb> {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_input::<Pixels>(vec![1, 2, 3, 4])
+            .unwrap()
+            .add_task::<CpuResize>()
+            .unwrap()
+            .add_task::<Upload>()
+            .unwrap();
+        builder
+    }
+
+    #[test]
+    fn test_replace_task() {
+        let mut builder = base();
+        builder.replace_task::<CpuResize, GpuResize>().unwrap();
+        let mut graph = builder.build();
+        assert_eq!(
+            graph.task_names().collect::<Vec<_>>(),
+            vec!["GpuResize", "Upload"]
+        );
+        graph.execute_all().unwrap();
+        assert_eq!(
+            graph.db().get::<Thumbnail>(),
+            Some(&Thumbnail(vec![1, 3], "gpu"))
+        );
+    }
+
+    #[test]
+    fn test_remove_task() {
+        let mut builder = base();
+        assert!(matches!(
+            builder.remove_task::<CpuResize>(),
+            Err(TaskEditError::InUse { .. })
+        ));
+        builder
+            .remove_task::<Upload>()
+            .unwrap()
+            .remove_task::<CpuResize>()
+            .unwrap();
+        assert_eq!(
+            builder.remove_task::<CpuResize>().err(),
+            Some(TaskEditError::UnknownTask {
+                task: type_name::<CpuResize>()
+            })
+        );
+        let graph = builder.build();
+        assert_eq!(graph.describe().nodes, vec![type_name::<Pixels>()]);
+        assert!(graph.describe().edges.is_empty());
+    }
+
+    #[test]
+    fn test_removed_nodes_are_unindexed() {
+        let mut builder = base();
+        builder.remove_task::<Upload>().unwrap();
+        builder.remove_task::<CpuResize>().unwrap();
+        assert!(matches!(
+            builder.add_task::<Upload>(),
+            Err(MissingDependency { .. })
+        ));
+        builder
+            .add_task::<CpuResize>()
+            .unwrap()
+            .add_task::<Upload>()
+            .unwrap();
+        let mut graph = builder.build();
+        graph.execute_all().unwrap();
+        assert_eq!(graph.db().get::<Thumbnail>().map(|t| t.1), Some("cpu"));
+    }
+
+    #[test]
+    fn test_remove_and_re_add_task() {
+        let mut builder = base();
+        builder
+            .graph
+            .swap_task::<CpuResize>(Box::new(FnTask::new(|input: Image| {
+                Thumbnail(input.0, "swapped")
+            })))
+            .unwrap();
+        builder.on_output::<Thumbnail>(|_| Err("stale hook".to_string()));
+        builder
+            .remove_task::<Upload>()
+            .unwrap()
+            .remove_task::<CpuResize>()
+            .unwrap()
+            .add_task::<CpuResize>()
+            .unwrap()
+            .add_task::<Upload>()
+            .unwrap();
+        let mut graph = builder.build();
+        graph.execute_all().unwrap();
+        assert_eq!(
+            graph.db().get::<Thumbnail>(),
+            Some(&Thumbnail(vec![1, 3], "cpu"))
+        );
+    }
+}
@@ -0,0 +1,633 @@
+use std::fmt;
+
+use crate::{limits::BudgetLimit, provenance::Provenance, KeyType};
+
+/// A task depends on a key that no registered input or task produces.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct MissingDependency {
+    /// Type name of the missing key.
+    pub key: &'static str,
+    /// Type name of the task that required it.
+    pub task: &'static str,
+    /// Every key produced at the time of the failure, as `(key, producer)`.
+    pub producers: Vec<(String, String)>,
+}
+
+impl fmt::Display for MissingDependency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "task `{}` depends on `{}`, which nothing produces; \
+             did you forget add_input/add_task for `{}`?",
+            self.task, self.key, self.key
+        )?;
+        if self.producers.is_empty() {
+            write!(f, "\nno producers are registered")
+        } else {
+            write!(f, "\nregistered producers:")?;
+            for (key, producer) in &self.producers {
+                write!(f, "\n  {key} <- {producer}")?;
+            }
+            Ok(())
+        }
+    }
+}
+
+impl std::error::Error for MissingDependency {}
+
+/// An input value was rejected by the validator registered for its key.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct InvalidInput {
+    /// Type name of the input key.
+    pub key: &'static str,
+    pub message: String,
+}
+
+impl fmt::Display for InvalidInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid value for input `{}`: {}",
+            self.key, self.message
+        )
+    }
+}
+
+impl std::error::Error for InvalidInput {}
+
+/// Why [`execute_all_with`](crate::ExecutionGraph::execute_all_with) failed.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ExecuteWithError {
+    /// One of the inputs was rejected, so the run did not start.
+    InvalidInput(InvalidInput),
+    Execution(ExecutionError),
+}
+
+impl fmt::Display for ExecuteWithError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExecuteWithError::InvalidInput(e) => e.fmt(f),
+            ExecuteWithError::Execution(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ExecuteWithError {}
+
+impl From<InvalidInput> for ExecuteWithError {
+    fn from(e: InvalidInput) -> Self {
+        ExecuteWithError::InvalidInput(e)
+    }
+}
+
+impl From<ExecutionError> for ExecuteWithError {
+    fn from(e: ExecutionError) -> Self {
+        ExecuteWithError::Execution(e)
+    }
+}
+
+/// A hook registered with
+/// [`on_output`](crate::ExecutionGraphBuilder::on_output) rejected the value a
+/// task wrote.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct OutputAssertionFailed {
+    /// Type name of the task that wrote the value.
+    pub task: &'static str,
+    /// Type name of the checked key.
+    pub key: &'static str,
+    pub message: String,
+}
+
+impl fmt::Display for OutputAssertionFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "output `{}` of task `{}` failed an assertion: {}",
+            self.key, self.task, self.message
+        )
+    }
+}
+
+impl std::error::Error for OutputAssertionFailed {}
+
+/// A task panicked while
+/// [`catch_panics`](crate::ExecutionGraph::catch_panics) was enabled.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TaskPanicked {
+    /// Type name of the task.
+    pub task: &'static str,
+    /// The panic message, if the payload was a string.
+    pub payload: String,
+    /// Backtrace captured at the point of the panic.
+    pub backtrace: String,
+}
+
+impl fmt::Display for TaskPanicked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "task `{}` panicked: {}", self.task, self.payload)
+    }
+}
+
+impl std::error::Error for TaskPanicked {}
+
+/// A task was refused because a key it reads was written by a task that
+/// failed.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Poisoned {
+    /// Type name of the task that was refused.
+    pub task: &'static str,
+    /// The poisoned key.
+    pub key: KeyType,
+}
+
+impl fmt::Display for Poisoned {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "task `{}` reads `{}`, which is poisoned by an earlier failure",
+            self.task, self.key
+        )
+    }
+}
+
+impl std::error::Error for Poisoned {}
+
+/// A [`FixpointGroup`](crate::fixpoint::FixpointGroup) was still changing
+/// after its maximum number of iterations.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct NotConverged {
+    /// Type name of the group's first task.
+    pub task: &'static str,
+    pub iterations: usize,
+}
+
+impl fmt::Display for NotConverged {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "fixpoint group of `{}` did not converge after {} iterations",
+            self.task, self.iterations
+        )
+    }
+}
+
+impl std::error::Error for NotConverged {}
+
+/// A run went over a limit of its
+/// [`ExecutorConfig`](crate::limits::ExecutorConfig) and was stopped before
+/// running `task`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BudgetExceeded {
+    /// Type name of the task that was refused.
+    pub task: &'static str,
+    pub limit: BudgetLimit,
+    /// Names of the tasks the run executed, in the order they were added.
+    pub completed: Vec<&'static str>,
+    /// Names of the other tasks of the graph, which the run did not reach.
+    pub pending: Vec<&'static str>,
+}
+
+impl fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.limit {
+            BudgetLimit::Tasks(max) => write!(f, "run exceeded its limit of {max} tasks")?,
+            BudgetLimit::WallTime(max) => write!(f, "run exceeded its limit of {max:?}")?,
+        }
+        write!(
+            f,
+            " before task `{}` ({} completed, {} pending)",
+            self.task,
+            self.completed.len(),
+            self.pending.len()
+        )
+    }
+}
+
+impl std::error::Error for BudgetExceeded {}
+
+/// A task could not be removed from or replaced in a builder.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum TaskEditError {
+    /// The task was never added.
+    UnknownTask { task: &'static str },
+    /// Other tasks read a key the task writes.
+    InUse {
+        task: &'static str,
+        key: &'static str,
+        dependents: Vec<&'static str>,
+    },
+    /// A replacement implementation does not return the task's output type.
+    OutputMismatch {
+        task: &'static str,
+        expected: &'static str,
+    },
+}
+
+impl fmt::Display for TaskEditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TaskEditError::UnknownTask { task } => write!(f, "task `{task}` was never added"),
+            TaskEditError::InUse {
+                task,
+                key,
+                dependents,
+            } => write!(
+                f,
+                "cannot remove task `{task}`: `{key}` is read by {}",
+                dependents.join(", ")
+            ),
+            TaskEditError::OutputMismatch { task, expected } => write!(
+                f,
+                "replacement for task `{task}` does not return its output `{expected}`"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TaskEditError {}
+
+/// Tasks could not be added with
+/// [`add_tasks`](crate::ExecutionGraphBuilder::add_tasks).
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum AddTasksError {
+    /// A task reads a key nothing produces.
+    MissingDependency(MissingDependency),
+    /// A task writes a key that is already an input or produced by a task.
+    DuplicateOutput {
+        task: &'static str,
+        key: &'static str,
+    },
+}
+
+impl fmt::Display for AddTasksError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddTasksError::MissingDependency(e) => e.fmt(f),
+            AddTasksError::DuplicateOutput { task, key } => {
+                write!(f, "task `{task}` writes `{key}`, which is already produced")
+            }
+        }
+    }
+}
+
+impl std::error::Error for AddTasksError {}
+
+impl From<MissingDependency> for AddTasksError {
+    fn from(e: MissingDependency) -> Self {
+        AddTasksError::MissingDependency(e)
+    }
+}
+
+/// A [`FixpointGroup`](crate::fixpoint::FixpointGroup) could not be added.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum FixpointError {
+    /// A task of the group reads a key nothing produces.
+    MissingDependency(MissingDependency),
+    /// A key the group seeds is already an input or produced by a task.
+    SeedExists { key: &'static str },
+    /// A task of the group writes a key something else produces.
+    DuplicateOutput {
+        task: &'static str,
+        key: &'static str,
+    },
+}
+
+impl fmt::Display for FixpointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FixpointError::MissingDependency(e) => e.fmt(f),
+            FixpointError::SeedExists { key } => {
+                write!(f, "fixpoint seed `{key}` is already produced")
+            }
+            FixpointError::DuplicateOutput { task, key } => {
+                write!(f, "task `{task}` writes `{key}`, which is already produced")
+            }
+        }
+    }
+}
+
+impl std::error::Error for FixpointError {}
+
+impl From<MissingDependency> for FixpointError {
+    fn from(e: MissingDependency) -> Self {
+        FixpointError::MissingDependency(e)
+    }
+}
+
+impl From<AddTasksError> for FixpointError {
+    fn from(e: AddTasksError) -> Self {
+        match e {
+            AddTasksError::MissingDependency(e) => FixpointError::MissingDependency(e),
+            AddTasksError::DuplicateOutput { task, key } => {
+                FixpointError::DuplicateOutput { task, key }
+            }
+        }
+    }
+}
+
+/// A task's output declares a key, in
+/// [`out_types`](crate::TaskOutput::out_types), that the task did not write.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct MissingOutput {
+    /// Type name of the task.
+    pub task: &'static str,
+    /// Type name of the missing key.
+    pub key: &'static str,
+}
+
+impl fmt::Display for MissingOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "task `{}` declares the output `{}`, but its to_db did not write it",
+            self.task, self.key
+        )
+    }
+}
+
+impl std::error::Error for MissingOutput {}
+
+/// A swapped-in implementation or stub of a task returned a value that is
+/// not the task's output type.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct OutputTypeMismatch {
+    /// Type name of the task.
+    pub task: &'static str,
+    /// Type name of the task's output type.
+    pub expected: &'static str,
+}
+
+impl fmt::Display for OutputTypeMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the implementation of task `{}` did not return a `{}`",
+            self.task, self.expected
+        )
+    }
+}
+
+impl std::error::Error for OutputTypeMismatch {}
+
+/// A task replaced the value of a key produced by another task or an input,
+/// with [`strict_writes`](crate::ExecutionGraph::strict_writes) enabled.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Overwrite {
+    /// Type name of the task that wrote the value.
+    pub task: &'static str,
+    /// Type name of the key.
+    pub key: &'static str,
+    /// The key's registered producer.
+    pub producer: &'static str,
+}
+
+impl fmt::Display for Overwrite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "task `{}` overwrote `{}`, which is produced by `{}`",
+            self.task, self.key, self.producer
+        )
+    }
+}
+
+impl std::error::Error for Overwrite {}
+
+/// A task wrote or removed an input, with
+/// [`immutable_inputs`](crate::ExecutionGraph::immutable_inputs) enabled.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct InputWritten {
+    /// Type name of the task.
+    pub task: &'static str,
+    /// Type name of the input key.
+    pub key: &'static str,
+}
+
+impl fmt::Display for InputWritten {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "task `{}` wrote the input `{}`; inputs can only be changed with set_input",
+            self.task, self.key
+        )
+    }
+}
+
+impl std::error::Error for InputWritten {}
+
+/// A database holds a value for a key, but not of the key's value type.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TypeMismatch {
+    /// Type name of the key.
+    pub key: &'static str,
+    /// Type name of the key's value type.
+    pub expected: &'static str,
+    /// Type name of the value stored, if the database knows it.
+    pub stored: Option<&'static str>,
+}
+
+impl fmt::Display for TypeMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` holds a value of type `{}`, not the expected `{}`",
+            self.key,
+            self.stored.unwrap_or("unknown"),
+            self.expected
+        )
+    }
+}
+
+impl std::error::Error for TypeMismatch {}
+
+/// Any reason executing a task can fail.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ExecutionError {
+    MissingDependency(MissingDependency),
+    OutputAssertionFailed(OutputAssertionFailed),
+    TaskPanicked(TaskPanicked),
+    Poisoned(Poisoned),
+    NotConverged(NotConverged),
+    BudgetExceeded(BudgetExceeded),
+    Overwrite(Overwrite),
+    InputWritten(InputWritten),
+    MissingOutput(MissingOutput),
+    OutputTypeMismatch(OutputTypeMismatch),
+}
+
+impl ExecutionError {
+    /// Type name of the task that failed.
+    pub fn task(&self) -> &'static str {
+        match self {
+            ExecutionError::MissingDependency(e) => e.task,
+            ExecutionError::OutputAssertionFailed(e) => e.task,
+            ExecutionError::TaskPanicked(e) => e.task,
+            ExecutionError::Poisoned(e) => e.task,
+            ExecutionError::NotConverged(e) => e.task,
+            ExecutionError::BudgetExceeded(e) => e.task,
+            ExecutionError::Overwrite(e) => e.task,
+            ExecutionError::InputWritten(e) => e.task,
+            ExecutionError::MissingOutput(e) => e.task,
+            ExecutionError::OutputTypeMismatch(e) => e.task,
+        }
+    }
+}
+
+impl fmt::Display for ExecutionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExecutionError::MissingDependency(e) => e.fmt(f),
+            ExecutionError::OutputAssertionFailed(e) => e.fmt(f),
+            ExecutionError::TaskPanicked(e) => e.fmt(f),
+            ExecutionError::Poisoned(e) => e.fmt(f),
+            ExecutionError::NotConverged(e) => e.fmt(f),
+            ExecutionError::BudgetExceeded(e) => e.fmt(f),
+            ExecutionError::Overwrite(e) => e.fmt(f),
+            ExecutionError::InputWritten(e) => e.fmt(f),
+            ExecutionError::MissingOutput(e) => e.fmt(f),
+            ExecutionError::OutputTypeMismatch(e) => e.fmt(f),
+        }
+    }
+}
+
+/// Transparent: `Display` already shows the wrapped error, so it is not
+/// reported again as the source.
+impl std::error::Error for ExecutionError {}
+
+impl From<MissingDependency> for ExecutionError {
+    fn from(e: MissingDependency) -> Self {
+        ExecutionError::MissingDependency(e)
+    }
+}
+
+impl From<OutputAssertionFailed> for ExecutionError {
+    fn from(e: OutputAssertionFailed) -> Self {
+        ExecutionError::OutputAssertionFailed(e)
+    }
+}
+
+impl From<TaskPanicked> for ExecutionError {
+    fn from(e: TaskPanicked) -> Self {
+        ExecutionError::TaskPanicked(e)
+    }
+}
+
+impl From<Poisoned> for ExecutionError {
+    fn from(e: Poisoned) -> Self {
+        ExecutionError::Poisoned(e)
+    }
+}
+
+impl From<NotConverged> for ExecutionError {
+    fn from(e: NotConverged) -> Self {
+        ExecutionError::NotConverged(e)
+    }
+}
+
+impl From<BudgetExceeded> for ExecutionError {
+    fn from(e: BudgetExceeded) -> Self {
+        ExecutionError::BudgetExceeded(e)
+    }
+}
+
+impl From<Overwrite> for ExecutionError {
+    fn from(e: Overwrite) -> Self {
+        ExecutionError::Overwrite(e)
+    }
+}
+
+impl From<InputWritten> for ExecutionError {
+    fn from(e: InputWritten) -> Self {
+        ExecutionError::InputWritten(e)
+    }
+}
+
+impl From<MissingOutput> for ExecutionError {
+    fn from(e: MissingOutput) -> Self {
+        ExecutionError::MissingOutput(e)
+    }
+}
+
+impl From<OutputTypeMismatch> for ExecutionError {
+    fn from(e: OutputTypeMismatch) -> Self {
+        ExecutionError::OutputTypeMismatch(e)
+    }
+}
+
+/// An [`ExecutionError`] together with the failures that led to it, built by
+/// [`ExecutionGraph::explain`](crate::ExecutionGraph::explain).
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct GraphRunError {
+    /// Never empty.
+    pub(crate) chain: Vec<ExecutionError>,
+    /// The inputs behind each error of `chain`, as long as `chain`.
+    pub(crate) inputs: Vec<Provenance>,
+}
+
+impl GraphRunError {
+    /// The error that was returned first; each following error is the
+    /// failure of the task that poisoned an input of the one before, ending
+    /// with the root cause.
+    pub fn chain(&self) -> &[ExecutionError] {
+        &self.chain
+    }
+
+    pub fn root_cause(&self) -> &ExecutionError {
+        self.chain.last().unwrap()
+    }
+
+    /// For each error of [`chain`](Self::chain), the inputs behind the keys
+    /// the failed task read, with their revisions when it failed. Empty for
+    /// an error the graph did not record.
+    pub fn inputs(&self) -> &[Provenance] {
+        &self.inputs
+    }
+
+    /// Type names of the failed tasks, from the one that was run to the
+    /// root cause.
+    pub fn tasks(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.chain.iter().map(ExecutionError::task)
+    }
+}
+
+impl fmt::Display for GraphRunError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for error in &self.chain[..self.chain.len() - 1] {
+            write!(f, "task `{}` failed because ", error.task())?;
+        }
+        self.root_cause().fmt(f)?;
+        let inputs = &self.inputs[self.inputs.len() - 1].inputs;
+        for (i, (key, revision)) in inputs.iter().enumerate() {
+            let separator = if i == 0 { " (from " } else { ", " };
+            write!(f, "{}`{}` at revision {}", separator, key, revision)?;
+        }
+        if !inputs.is_empty() {
+            f.write_str(")")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for GraphRunError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(self.root_cause())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use super::*;
+
+    #[test]
+    fn test_execution_error_is_transparent() {
+        let inner = MissingOutput {
+            task: "Publish",
+            key: "Report",
+        };
+        let error = ExecutionError::from(inner.clone());
+        assert_eq!(error.to_string(), inner.to_string());
+        assert!(error.source().is_none());
+    }
+}
@@ -0,0 +1,305 @@
+use std::{
+    collections::HashSet,
+    fs, io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use crate::{
+    fingerprint::Fingerprint, DataBase, DbKey, ExecutionGraph, ExecutionGraphBuilder, KeyType,
+};
+
+/// The stored value of a [`FileKey`]: where the file lives and what it
+/// contained when it was last hashed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileState {
+    pub path: PathBuf,
+    pub modified: Option<SystemTime>,
+    pub hash: u64,
+}
+
+impl FileState {
+    pub fn read(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let modified = fs::metadata(&path)?.modified().ok();
+        let hash = hash_file(&path)?;
+        Ok(FileState {
+            path,
+            modified,
+            hash,
+        })
+    }
+}
+
+fn hash_file(path: &Path) -> io::Result<u64> {
+    Ok(fs::read(path)?.fingerprint())
+}
+
+/// A key whose value tracks a file on disk, registered with
+/// [`ExecutionGraphBuilder::add_file`].
+pub trait FileKey: DbKey<Value = FileState> {}
+
+impl<K: DbKey<Value = FileState>> FileKey for K {}
+
+pub(crate) struct FileEntry<Db> {
+    key: KeyType,
+    path: PathBuf,
+    /// Re-hashes the file at the path if it changed, or hashes it if the
+    /// database has no state for it, returning whether its content changed.
+    refresh: fn(&mut Db, &Path) -> io::Result<bool>,
+}
+
+impl<Db> Clone for FileEntry<Db> {
+    fn clone(&self) -> Self {
+        FileEntry {
+            key: self.key,
+            path: self.path.clone(),
+            refresh: self.refresh,
+        }
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraphBuilder<Db, Ctx> {
+    /// Hashes the file at `path` and registers it as the input `K`.
+    pub fn add_file<K: FileKey>(&mut self, path: impl Into<PathBuf>) -> io::Result<&mut Self> {
+        let state = FileState::read(path)?;
+        let path = state.path.clone();
+        self.graph.with_shared_db(|graph| graph.db.put::<K>(state));
+        if self.graph.contains_node(&KeyType::of::<K>().id).is_none() {
+            self.graph.register_input::<K>("add_file");
+        }
+        self.graph.files.push(FileEntry {
+            key: KeyType::of::<K>(),
+            path,
+            refresh: |db, path| {
+                let modified = fs::metadata(path)?.modified().ok();
+                let state = db.get::<K>();
+                if modified.is_some() && state.is_some_and(|state| state.modified == modified) {
+                    return Ok(false);
+                }
+                let hash = hash_file(path)?;
+                let changed = state.is_none_or(|state| state.hash != hash);
+                db.put::<K>(FileState {
+                    path: path.to_path_buf(),
+                    modified,
+                    hash,
+                });
+                Ok(changed)
+            },
+        });
+        Ok(self)
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraph<Db, Ctx> {
+    /// Re-hashes every file registered with
+    /// [`add_file`](ExecutionGraphBuilder::add_file) whose modification time
+    /// changed. Files whose content changed are updated, and the outputs of
+    /// tasks depending on them, directly or not, are invalidated.
+    ///
+    /// Returns the keys of the files that changed, including files the
+    /// database has no state for yet, as in a graph copied with
+    /// [`clone_structure`](Self::clone_structure). If a file cannot be read,
+    /// e.g. because it was deleted, the other files are still refreshed and
+    /// the first error is returned.
+    pub fn refresh_files(&mut self) -> io::Result<Vec<KeyType>> {
+        let refreshes = self
+            .files
+            .iter()
+            .cloned()
+            .map(|file| (file.key, move |db: &mut Db| (file.refresh)(db, &file.path)))
+            .collect();
+        self.refresh_inputs(refreshes)
+    }
+
+    /// Runs every refresh, finishing the pass even if some fail, and
+    /// invalidates the dependents of the keys that changed.
+    pub(crate) fn refresh_inputs(
+        &mut self,
+        refreshes: Vec<(KeyType, impl FnOnce(&mut Db) -> io::Result<bool>)>,
+    ) -> io::Result<Vec<KeyType>> {
+        self.with_shared_db(|graph| {
+            let mut changed = Vec::new();
+            let mut error = None;
+            for (key, refresh) in refreshes {
+                match refresh(&mut graph.db) {
+                    Ok(true) => changed.push(key),
+                    Ok(false) => {}
+                    Err(e) => {
+                        error.get_or_insert(e);
+                    }
+                }
+            }
+            for key in &changed {
+                graph.bump_revision(*key);
+            }
+            graph.invalidate_dependents(&changed);
+            #[cfg(feature = "web-ui")]
+            graph.publish_web_ui();
+            match error {
+                Some(e) => Err(e),
+                None => Ok(changed),
+            }
+        })
+    }
+
+    pub(crate) fn invalidate_dependents(&mut self, keys: &[KeyType]) {
+        let mut dirty: HashSet<KeyType> = keys.iter().copied().collect();
+        for entry in &self.entries {
+            if entry.deps.iter().any(|dep| dirty.contains(dep)) {
+                entry.invalidate(&mut self.db);
+                dirty.extend(entry.writes.iter().copied());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemoryDb, Task, TaskInput, TaskOutput};
+
+    struct Manifest;
+
+    impl DbKey for Manifest {
+        type Value = FileState;
+    }
+
+    struct ManifestText(String);
+
+    impl DbKey for ManifestText {
+        type Value = ManifestText;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for ManifestText {
+        fn from_db(db: &Db) -> Self {
+            ManifestText(fs::read_to_string(&db.get::<Manifest>().unwrap().path).unwrap())
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Manifest>()]
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct LineCount(usize);
+
+    impl DbKey for LineCount {
+        type Value = LineCount;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for LineCount {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<LineCount>(LineCount(self.0));
+        }
+    }
+
+    struct CountLines;
+
+    impl Task<InMemoryDb> for CountLines {
+        type Input = ManifestText;
+        type Output = LineCount;
+
+        fn execute(input: Self::Input) -> Self::Output {
+            LineCount(input.0.lines().count())
+        }
+    }
+
+    struct Lockfile;
+
+    impl DbKey for Lockfile {
+        type Value = FileState;
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("cg-{name}-{}", std::process::id()))
+    }
+
+    fn touch_later(path: &Path) {
+        let file = fs::File::options().write(true).open(path).unwrap();
+        file.set_modified(SystemTime::now() + std::time::Duration::from_secs(1))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_refresh_files() {
+        let path = temp_path("refresh-files");
+        fs::write(&path, "a\nb\n").unwrap();
+
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_file::<Manifest>(&path)
+            .unwrap()
+            .add_task::<CountLines>()
+            .unwrap();
+        let mut graph = builder.build();
+        graph.execute_all().unwrap();
+        assert_eq!(graph.refresh_files().unwrap(), vec![]);
+        assert_eq!(graph.db().get::<LineCount>(), Some(&LineCount(2)));
+
+        fs::write(&path, "a\nb\nc\n").unwrap();
+        touch_later(&path);
+        assert_eq!(
+            graph.refresh_files().unwrap(),
+            vec![KeyType::of::<Manifest>()]
+        );
+        assert_eq!(graph.db().get::<LineCount>(), None);
+
+        graph.execute_all().unwrap();
+        assert_eq!(graph.db().get::<LineCount>(), Some(&LineCount(3)));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_refresh_with_missing_file() {
+        let (manifest, lockfile) = (temp_path("manifest"), temp_path("lockfile"));
+        fs::write(&manifest, "a\n").unwrap();
+        fs::write(&lockfile, "").unwrap();
+
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_file::<Manifest>(&manifest)
+            .unwrap()
+            .add_file::<Lockfile>(&lockfile)
+            .unwrap()
+            .add_task::<CountLines>()
+            .unwrap();
+        let mut graph = builder.build();
+        graph.execute_all().unwrap();
+
+        fs::write(&manifest, "a\nb\n").unwrap();
+        touch_later(&manifest);
+        fs::remove_file(&lockfile).unwrap();
+        assert!(graph.refresh_files().is_err());
+        assert_eq!(graph.db().get::<LineCount>(), None);
+
+        graph.execute_all().unwrap();
+        assert_eq!(graph.db().get::<LineCount>(), Some(&LineCount(2)));
+        fs::remove_file(&manifest).unwrap();
+    }
+
+    #[test]
+    fn test_refresh_cloned_graph() {
+        let path = temp_path("cloned");
+        fs::write(&path, "a\nb\n").unwrap();
+
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_file::<Manifest>(&path)
+            .unwrap()
+            .add_task::<CountLines>()
+            .unwrap();
+        let mut graph = builder.build();
+        graph.execute_all().unwrap();
+
+        let mut clone = graph.clone_structure(InMemoryDb::new());
+        assert_eq!(
+            clone.refresh_files().unwrap(),
+            vec![KeyType::of::<Manifest>()]
+        );
+        clone.execute_all().unwrap();
+        assert_eq!(clone.db().get::<LineCount>(), Some(&LineCount(2)));
+        assert!(clone.refresh_files().unwrap().is_empty());
+        fs::remove_file(&path).unwrap();
+    }
+}
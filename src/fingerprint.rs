@@ -0,0 +1,69 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+/// A cheap summary of a value, used instead of comparing whole values to tell
+/// whether an input changed. Equal values must have equal fingerprints.
+///
+/// Every `Hash` type is fingerprinted by hashing it, so `#[derive(Hash)]` is
+/// enough to get one. Types that are not `Hash`, or that know a cheaper
+/// summary such as a version counter, can implement it directly.
+/// Fingerprints are stable within a build but not across Rust versions, so
+/// they should not be persisted.
+pub trait Fingerprint {
+    fn fingerprint(&self) -> u64;
+}
+
+impl<T: Hash + ?Sized> Fingerprint for T {
+    fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+/// The fingerprint of a sequence of values, in order.
+pub fn fingerprint_all<'a, T: Fingerprint + 'a>(values: impl IntoIterator<Item = &'a T>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for value in values {
+        hasher.write_u64(value.fingerprint());
+    }
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Hash)]
+    struct Document {
+        path: &'static str,
+        text: String,
+    }
+
+    #[test]
+    fn test_derived_fingerprint() {
+        let a = Document {
+            path: "a.md",
+            text: "hello".to_string(),
+        };
+        let b = Document {
+            path: "a.md",
+            text: "hello".to_string(),
+        };
+        assert_eq!(a.fingerprint(), b.fingerprint());
+
+        let c = Document {
+            path: "a.md",
+            text: "hello!".to_string(),
+        };
+        assert_ne!(a.fingerprint(), c.fingerprint());
+    }
+
+    #[test]
+    fn test_fingerprint_all_is_ordered() {
+        assert_eq!(fingerprint_all(&[1, 2, 3]), fingerprint_all(&vec![1, 2, 3]));
+        assert_ne!(fingerprint_all(&[1, 2, 3]), fingerprint_all(&[3, 2, 1]));
+    }
+}
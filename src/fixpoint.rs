@@ -0,0 +1,371 @@
+use std::{
+    any::{type_name, Any, TypeId},
+    rc::Rc,
+};
+
+use crate::{
+    descriptor::TaskDescriptor, error::NotConverged, DataBase, DbKey, ExecutionError,
+    ExecutionGraph, ExecutionGraphBuilder, FixpointError, KeyType, TaskWithContext,
+};
+
+type Run<Db, Ctx> = fn(&mut ExecutionGraph<Db, Ctx>, TypeId) -> Result<(), ExecutionError>;
+type Converged<Db> = Rc<dyn Fn(&dyn Any, &Db) -> bool>;
+
+struct Seed<Db> {
+    key: KeyType,
+    reset: Rc<dyn Fn(&mut Db)>,
+    snapshot: fn(&Db) -> Box<dyn Any>,
+    converged: Converged<Db>,
+}
+
+impl<Db> Clone for Seed<Db> {
+    fn clone(&self) -> Self {
+        Seed {
+            key: self.key,
+            reset: self.reset.clone(),
+            snapshot: self.snapshot,
+            converged: self.converged.clone(),
+        }
+    }
+}
+
+/// A set of mutually dependent tasks, run in order again and again until
+/// their seeded keys stop changing. Added with
+/// [`ExecutionGraphBuilder::add_fixpoint`].
+///
+/// A task of the group may read a key written by a later one if the key is
+/// [seeded](Self::seed); each run of the group starts again from the seeds.
+pub struct FixpointGroup<Db: DataBase, Ctx = ()> {
+    tasks: Vec<TaskDescriptor<Db, Ctx>>,
+    members: Vec<(TypeId, &'static str, Run<Db, Ctx>)>,
+    seeds: Vec<Seed<Db>>,
+    max_iterations: usize,
+}
+
+impl<Db: DataBase, Ctx> FixpointGroup<Db, Ctx> {
+    pub fn new() -> Self {
+        FixpointGroup {
+            tasks: Vec::new(),
+            members: Vec::new(),
+            seeds: Vec::new(),
+            max_iterations: 100,
+        }
+    }
+
+    /// Adds `T` to the group. Running it through [`execute_all`] or its
+    /// [`TaskHandle`] runs the whole group, while
+    /// [`ExecutionGraph::execute`] still runs `T` alone, once.
+    ///
+    /// [`execute_all`]: ExecutionGraph::execute_all
+    /// [`TaskHandle`]: crate::handle::TaskHandle
+    pub fn task<T: TaskWithContext<Db, Ctx>>(mut self) -> Self {
+        let mut task = TaskDescriptor::of::<T>();
+        self.members
+            .push((TypeId::of::<T>(), type_name::<T>(), task.entry.run));
+        task.entry.run = |graph, id| graph.run_fixpoint(id);
+        self.tasks.push(task);
+        self
+    }
+
+    /// Seeds `K` with `initial`. The group has converged once an iteration
+    /// leaves every seeded key equal to its previous value.
+    pub fn seed<K: DbKey>(self, initial: K::Value) -> Self
+    where
+        K::Value: Clone + PartialEq,
+    {
+        self.seed_with::<K>(initial, PartialEq::eq)
+    }
+
+    /// Like [`seed`](Self::seed), with `converged(previous, current)`
+    /// deciding whether `K` has stopped changing.
+    pub fn seed_with<K: DbKey>(
+        mut self,
+        initial: K::Value,
+        converged: impl Fn(&K::Value, &K::Value) -> bool + 'static,
+    ) -> Self
+    where
+        K::Value: Clone,
+    {
+        self.seeds.push(Seed {
+            key: KeyType::of::<K>(),
+            reset: Rc::new(move |db| {
+                db.put::<K>(initial.clone());
+            }),
+            snapshot: |db| Box::new(db.get_cloned::<K>()),
+            converged: Rc::new(move |previous, db| {
+                let previous = previous.downcast_ref::<Option<K::Value>>().unwrap();
+                match (previous, db.get::<K>()) {
+                    (Some(previous), Some(current)) => converged(previous, current),
+                    (previous, current) => previous.is_none() && current.is_none(),
+                }
+            }),
+        });
+        self
+    }
+
+    /// The most iterations before the group fails with
+    /// [`ExecutionError::NotConverged`]. 100 by default.
+    pub fn max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+}
+
+impl<Db: DataBase, Ctx> Default for FixpointGroup<Db, Ctx> {
+    fn default() -> Self {
+        FixpointGroup::new()
+    }
+}
+
+/// A group as stored in the graph, under the id of each of its tasks.
+pub(crate) struct Fixpoint<Db: DataBase, Ctx> {
+    members: Vec<(TypeId, &'static str, Run<Db, Ctx>)>,
+    seeds: Vec<Seed<Db>>,
+    max_iterations: usize,
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraphBuilder<Db, Ctx> {
+    /// Adds the tasks of `group`. Each may depend on keys produced before the
+    /// group, on keys written by earlier tasks of the group, and on the
+    /// group's seeded keys, which nothing else may produce.
+    pub fn add_fixpoint(
+        &mut self,
+        group: FixpointGroup<Db, Ctx>,
+    ) -> Result<&mut Self, FixpointError> {
+        let graph = &mut self.graph;
+        let cyclic: Vec<KeyType> = group.seeds.iter().map(|seed| seed.key).collect();
+        if let Some(seed) = cyclic
+            .iter()
+            .find(|key| graph.contains_node(&key.id).is_some())
+        {
+            return Err(FixpointError::SeedExists { key: seed.name });
+        }
+        for seed in &group.seeds {
+            graph.register(seed.key);
+        }
+        graph.with_shared_db(|graph| {
+            for seed in &group.seeds {
+                (seed.reset)(&mut graph.db);
+            }
+        });
+        let mut entries = Vec::new();
+        for task in group.tasks {
+            entries.push(graph.wire_descriptor(task, &cyclic)?);
+        }
+        let fixpoint = Rc::new(Fixpoint {
+            members: group.members,
+            seeds: group.seeds,
+            max_iterations: group.max_iterations,
+        });
+        for entry in entries {
+            graph.fixpoints.insert(entry.id, fixpoint.clone());
+            graph.entries.push(entry);
+        }
+        Ok(self)
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraph<Db, Ctx> {
+    /// Runs the fixpoint group of `member` from its seeds until it converges.
+    fn run_fixpoint(&mut self, member: TypeId) -> Result<(), ExecutionError> {
+        let group = self.fixpoints[&member].clone();
+        self.with_shared_db(|graph| {
+            for seed in &group.seeds {
+                (seed.reset)(&mut graph.db);
+            }
+            for _ in 0..group.max_iterations {
+                let previous: Vec<_> = group
+                    .seeds
+                    .iter()
+                    .map(|seed| (seed.snapshot)(&graph.db))
+                    .collect();
+                for &(id, _, run) in &group.members {
+                    run(graph, id)?;
+                }
+                let converged = group
+                    .seeds
+                    .iter()
+                    .zip(&previous)
+                    .all(|(seed, previous)| (seed.converged)(previous.as_ref(), &graph.db));
+                if converged {
+                    return Ok(());
+                }
+            }
+            Err(NotConverged {
+                task: group.members[0].1,
+                iterations: group.max_iterations,
+            }
+            .into())
+        })
+    }
+
+    /// Whether `task` belongs to a fixpoint group it is not the first task
+    /// of, and so already ran with the group.
+    pub(crate) fn runs_with_group(&self, task: TypeId) -> bool {
+        self.fixpoints
+            .get(&task)
+            .is_some_and(|group| group.members[0].0 != task)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemoryDb, Task, TaskInput, TaskOutput};
+
+    struct Target;
+
+    impl DbKey for Target {
+        type Value = i64;
+    }
+
+    struct Estimate;
+
+    impl DbKey for Estimate {
+        type Value = i64;
+    }
+
+    struct Midpoint;
+
+    impl DbKey for Midpoint {
+        type Value = i64;
+    }
+
+    struct AverageIn(i64, i64);
+
+    impl DbKey for AverageIn {
+        type Value = AverageIn;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for AverageIn {
+        fn from_db(db: &Db) -> Self {
+            AverageIn(*db.get::<Target>().unwrap(), *db.get::<Estimate>().unwrap())
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Target>(), KeyType::of::<Estimate>()]
+        }
+    }
+
+    struct MidpointOut(i64);
+
+    impl DbKey for MidpointOut {
+        type Value = MidpointOut;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for MidpointOut {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<Midpoint>(self.0);
+        }
+
+        fn out_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Midpoint>()]
+        }
+    }
+
+    struct Average;
+
+    impl Task<InMemoryDb> for Average {
+        type Input = AverageIn;
+        type Output = MidpointOut;
+
+        fn execute(input: Self::Input) -> Self::Output {
+            MidpointOut((input.0 + input.1) / 2)
+        }
+    }
+
+    struct MidpointIn(i64);
+
+    impl DbKey for MidpointIn {
+        type Value = MidpointIn;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for MidpointIn {
+        fn from_db(db: &Db) -> Self {
+            MidpointIn(*db.get::<Midpoint>().unwrap())
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Midpoint>()]
+        }
+    }
+
+    struct EstimateOut(i64);
+
+    impl DbKey for EstimateOut {
+        type Value = EstimateOut;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for EstimateOut {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<Estimate>(self.0);
+        }
+
+        fn out_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Estimate>()]
+        }
+    }
+
+    struct Follow;
+
+    impl Task<InMemoryDb> for Follow {
+        type Input = MidpointIn;
+        type Output = EstimateOut;
+
+        fn execute(input: Self::Input) -> Self::Output {
+            EstimateOut(input.0)
+        }
+    }
+
+    fn graph(group: FixpointGroup<InMemoryDb>) -> ExecutionGraph<InMemoryDb> {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_input::<Target>(10)
+            .unwrap()
+            .add_fixpoint(group.task::<Average>().task::<Follow>())
+            .unwrap();
+        builder.build()
+    }
+
+    #[test]
+    fn test_fixpoint_converges() {
+        let mut graph = graph(FixpointGroup::new().seed::<Estimate>(0));
+        graph.execute_all().unwrap();
+        assert_eq!(graph.db().get::<Estimate>(), Some(&9));
+
+        graph.set_input::<Target>(-10).unwrap();
+        graph.task_by_name("Follow").unwrap().execute().unwrap();
+        assert_eq!(graph.db().get::<Estimate>(), Some(&-9));
+    }
+
+    #[test]
+    fn test_fixpoint_not_converged() {
+        let group = FixpointGroup::new()
+            .seed_with::<Estimate>(0, |_, _| false)
+            .max_iterations(3);
+        let err = graph(group).execute_all().err().unwrap();
+        assert_eq!(
+            err,
+            ExecutionError::NotConverged(NotConverged {
+                task: type_name::<Average>(),
+                iterations: 3,
+            })
+        );
+    }
+
+    #[test]
+    fn test_seeding_produced_key_fails() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder.add_input::<Target>(10).unwrap();
+        let group = FixpointGroup::new().seed::<Target>(0).task::<Average>();
+        let Err(err) = builder.add_fixpoint(group) else {
+            panic!("expected the seed to be rejected")
+        };
+        assert_eq!(
+            err,
+            FixpointError::SeedExists {
+                key: type_name::<Target>()
+            }
+        );
+    }
+}
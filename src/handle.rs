@@ -0,0 +1,256 @@
+use std::any::TypeId;
+
+use crate::{
+    DataBase, ExecutionError, ExecutionGraph, KeyType, TaskEntry, TaskId, TaskWithContext,
+};
+
+/// A task of a built graph, looked up by name with
+/// [`ExecutionGraph::task_by_name`].
+pub struct TaskHandle<'g, Db: DataBase, Ctx = ()> {
+    pub(crate) graph: &'g mut ExecutionGraph<Db, Ctx>,
+    index: usize,
+}
+
+impl<'g, Db: DataBase, Ctx> TaskHandle<'g, Db, Ctx> {
+    pub(crate) fn entry(&self) -> &TaskEntry<Db, Ctx> {
+        &self.graph.entries[self.index]
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.entry().name
+    }
+
+    pub fn id(&self) -> TaskId {
+        self.entry().task_id()
+    }
+
+    pub fn input(&self) -> KeyType {
+        self.entry().input
+    }
+
+    pub fn output(&self) -> KeyType {
+        self.entry().output
+    }
+
+    /// The keys the task reads, including those a
+    /// [`DynamicTask`](crate::dynamic::DynamicTask) discovered while running.
+    pub fn deps(&self) -> &[KeyType] {
+        &self.entry().deps
+    }
+
+    /// Whether the task's output is currently stored in the database.
+    pub fn has_output(&self) -> bool {
+        self.graph.with_db(|db| self.entry().output_stored(db))
+    }
+
+    pub fn execute(&mut self) -> Result<(), ExecutionError> {
+        let (run, id) = (self.entry().run, self.entry().id);
+        run(self.graph, id)
+    }
+
+    /// Removes the task's output from the database.
+    pub fn invalidate(&mut self) {
+        let index = self.index;
+        self.graph
+            .with_shared_db(|graph| graph.entries[index].invalidate(&mut graph.db))
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraph<Db, Ctx> {
+    pub fn task_by_name(&mut self, name: &str) -> Option<TaskHandle<'_, Db, Ctx>> {
+        let index = self.entries.iter().position(|e| e.name == name)?;
+        Some(TaskHandle { graph: self, index })
+    }
+
+    pub fn task(&mut self, id: TaskId) -> Option<TaskHandle<'_, Db, Ctx>> {
+        let index = self.entries.iter().position(|e| e.task_id() == id)?;
+        Some(TaskHandle { graph: self, index })
+    }
+
+    pub fn task_id<T: TaskWithContext<Db, Ctx>>(&self) -> Option<TaskId> {
+        self.entries
+            .iter()
+            .find(|e| e.id == TypeId::of::<T>())
+            .map(TaskEntry::task_id)
+    }
+
+    pub fn task_ids(&self) -> impl Iterator<Item = TaskId> + '_ {
+        self.entries.iter().map(TaskEntry::task_id)
+    }
+
+    pub fn task_names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.entries.iter().map(|e| e.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::any::type_name;
+
+    use super::*;
+    use crate::{DbKey, ExecutionGraphBuilder, InMemoryDb, Task, TaskInput, TaskOutput};
+
+    struct Source;
+
+    impl DbKey for Source {
+        type Value = String;
+    }
+
+    struct Ir(String);
+
+    impl DbKey for Ir {
+        type Value = Ir;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for Ir {
+        fn from_db(db: &Db) -> Self {
+            Ir(db.get::<Source>().unwrap().clone())
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Lowered(String);
+
+    impl DbKey for Lowered {
+        type Value = Lowered;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for Lowered {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<Lowered>(Lowered(self.0.clone()));
+        }
+    }
+
+    struct LowerToIR;
+
+    impl Task<InMemoryDb> for LowerToIR {
+        type Input = Ir;
+        type Output = Lowered;
+
+        fn execute(input: Self::Input) -> Self::Output {
+            Lowered(input.0.to_lowercase())
+        }
+    }
+
+    #[test]
+    fn test_task_by_name() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_input::<Source>("ABC".to_string())
+            .unwrap()
+            .add_task::<LowerToIR>()
+            .unwrap();
+        let mut graph = builder.build();
+        assert_eq!(graph.task_names().collect::<Vec<_>>(), vec!["LowerToIR"]);
+        assert!(graph.task_by_name("Missing").is_none());
+
+        let mut task = graph.task_by_name("LowerToIR").unwrap();
+        assert_eq!(task.output(), KeyType::of::<Lowered>());
+        assert!(!task.has_output());
+        task.execute().unwrap();
+        assert!(task.has_output());
+        task.invalidate();
+        assert!(!task.has_output());
+
+        graph.task_by_name("LowerToIR").unwrap().execute().unwrap();
+        assert_eq!(
+            graph.db().get::<Lowered>(),
+            Some(&Lowered("abc".to_string()))
+        );
+    }
+
+    struct Len;
+
+    impl DbKey for Len {
+        type Value = usize;
+    }
+
+    struct LenOut(usize);
+
+    impl DbKey for LenOut {
+        type Value = LenOut;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for LenOut {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<Len>(self.0);
+        }
+
+        fn out_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Len>()]
+        }
+    }
+
+    struct SourceIn(String);
+
+    impl DbKey for SourceIn {
+        type Value = SourceIn;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for SourceIn {
+        fn from_db(db: &Db) -> Self {
+            SourceIn(db.get::<Source>().unwrap().clone())
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Source>()]
+        }
+    }
+
+    struct Measure;
+
+    impl Task<InMemoryDb> for Measure {
+        type Input = SourceIn;
+        type Output = LenOut;
+
+        fn execute(input: Self::Input) -> Self::Output {
+            LenOut(input.0.len())
+        }
+    }
+
+    #[test]
+    fn test_invalidate_declared_outputs() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_input::<Source>("ABC".to_string())
+            .unwrap()
+            .add_task::<Measure>()
+            .unwrap()
+            .undoable_input::<Source>();
+        let mut graph = builder.build();
+
+        let mut task = graph.task_by_name("Measure").unwrap();
+        assert!(!task.has_output());
+        task.execute().unwrap();
+        assert!(task.has_output());
+        task.invalidate();
+        assert!(!task.has_output());
+        assert_eq!(graph.db().get::<Len>(), None);
+
+        graph.set_input::<Source>("ABCD".to_string()).unwrap();
+        graph.execute_all().unwrap();
+        assert_eq!(graph.db().get::<Len>(), Some(&4));
+        assert_eq!(graph.undo_input_change(), Some(KeyType::of::<Source>()));
+        assert_eq!(graph.db().get::<Len>(), None);
+    }
+
+    #[test]
+    fn test_task_ids() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_input::<Source>("ABC".to_string())
+            .unwrap()
+            .add_task::<LowerToIR>()
+            .unwrap();
+        let mut graph = builder.build();
+
+        let id = graph.task_id::<LowerToIR>().unwrap();
+        assert_eq!(id.to_string(), "LowerToIR");
+        assert_eq!(graph.task_ids().collect::<Vec<_>>(), vec![id]);
+        assert_eq!(graph.task(id).unwrap().id(), id);
+        assert_eq!(
+            graph.task(id).unwrap().output().to_string(),
+            type_name::<Lowered>()
+        );
+    }
+}
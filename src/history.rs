@@ -0,0 +1,370 @@
+use std::{
+    any::{Any, TypeId},
+    collections::{HashMap, VecDeque},
+};
+
+use crate::{DataBase, DbKey, ExecutionGraph, InMemoryDb, KeyType, Write};
+
+type CloneValue = fn(&dyn Any) -> Box<dyn Any>;
+
+struct History<Db> {
+    key: KeyType,
+    limit: usize,
+    clone: CloneValue,
+    /// A copy of the key's current value in the inner database.
+    current: fn(&Db) -> Option<Box<dyn Any>>,
+    /// Revision at which the current value, or its absence, was written.
+    since: u64,
+    /// Earlier values, oldest first, each with the revision it was written
+    /// at. `None` for a span where the key had no value.
+    past: VecDeque<(u64, Option<Box<dyn Any>>)>,
+}
+
+impl<Db> History<Db> {
+    fn push(&mut self, previous: Option<Box<dyn Any>>, revision: u64) {
+        self.past.push_back((self.since, previous));
+        self.since = revision;
+        while self.past.len() > self.limit {
+            self.past.pop_front();
+        }
+    }
+}
+
+/// The writes of an open transaction, archived only once it commits.
+struct Transaction {
+    /// The revision when it began, restored on rollback.
+    revision: u64,
+    /// Each kept key written in it, with the value it had before and the
+    /// revision of its last write.
+    touched: HashMap<TypeId, (Option<Box<dyn Any>>, u64)>,
+}
+
+/// A database keeping, for keys registered with [`VersionedDb::keep`], the
+/// values they held before, readable with [`VersionedDb::get_at`].
+///
+/// Every write or removal of any key counts as one revision. Transactions
+/// are forwarded to the inner database; writes made in one are only kept in
+/// the history once it commits, and a rollback undoes their revisions.
+pub struct VersionedDb<Db = InMemoryDb> {
+    inner: Db,
+    revision: u64,
+    histories: HashMap<TypeId, History<Db>>,
+    transaction: Option<Transaction>,
+}
+
+impl<Db: DataBase> VersionedDb<Db> {
+    pub fn new(inner: Db) -> Self {
+        VersionedDb {
+            inner,
+            revision: 0,
+            histories: HashMap::new(),
+            transaction: None,
+        }
+    }
+
+    /// Keeps the last `n` values of `K` besides the current one.
+    pub fn keep<K: DbKey>(mut self, n: usize) -> Self
+    where
+        K::Value: Clone,
+    {
+        self.histories.insert(
+            TypeId::of::<K>(),
+            History {
+                key: KeyType::of::<K>(),
+                limit: n,
+                clone: |value| Box::new(value.downcast_ref::<K::Value>().unwrap().clone()),
+                current: |db| {
+                    db.get::<K>()
+                        .map(|value| Box::new(value.clone()) as Box<dyn Any>)
+                },
+                since: 0,
+                past: VecDeque::new(),
+            },
+        );
+        self
+    }
+
+    /// The revision of the last write or removal.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// The value `K` had at `revision`, or `None` if it had none, or if that
+    /// value is no longer kept.
+    pub fn get_at<K: DbKey>(&self, revision: u64) -> Option<&K::Value> {
+        let history = self.histories.get(&TypeId::of::<K>())?;
+        if revision >= history.since {
+            return self.inner.get::<K>();
+        }
+        let (_, value) = history
+            .past
+            .iter()
+            .rev()
+            .find(|(since, _)| *since <= revision)?;
+        value.as_ref()?.downcast_ref::<K::Value>()
+    }
+
+    /// The value `K` had before its last write or removal, if it is kept.
+    pub fn get_previous<K: DbKey>(&self) -> Option<&K::Value> {
+        let history = self.histories.get(&TypeId::of::<K>())?;
+        history.past.back()?.1.as_ref()?.downcast_ref::<K::Value>()
+    }
+
+    /// The writes and removals of kept keys whose values are still kept, in
+    /// order, by revision.
+    pub fn revision_log(&self) -> Vec<(u64, KeyType)> {
+        let mut log: Vec<(u64, KeyType)> = self
+            .histories
+            .values()
+            .flat_map(|history| {
+                history
+                    .past
+                    .iter()
+                    .map(|(since, _)| *since)
+                    .chain([history.since])
+                    .filter(|&since| since > 0)
+                    .map(|since| (since, history.key))
+            })
+            .collect();
+        log.sort_by_key(|(revision, _)| *revision);
+        log
+    }
+
+    pub fn inner(&self) -> &Db {
+        &self.inner
+    }
+
+    /// Starts a new revision writing `key`. In a transaction, the value
+    /// `key` had before it is set aside until the transaction ends.
+    fn begin_write(&mut self, key: TypeId) {
+        self.revision += 1;
+        let (Some(transaction), Some(history)) = (&mut self.transaction, self.histories.get(&key))
+        else {
+            return;
+        };
+        let inner = &self.inner;
+        let touched = transaction
+            .touched
+            .entry(key)
+            .or_insert_with(|| ((history.current)(inner), 0));
+        touched.1 = self.revision;
+    }
+
+    /// Moves the value `key` had before this revision into its history,
+    /// unless a transaction is open.
+    fn archive(&mut self, key: TypeId, previous: Option<&dyn Any>) {
+        if self.transaction.is_some() {
+            return;
+        }
+        let Some(history) = self.histories.get_mut(&key) else {
+            return;
+        };
+        let previous = previous.map(|value| (history.clone)(value));
+        history.push(previous, self.revision);
+    }
+}
+
+impl<Db: DataBase> DataBase for VersionedDb<Db> {
+    fn get<K: DbKey>(&self) -> Option<&K::Value> {
+        self.inner.get::<K>()
+    }
+
+    fn put<K: DbKey>(&mut self, value: K::Value) -> Option<K::Value> {
+        self.begin_write(TypeId::of::<K>());
+        let previous = self.inner.put::<K>(value);
+        self.archive(TypeId::of::<K>(), previous.as_ref().map(|v| v as &dyn Any));
+        previous
+    }
+
+    fn remove<K: DbKey>(&mut self) -> Option<K::Value> {
+        self.begin_write(TypeId::of::<K>());
+        let previous = self.inner.remove::<K>();
+        self.archive(TypeId::of::<K>(), previous.as_ref().map(|v| v as &dyn Any));
+        previous
+    }
+
+    fn remove_key(&mut self, key: KeyType) -> Option<Box<dyn Any>> {
+        self.begin_write(key.id);
+        let previous = self.inner.remove_key(key);
+        self.archive(key.id, previous.as_deref());
+        previous
+    }
+
+    fn prefetch(&mut self, keys: &[KeyType]) {
+        self.inner.prefetch(keys);
+    }
+
+    fn reserve(&mut self, keys: &[KeyType]) {
+        self.inner.reserve(keys);
+    }
+
+    fn contains(&self, key: KeyType) -> Option<bool> {
+        self.inner.contains(key)
+    }
+
+    /// Transactions do not nest, like [`InMemoryDb`]'s.
+    fn begin(&mut self) {
+        self.inner.begin();
+        self.transaction.get_or_insert_with(|| Transaction {
+            revision: self.revision,
+            touched: HashMap::new(),
+        });
+    }
+
+    fn commit(&mut self) {
+        self.inner.commit();
+        let Some(transaction) = self.transaction.take() else {
+            return;
+        };
+        for (key, (previous, revision)) in transaction.touched {
+            self.histories
+                .get_mut(&key)
+                .unwrap()
+                .push(previous, revision);
+        }
+    }
+
+    fn rollback(&mut self) {
+        self.inner.rollback();
+        if let Some(transaction) = self.transaction.take() {
+            self.revision = transaction.revision;
+        }
+    }
+
+    fn record_writes(&mut self) {
+        self.inner.record_writes();
+    }
+
+    fn take_writes(&mut self) -> Option<Vec<Write>> {
+        self.inner.take_writes()
+    }
+
+    fn revision(&self) -> Option<u64> {
+        Some(self.revision)
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraph<VersionedDb<Db>, Ctx> {
+    /// The writes of kept keys still in the database's history, like
+    /// [`VersionedDb::revision_log`].
+    pub fn revision_log(&self) -> Vec<(u64, KeyType)> {
+        self.db.revision_log()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExecutionGraphBuilder, Task, TaskInput, TaskOutput};
+
+    struct Threshold;
+
+    impl DbKey for Threshold {
+        type Value = u32;
+    }
+
+    struct Alerts;
+
+    impl DbKey for Alerts {
+        type Value = u32;
+    }
+
+    struct ThresholdIn(u32);
+
+    impl DbKey for ThresholdIn {
+        type Value = ThresholdIn;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for ThresholdIn {
+        fn from_db(db: &Db) -> Self {
+            ThresholdIn(*db.get::<Threshold>().unwrap())
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Threshold>()]
+        }
+    }
+
+    struct AlertsOut(u32);
+
+    impl DbKey for AlertsOut {
+        type Value = AlertsOut;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for AlertsOut {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<Alerts>(self.0);
+        }
+
+        fn out_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Alerts>()]
+        }
+    }
+
+    struct CountAlerts;
+
+    impl Task<VersionedDb> for CountAlerts {
+        type Input = ThresholdIn;
+        type Output = AlertsOut;
+
+        fn execute(input: Self::Input) -> Self::Output {
+            AlertsOut(100 / input.0)
+        }
+    }
+
+    #[test]
+    fn test_time_travel_reads() {
+        let db = VersionedDb::new(InMemoryDb::new()).keep::<Alerts>(1);
+        let mut builder = ExecutionGraphBuilder::new(db);
+        builder
+            .add_input::<Threshold>(10)
+            .unwrap()
+            .add_task::<CountAlerts>()
+            .unwrap();
+        let mut graph = builder.build();
+        graph.execute_all().unwrap();
+        let first = graph.db().revision();
+        graph.set_input::<Threshold>(20).unwrap();
+        graph.execute_all().unwrap();
+        let second = graph.db().revision();
+        graph.set_input::<Threshold>(25).unwrap();
+        graph.execute_all().unwrap();
+
+        let db = graph.db();
+        assert_eq!(db.get::<Alerts>(), Some(&4));
+        assert_eq!(db.get_previous::<Alerts>(), Some(&5));
+        // Only one earlier value is kept.
+        assert_eq!(db.get_at::<Alerts>(first), None);
+        assert_eq!(db.get_at::<Alerts>(second), Some(&5));
+        let log = graph.revision_log();
+        assert_eq!(log.len(), 2);
+        assert!(log.iter().all(|(_, key)| *key == KeyType::of::<Alerts>()));
+    }
+
+    #[test]
+    fn test_rolled_back_writes_not_kept() {
+        let db = VersionedDb::new(InMemoryDb::new()).keep::<Alerts>(2);
+        let mut builder = ExecutionGraphBuilder::new(db);
+        builder
+            .add_input::<Threshold>(10)
+            .unwrap()
+            .add_task::<CountAlerts>()
+            .unwrap()
+            .on_output::<Alerts>(|alerts| match alerts {
+                0..=50 => Ok(()),
+                _ => Err("too many alerts".to_string()),
+            });
+        let mut graph = builder.build();
+        graph.transactional_outputs(true);
+        graph.execute_all().unwrap();
+
+        graph.set_input::<Threshold>(1).unwrap();
+        let before = graph.db().revision();
+        assert!(graph.execute_all().is_err());
+        let db = graph.db();
+        assert_eq!(db.revision(), before);
+        assert_eq!(db.get::<Alerts>(), Some(&10));
+        assert_eq!(db.get_previous::<Alerts>(), None);
+        assert_eq!(graph.revision_log().len(), 1);
+    }
+}
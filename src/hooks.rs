@@ -0,0 +1,150 @@
+use std::{
+    any::{type_name, TypeId},
+    rc::Rc,
+};
+
+use crate::{
+    DataBase, DbKey, ExecutionGraph, ExecutionGraphBuilder, OutputAssertionFailed, TaskOutput,
+    TaskWithContext,
+};
+
+pub(crate) type OutputHook<Db> = Rc<dyn Fn(&Db) -> Result<(), OutputHookFailure>>;
+
+pub(crate) struct OutputHookFailure {
+    key: &'static str,
+    message: String,
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraphBuilder<Db, Ctx> {
+    /// Registers an assertion on `K`, checked after every task that writes
+    /// `K` (as its output or one of its `out_types`). A failing assertion
+    /// fails the task.
+    pub fn on_output<K: DbKey>(
+        &mut self,
+        check: impl Fn(&K::Value) -> Result<(), String> + 'static,
+    ) -> &mut Self {
+        self.graph
+            .output_hooks
+            .entry(TypeId::of::<K>())
+            .or_default()
+            .push(Rc::new(move |db| match db.get::<K>() {
+                Some(value) => check(value).map_err(|message| OutputHookFailure {
+                    key: type_name::<K>(),
+                    message,
+                }),
+                None => Ok(()),
+            }));
+        self
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraph<Db, Ctx> {
+    pub(crate) fn run_output_hooks<T: TaskWithContext<Db, Ctx>>(
+        &self,
+    ) -> Result<(), OutputAssertionFailed> {
+        let written = std::iter::once(TypeId::of::<T::Output>())
+            .chain(T::Output::out_types().into_iter().map(|key| key.id));
+        for key in written {
+            for hook in self.output_hooks.get(&key).into_iter().flatten() {
+                hook(&self.db).map_err(|failure| OutputAssertionFailed {
+                    task: type_name::<T>(),
+                    key: failure.key,
+                    message: failure.message,
+                })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExecutionError, InMemoryDb, Task, TaskInput};
+
+    struct Rows;
+
+    impl DbKey for Rows {
+        type Value = Vec<u32>;
+    }
+
+    struct Filter(Vec<u32>);
+
+    impl DbKey for Filter {
+        type Value = Filter;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for Filter {
+        fn from_db(db: &Db) -> Self {
+            Filter(db.get::<Rows>().unwrap().clone())
+        }
+    }
+
+    struct Filtered(Vec<u32>);
+
+    impl DbKey for Filtered {
+        type Value = Filtered;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for Filtered {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<Filtered>(Filtered(self.0.clone()));
+        }
+    }
+
+    struct KeepEven;
+
+    impl Task<InMemoryDb> for KeepEven {
+        type Input = Filter;
+        type Output = Filtered;
+
+        fn execute(input: Self::Input) -> Self::Output {
+            Filtered(input.0.into_iter().filter(|x| x % 2 == 0).collect())
+        }
+    }
+
+    fn graph(rows: Vec<u32>) -> ExecutionGraph<InMemoryDb> {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_input::<Rows>(rows)
+            .unwrap()
+            .add_task::<KeepEven>()
+            .unwrap()
+            .on_output::<Filtered>(|rows| {
+                if rows.0.is_empty() {
+                    Err("row count must be > 0".to_string())
+                } else {
+                    Ok(())
+                }
+            });
+        builder.build()
+    }
+
+    #[test]
+    fn test_output_assertion_passes() {
+        assert!(graph(vec![1, 2, 3]).execute::<KeepEven>().is_ok());
+    }
+
+    #[test]
+    fn test_failed_task_writes_are_rolled_back() {
+        let mut graph = graph(vec![2]);
+        graph.execute_all().unwrap();
+        graph.set_input::<Rows>(vec![1, 3]).unwrap();
+        graph.transactional_outputs(true);
+        assert!(graph.execute_all().is_err());
+        assert_eq!(graph.db().get::<Filtered>().unwrap().0, [2]);
+    }
+
+    #[test]
+    fn test_output_assertion_fails_task() {
+        let err = graph(vec![1, 3]).execute_all().unwrap_err();
+        assert_eq!(
+            err,
+            ExecutionError::OutputAssertionFailed(OutputAssertionFailed {
+                task: type_name::<KeepEven>(),
+                key: type_name::<Filtered>(),
+                message: "row count must be > 0".to_string(),
+            })
+        );
+    }
+}
@@ -0,0 +1,388 @@
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+use crate::{DataBase, DbKey, ExecutionGraph, ExecutionGraphBuilder, KeyType};
+
+/// The stored value of an [`HttpSource`] key: the last body fetched from
+/// `url`, with the validators the server sent alongside it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HttpResource {
+    pub url: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub body: Vec<u8>,
+}
+
+impl HttpResource {
+    pub fn fetch(url: impl Into<String>) -> io::Result<Self> {
+        let mut resource = HttpResource::unfetched(url.into());
+        resource.refetch()?;
+        Ok(resource)
+    }
+
+    fn unfetched(url: String) -> Self {
+        HttpResource {
+            url,
+            etag: None,
+            last_modified: None,
+            body: Vec::new(),
+        }
+    }
+
+    /// Fetches the resource again, sending `If-None-Match` and
+    /// `If-Modified-Since` when the previous response had validators. Returns
+    /// whether the body changed.
+    pub fn refetch(&mut self) -> io::Result<bool> {
+        let Some(response) = get(
+            &self.url,
+            self.etag.as_deref(),
+            self.last_modified.as_deref(),
+        )?
+        else {
+            return Ok(false);
+        };
+        let changed = response.body != self.body;
+        self.etag = response.etag;
+        self.last_modified = response.last_modified;
+        self.body = response.body;
+        Ok(changed)
+    }
+}
+
+/// A key whose value is fetched over HTTP, registered with
+/// [`ExecutionGraphBuilder::add_http_source`].
+pub trait HttpSource: DbKey<Value = HttpResource> {}
+
+impl<K: DbKey<Value = HttpResource>> HttpSource for K {}
+
+pub(crate) struct HttpEntry<Db> {
+    key: KeyType,
+    url: String,
+    /// Re-fetches the resource at the URL, or fetches it if the database has
+    /// none, returning whether its body changed.
+    refresh: fn(&mut Db, &str) -> io::Result<bool>,
+}
+
+impl<Db> Clone for HttpEntry<Db> {
+    fn clone(&self) -> Self {
+        HttpEntry {
+            key: self.key,
+            url: self.url.clone(),
+            refresh: self.refresh,
+        }
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraphBuilder<Db, Ctx> {
+    /// Fetches `url` and registers the response as the input `K`.
+    pub fn add_http_source<K: HttpSource>(
+        &mut self,
+        url: impl Into<String>,
+    ) -> io::Result<&mut Self> {
+        let resource = HttpResource::fetch(url)?;
+        let url = resource.url.clone();
+        self.graph
+            .with_shared_db(|graph| graph.db.put::<K>(resource));
+        if self.graph.contains_node(&KeyType::of::<K>().id).is_none() {
+            self.graph.register_input::<K>("add_http_source");
+        }
+        self.graph.http_sources.push(HttpEntry {
+            key: KeyType::of::<K>(),
+            url,
+            refresh: |db, url| {
+                let (mut resource, fetched) = match db.get::<K>() {
+                    Some(resource) => (resource.clone(), true),
+                    None => (HttpResource::unfetched(url.to_string()), false),
+                };
+                let changed = resource.refetch()? || !fetched;
+                db.put::<K>(resource);
+                Ok(changed)
+            },
+        });
+        Ok(self)
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraph<Db, Ctx> {
+    /// Re-fetches every source registered with
+    /// [`add_http_source`](ExecutionGraphBuilder::add_http_source). Sources
+    /// whose body changed invalidate the outputs of the tasks depending on
+    /// them; a `304 Not Modified` leaves everything in place.
+    ///
+    /// Returns the keys of the sources that changed. If a source cannot be
+    /// fetched, the other sources are still refreshed and the first error is
+    /// returned.
+    pub fn refresh_http_sources(&mut self) -> io::Result<Vec<KeyType>> {
+        let refreshes = self
+            .http_sources
+            .iter()
+            .cloned()
+            .map(|source| {
+                let refresh = move |db: &mut Db| (source.refresh)(db, &source.url);
+                (source.key, refresh)
+            })
+            .collect();
+        self.refresh_inputs(refreshes)
+    }
+}
+
+struct Response {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    body: Vec<u8>,
+}
+
+/// How long connecting, and then each read or write, may take before a fetch
+/// fails.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+/// A minimal HTTP/1.0 GET. Only plain `http://` URLs are supported. Returns
+/// `None` on `304 Not Modified`.
+fn get(url: &str, etag: Option<&str>, last_modified: Option<&str>) -> io::Result<Option<Response>> {
+    let rest = url.strip_prefix("http://").ok_or_else(|| {
+        invalid(format!(
+            "unsupported URL `{}`: only http:// URLs are supported",
+            url
+        ))
+    })?;
+    let (authority, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+    let addr = if authority.contains(':') {
+        authority.to_string()
+    } else {
+        format!("{}:80", authority)
+    };
+
+    let mut stream = connect(&addr)?;
+    write!(stream, "GET {} HTTP/1.0\r\nHost: {}\r\n", path, authority)?;
+    if let Some(etag) = etag {
+        write!(stream, "If-None-Match: {}\r\n", etag)?;
+    }
+    if let Some(last_modified) = last_modified {
+        write!(stream, "If-Modified-Since: {}\r\n", last_modified)?;
+    }
+    stream.write_all(b"\r\n")?;
+
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let status: u16 = line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| invalid(format!("malformed status line `{}`", line.trim_end())))?;
+
+    let mut response = Response {
+        etag: None,
+        last_modified: None,
+        body: Vec::new(),
+    };
+    loop {
+        line.clear();
+        reader.read_line(&mut line)?;
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            let value = Some(value.trim().to_string());
+            match name.to_ascii_lowercase().as_str() {
+                "etag" => response.etag = value,
+                "last-modified" => response.last_modified = value,
+                _ => {}
+            }
+        }
+    }
+    match status {
+        304 => Ok(None),
+        200..=299 => {
+            reader.read_to_end(&mut response.body)?;
+            Ok(Some(response))
+        }
+        _ => Err(invalid(format!("GET {} returned {}", url, status))),
+    }
+}
+
+/// Connects to the first address `addr` resolves to that accepts within
+/// [`TIMEOUT`], with reads and writes bounded by it too.
+fn connect(addr: &str) -> io::Result<TcpStream> {
+    let mut last = invalid(format!("`{}` resolves to no address", addr));
+    for addr in addr.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, TIMEOUT) {
+            Ok(stream) => {
+                stream.set_read_timeout(Some(TIMEOUT))?;
+                stream.set_write_timeout(Some(TIMEOUT))?;
+                return Ok(stream);
+            }
+            Err(e) => last = e,
+        }
+    }
+    Err(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::TcpListener, thread};
+
+    use super::*;
+    use crate::{InMemoryDb, Task, TaskInput, TaskOutput};
+
+    struct Feed;
+
+    impl DbKey for Feed {
+        type Value = HttpResource;
+    }
+
+    struct FeedBody(usize);
+
+    impl DbKey for FeedBody {
+        type Value = FeedBody;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for FeedBody {
+        fn from_db(db: &Db) -> Self {
+            FeedBody(db.get::<Feed>().unwrap().body.len())
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Feed>()]
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct FeedSize(usize);
+
+    impl DbKey for FeedSize {
+        type Value = FeedSize;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for FeedSize {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<FeedSize>(FeedSize(self.0));
+        }
+    }
+
+    struct MeasureFeed;
+
+    impl Task<InMemoryDb> for MeasureFeed {
+        type Input = FeedBody;
+        type Output = FeedSize;
+
+        fn execute(input: Self::Input) -> Self::Output {
+            FeedSize(input.0)
+        }
+    }
+
+    /// Serves `bodies` in turn, answering `304` when the client already has
+    /// the current one.
+    fn serve(bodies: &'static [&'static str]) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            for (i, stream) in listener.incoming().enumerate() {
+                let stream = stream.unwrap();
+                let mut reader = BufReader::new(&stream);
+                let mut cached = None;
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    if let Some(etag) = line.strip_prefix("If-None-Match: ") {
+                        cached = Some(etag.trim_end().to_string());
+                    }
+                    line.clear();
+                }
+                let index = bodies.len().min(i + 1) - 1;
+                let etag = format!("\"{}\"", index);
+                let mut stream = reader.into_inner();
+                if cached.as_ref() == Some(&etag) {
+                    write!(stream, "HTTP/1.0 304 Not Modified\r\n\r\n").unwrap();
+                } else {
+                    write!(
+                        stream,
+                        "HTTP/1.0 200 OK\r\nETag: {}\r\n\r\n{}",
+                        etag, bodies[index]
+                    )
+                    .unwrap();
+                }
+            }
+        });
+        format!("http://{}/feed", addr)
+    }
+
+    #[test]
+    fn test_refresh_http_sources() {
+        let url = serve(&["abc", "abcdef"]);
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_http_source::<Feed>(&url)
+            .unwrap()
+            .add_task::<MeasureFeed>()
+            .unwrap();
+        let mut graph = builder.build();
+        graph.execute_all().unwrap();
+        assert_eq!(graph.db().get::<FeedSize>(), Some(&FeedSize(3)));
+
+        assert_eq!(
+            graph.refresh_http_sources().unwrap(),
+            vec![KeyType::of::<Feed>()]
+        );
+        assert_eq!(graph.db().get::<FeedSize>(), None);
+        graph.execute_all().unwrap();
+        assert_eq!(graph.db().get::<FeedSize>(), Some(&FeedSize(6)));
+
+        assert_eq!(graph.refresh_http_sources().unwrap(), vec![]);
+        assert_eq!(graph.db().get::<FeedSize>(), Some(&FeedSize(6)));
+    }
+
+    struct Mirror;
+
+    impl DbKey for Mirror {
+        type Value = HttpResource;
+    }
+
+    /// Serves `body` to a single request, then stops listening.
+    fn serve_once(body: &'static str) -> (String, thread::JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/mirror", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(&stream);
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            write!(reader.into_inner(), "HTTP/1.0 200 OK\r\n\r\n{}", body).unwrap();
+        });
+        (url, server)
+    }
+
+    #[test]
+    fn test_refresh_with_unreachable_source() {
+        let url = serve(&["abc", "abcdef"]);
+        let (mirror, server) = serve_once("xyz");
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_http_source::<Feed>(&url)
+            .unwrap()
+            .add_http_source::<Mirror>(&mirror)
+            .unwrap()
+            .add_task::<MeasureFeed>()
+            .unwrap();
+        server.join().unwrap();
+        let mut graph = builder.build();
+        graph.execute_all().unwrap();
+
+        assert!(graph.refresh_http_sources().is_err());
+        assert_eq!(graph.db().get::<FeedSize>(), None);
+        graph.execute_all().unwrap();
+        assert_eq!(graph.db().get::<FeedSize>(), Some(&FeedSize(6)));
+    }
+}
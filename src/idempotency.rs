@@ -0,0 +1,112 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+};
+
+use crate::{DataBase, ExecutionGraph, TaskContext, TaskWithContext};
+
+/// The side effects a task committed since it last succeeded, by token, with
+/// their results.
+#[derive(Default)]
+pub(crate) struct SideEffects {
+    committed: HashMap<String, Box<dyn Any>>,
+}
+
+impl<Ctx> TaskContext<'_, Ctx> {
+    /// Runs `effect`, e.g. sending an email or uploading an artifact, unless
+    /// an earlier execution of the task already ran the effect named `token`
+    /// and then failed. In that case, returns the result it had instead.
+    ///
+    /// Tokens are forgotten once the task succeeds, so the next successful
+    /// execution runs every effect again.
+    pub fn side_effect<R: Clone + 'static>(
+        &mut self,
+        token: impl Into<String>,
+        effect: impl FnOnce() -> R,
+    ) -> R {
+        let result = self
+            .side_effects
+            .committed
+            .entry(token.into())
+            .or_insert_with(|| Box::new(effect()));
+        result
+            .downcast_ref::<R>()
+            .expect("side effect result type mismatch")
+            .clone()
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraph<Db, Ctx> {
+    /// Forgets the side effects `T` committed, so that its next execution
+    /// runs them again even if the last one failed.
+    pub fn clear_side_effects<T: TaskWithContext<Db, Ctx>>(&mut self) -> &mut Self {
+        self.side_effects.remove(&TypeId::of::<T>());
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::{
+        recovery::Recovery, DbKey, ExecutionError, ExecutionGraphBuilder, InMemoryDb, TaskOutput,
+    };
+
+    thread_local! {
+        static SENT: Cell<u32> = const { Cell::new(0) };
+        static ATTEMPTS: Cell<u32> = const { Cell::new(0) };
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Receipt(u32);
+
+    impl DbKey for Receipt {
+        type Value = Receipt;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for Receipt {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<Receipt>(Receipt(self.0));
+        }
+    }
+
+    struct Notify;
+
+    impl TaskWithContext<InMemoryDb, ()> for Notify {
+        type Input = ();
+        type Output = Receipt;
+
+        fn execute(_input: Self::Input, ctx: &mut TaskContext<'_, ()>) -> Self::Output {
+            let message_id = ctx.side_effect("welcome-email", || {
+                SENT.with(|sent| sent.set(sent.get() + 1));
+                SENT.with(Cell::get)
+            });
+            let attempt = ATTEMPTS.with(|attempts| {
+                attempts.set(attempts.get() + 1);
+                attempts.get()
+            });
+            if attempt == 1 {
+                panic!("connection reset after sending");
+            }
+            Receipt(message_id)
+        }
+    }
+
+    #[test]
+    fn test_side_effect_not_repeated_on_retry() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .error_handler(|_: &ExecutionError, _| Recovery::Retry)
+            .add_task::<Notify>()
+            .unwrap();
+        let mut graph = builder.build();
+        graph.catch_panics(true);
+        assert_eq!(graph.execute::<Notify>().unwrap(), Receipt(1));
+        assert_eq!(SENT.with(Cell::get), 1);
+
+        // Succeeded, so the next execution sends again.
+        assert_eq!(graph.execute::<Notify>().unwrap(), Receipt(2));
+    }
+}
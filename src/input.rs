@@ -0,0 +1,272 @@
+use std::{
+    any::{Any, TypeId},
+    rc::Rc,
+};
+
+use crate::{
+    DataBase, DbKey, ExecuteWithError, ExecutionGraph, ExecutionGraphBuilder, InvalidInput, KeyType,
+};
+
+pub(crate) type Validator = Rc<dyn Fn(&dyn Any) -> Result<(), String>>;
+
+type Restore<Db> = Box<dyn FnOnce(&mut Db)>;
+
+/// A type-erased input value, supplied to a single run with
+/// [`ExecutionGraph::execute_all_with`].
+pub struct DynInput<Db: DataBase> {
+    key: KeyType,
+    value: Box<dyn Any>,
+    /// Stores `value`, returning how to put back what it replaced.
+    apply: fn(&mut Db, Box<dyn Any>) -> Restore<Db>,
+}
+
+impl<Db: DataBase> DynInput<Db> {
+    pub fn new<K: DbKey>(value: K::Value) -> Self {
+        DynInput {
+            key: KeyType::of::<K>(),
+            value: Box::new(value),
+            apply: |db, value| {
+                let previous = db.put::<K>(*value.downcast().unwrap());
+                Box::new(move |db| {
+                    match previous {
+                        Some(previous) => db.put::<K>(previous),
+                        None => db.remove::<K>(),
+                    };
+                })
+            },
+        }
+    }
+
+    pub fn key(&self) -> KeyType {
+        self.key
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraphBuilder<Db, Ctx> {
+    /// Registers a check run on every value of `K` passed to
+    /// [`add_input`](Self::add_input) or [`ExecutionGraph::set_input`].
+    ///
+    /// A value already stored for `K` is checked immediately.
+    pub fn validate_input<K: DbKey>(
+        &mut self,
+        validator: fn(&K::Value) -> Result<(), String>,
+    ) -> Result<&mut Self, InvalidInput> {
+        self.graph.validators.insert(
+            TypeId::of::<K>(),
+            Rc::new(move |value| validator(value.downcast_ref::<K::Value>().unwrap())),
+        );
+        self.graph.with_db(|db| match db.get::<K>() {
+            Some(value) => self.graph.validate::<K>(value),
+            None => Ok(()),
+        })?;
+        Ok(self)
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraph<Db, Ctx> {
+    pub(crate) fn validate<K: DbKey>(&self, value: &K::Value) -> Result<(), InvalidInput> {
+        self.validate_dyn(KeyType::of::<K>(), value)
+    }
+
+    fn validate_dyn(&self, key: KeyType, value: &dyn Any) -> Result<(), InvalidInput> {
+        match self.validators.get(&key.id) {
+            Some(validator) => validator(value).map_err(|message| InvalidInput {
+                key: key.name,
+                message,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Replaces the value of input `K`, returning the previous one. The value
+    /// is rejected if it fails the validator registered for `K`.
+    pub fn set_input<K: DbKey>(
+        &mut self,
+        value: K::Value,
+    ) -> Result<Option<K::Value>, InvalidInput> {
+        self.validate::<K>(&value)?;
+        self.bump_revision(KeyType::of::<K>());
+        let previous = self.with_shared_db(|graph| graph.db.put::<K>(value));
+        self.record_input_change::<K>(previous.as_ref());
+        #[cfg(feature = "web-ui")]
+        self.publish_web_ui();
+        Ok(previous)
+    }
+
+    /// Executes every task with `inputs` stored for the duration of the run.
+    ///
+    /// Each input is first checked by the validator registered for its key;
+    /// if one is rejected, nothing is stored or run. Once the run finishes,
+    /// successfully or not, each input key is restored to the value it had
+    /// before, so per-request values never leak into the shared database.
+    /// Task outputs are left in place.
+    pub fn execute_all_with(
+        &mut self,
+        inputs: impl IntoIterator<Item = DynInput<Db>>,
+    ) -> Result<(), ExecuteWithError> {
+        let inputs: Vec<DynInput<Db>> = inputs.into_iter().collect();
+        for input in &inputs {
+            self.validate_dyn(input.key, &*input.value)?;
+        }
+        self.with_shared_db(|graph| {
+            let restores: Vec<(KeyType, Restore<Db>)> = inputs
+                .into_iter()
+                .map(|input| {
+                    graph.bump_revision(input.key);
+                    (input.key, (input.apply)(&mut graph.db, input.value))
+                })
+                .collect();
+            let result = graph.execute_all();
+            for (key, restore) in restores.into_iter().rev() {
+                graph.bump_revision(key);
+                restore(&mut graph.db);
+            }
+            Ok(result?)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::any::type_name;
+
+    use super::*;
+    use crate::{ExecutionGraphBuilder, InMemoryDb, Task, TaskInput, TaskOutput};
+
+    struct Query;
+
+    impl DbKey for Query {
+        type Value = String;
+    }
+
+    struct Greeting(String);
+
+    impl DbKey for Greeting {
+        type Value = Greeting;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for Greeting {
+        fn from_db(db: &Db) -> Self {
+            Greeting(db.get::<Query>().cloned().unwrap_or_default())
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Query>()]
+        }
+    }
+
+    struct Reply(String);
+
+    impl DbKey for Reply {
+        type Value = Reply;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for Reply {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<Reply>(Reply(self.0.clone()));
+        }
+    }
+
+    struct Greet;
+
+    impl Task<InMemoryDb> for Greet {
+        type Input = Greeting;
+        type Output = Reply;
+
+        fn execute(input: Self::Input) -> Self::Output {
+            Reply(format!("hello {}", input.0))
+        }
+    }
+
+    #[test]
+    fn test_execute_all_with_late_inputs() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .declare_input::<Query>()
+            .add_task::<Greet>()
+            .unwrap();
+        let mut graph = builder.build();
+
+        graph
+            .execute_all_with([DynInput::new::<Query>("world".to_string())])
+            .unwrap();
+        assert_eq!(graph.db().get::<Reply>().unwrap().0, "hello world");
+        assert!(graph.db().get::<Query>().is_none());
+    }
+
+    #[test]
+    fn test_execute_all_with_restores_previous_value() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_input::<Query>("default".to_string())
+            .unwrap()
+            .add_task::<Greet>()
+            .unwrap();
+        let mut graph = builder.build();
+
+        graph
+            .execute_all_with([DynInput::new::<Query>("request".to_string())])
+            .unwrap();
+        assert_eq!(graph.db().get::<Reply>().unwrap().0, "hello request");
+        assert_eq!(graph.db().get::<Query>().unwrap(), "default");
+    }
+
+    const NON_EMPTY: fn(&String) -> Result<(), String> = |query| {
+        if query.is_empty() {
+            Err("query must not be empty".to_string())
+        } else {
+            Ok(())
+        }
+    };
+
+    #[test]
+    fn test_input_validation() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder.validate_input::<Query>(NON_EMPTY).unwrap();
+        let err = builder.add_input::<Query>(String::new()).err().unwrap();
+        assert_eq!(err.key, type_name::<Query>());
+        assert_eq!(err.message, "query must not be empty");
+
+        builder.add_input::<Query>("ok".to_string()).unwrap();
+        let mut graph = builder.build();
+        assert!(graph.set_input::<Query>(String::new()).is_err());
+        assert_eq!(graph.db().get::<Query>().unwrap(), "ok");
+        assert_eq!(
+            graph.set_input::<Query>("new".to_string()).unwrap(),
+            Some("ok".to_string())
+        );
+    }
+
+    #[test]
+    fn test_validator_checks_existing_value() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder.add_input::<Query>(String::new()).unwrap();
+        assert!(builder.validate_input::<Query>(NON_EMPTY).is_err());
+    }
+
+    #[test]
+    fn test_execute_all_with_validates_inputs() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .validate_input::<Query>(NON_EMPTY)
+            .unwrap()
+            .add_input::<Query>("default".to_string())
+            .unwrap()
+            .add_task::<Greet>()
+            .unwrap();
+        let mut graph = builder.build();
+
+        let err = graph
+            .execute_all_with([DynInput::new::<Query>(String::new())])
+            .unwrap_err();
+        assert_eq!(
+            err,
+            ExecuteWithError::InvalidInput(InvalidInput {
+                key: type_name::<Query>(),
+                message: "query must not be empty".to_string(),
+            })
+        );
+        assert!(graph.db().get::<Reply>().is_none());
+        assert_eq!(graph.db().get::<Query>().unwrap(), "default");
+    }
+}
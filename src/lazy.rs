@@ -0,0 +1,106 @@
+use std::{
+    cell::{Cell, OnceCell},
+    fmt,
+    ops::Deref,
+};
+
+type Init<T> = Box<dyn FnOnce() -> Result<T, String>>;
+
+/// A value computed, e.g. deserialized, on first access, returned by
+/// [`DataBase::get_lazy`](crate::DataBase::get_lazy). A task that holds a
+/// `Lazy` in its input pays for loading the value only if it reads it.
+pub struct Lazy<T> {
+    init: Cell<Option<Init<T>>>,
+    value: OnceCell<Result<T, String>>,
+}
+
+impl<T> Lazy<T> {
+    pub fn new(init: impl FnOnce() -> T + 'static) -> Self {
+        Lazy::try_new(move || Ok(init()))
+    }
+
+    /// A `Lazy` whose loading can fail, e.g. because the value's storage
+    /// cannot be read.
+    pub fn try_new(init: impl FnOnce() -> Result<T, String> + 'static) -> Self {
+        Lazy {
+            init: Cell::new(Some(Box::new(init))),
+            value: OnceCell::new(),
+        }
+    }
+
+    /// A `Lazy` whose value is already available.
+    pub fn ready(value: T) -> Self {
+        Lazy {
+            init: Cell::new(None),
+            value: OnceCell::from(Ok(value)),
+        }
+    }
+
+    /// The value, loading it first if needed, or why it could not be loaded.
+    pub fn try_get(&self) -> Result<&T, &str> {
+        let value = self.value.get_or_init(|| match self.init.take() {
+            Some(init) => init(),
+            None => panic!("Lazy value panicked while being initialized"),
+        });
+        value.as_ref().map_err(String::as_str)
+    }
+
+    /// # Panics
+    ///
+    /// If the value could not be loaded; see [`try_get`](Self::try_get).
+    pub fn get(&self) -> &T {
+        self.try_get()
+            .unwrap_or_else(|e| panic!("Lazy value could not be loaded: {e}"))
+    }
+
+    /// Whether the value has been computed yet.
+    pub fn is_loaded(&self) -> bool {
+        self.value.get().is_some_and(Result::is_ok)
+    }
+
+    pub fn into_inner(self) -> T {
+        self.get();
+        self.value.into_inner().unwrap().ok().unwrap()
+    }
+}
+
+impl<T> Deref for Lazy<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.get()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Lazy<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.value.get() {
+            Some(Ok(value)) => f.debug_tuple("Lazy").field(value).finish(),
+            Some(Err(e)) => write!(f, "Lazy(<failed: {e}>)"),
+            None => f.write_str("Lazy(<not loaded>)"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lazy_loads_once() {
+        let calls = std::rc::Rc::new(Cell::new(0));
+        let counter = calls.clone();
+        let lazy = Lazy::new(move || {
+            counter.set(counter.get() + 1);
+            vec![1, 2, 3]
+        });
+        assert!(!lazy.is_loaded());
+        assert_eq!(format!("{lazy:?}"), "Lazy(<not loaded>)");
+
+        assert_eq!(lazy.len(), 3);
+        assert_eq!(lazy[0], 1);
+        assert!(lazy.is_loaded());
+        assert_eq!(calls.get(), 1);
+        assert_eq!(lazy.into_inner(), vec![1, 2, 3]);
+    }
+}
@@ -1,55 +1,438 @@
 use std::{
-    any::{Any, TypeId},
-    collections::HashMap,
+    any::{type_name, Any, TypeId},
+    collections::{HashMap, HashSet},
+    fmt,
+    rc::Rc,
+    sync::Arc,
+    time::Instant,
 };
 
 use petgraph::graph::NodeIndex;
 
+pub mod adapter;
+pub mod arc_value;
+pub mod borrow;
+pub mod budget;
+pub mod cas;
+pub mod clock;
+pub mod context;
+pub mod critical_path;
+pub mod cycle;
+pub mod debugger;
+pub mod describe;
+pub mod descriptor;
+pub mod dot;
+pub mod dynamic;
+pub mod edit;
+pub mod error;
+pub mod files;
+pub mod fingerprint;
+pub mod fixpoint;
+pub mod handle;
+pub mod history;
+pub mod hooks;
+#[cfg(feature = "http")]
+pub mod http;
+mod idempotency;
+pub mod input;
+mod json;
+pub mod lazy;
+pub mod limits;
+pub mod lineage;
+pub mod lints;
+pub mod map;
+pub mod map_reduce;
+pub mod merge;
+pub mod metadata;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod offload;
+mod panic;
+pub mod parallelism;
+pub mod poison;
+pub mod provenance;
+pub mod reach;
+pub mod recovery;
+pub mod reduce;
+pub mod registry;
+pub mod retention;
+pub mod shared;
+pub mod slot_db;
+pub mod spawn;
+pub mod spill;
+pub mod stats;
+pub mod status;
+pub mod swap;
+pub mod testing;
+pub mod timeline;
+pub mod undo;
+#[cfg(feature = "web-ui")]
+pub mod web_ui;
+
+pub use context::{Scratch, TaskContext};
+pub use error::{
+    AddTasksError, BudgetExceeded, ExecuteWithError, ExecutionError, FixpointError, GraphRunError,
+    InputWritten, InvalidInput, MissingDependency, MissingOutput, NotConverged,
+    OutputAssertionFailed, OutputTypeMismatch, Overwrite, Poisoned, TaskEditError, TaskPanicked,
+    TypeMismatch,
+};
+pub use metadata::TaskMetadata;
+pub use status::TaskStatus;
+
 pub trait DbKey: 'static {
     type Value: 'static;
 }
 
+/// The identity of a key together with its human-readable name.
+#[derive(Copy, Clone, Debug)]
+pub struct KeyType {
+    pub(crate) id: TypeId,
+    pub(crate) name: &'static str,
+}
+
+/// Identifies a value of the graph, i.e. a key. Stable for the lifetime of
+/// the process, whatever happens to the graph.
+pub type ValueId = KeyType;
+
+impl KeyType {
+    pub fn of<K: DbKey>() -> Self {
+        KeyType {
+            id: TypeId::of::<K>(),
+            name: type_name::<K>(),
+        }
+    }
+
+    /// Type name of the key.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+impl fmt::Display for KeyType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name)
+    }
+}
+
+/// Identifies a task of the graph. Stable for the lifetime of the process,
+/// whatever happens to the graph.
+#[derive(Copy, Clone, Debug)]
+pub struct TaskId {
+    id: TypeId,
+    name: &'static str,
+}
+
+impl TaskId {
+    /// The task's [`name`](Task::name).
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+impl PartialEq for TaskId {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for TaskId {}
+
+impl std::hash::Hash for TaskId {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl fmt::Display for TaskId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name)
+    }
+}
+
+impl PartialEq for KeyType {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for KeyType {}
+
+impl std::hash::Hash for KeyType {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.id.hash(state)
+    }
+}
+
 pub trait DataBase {
     fn get<K: DbKey>(&self) -> Option<&K::Value>;
+    /// Like [`get`](Self::get), but tells a missing value apart from one of
+    /// the wrong type, which `get` also reports as `None`. Backends that
+    /// cannot tell them apart, the default, never fail.
+    fn try_get<K: DbKey>(&self) -> Result<Option<&K::Value>, TypeMismatch> {
+        Ok(self.get::<K>())
+    }
     fn get_cloned<K: DbKey>(&self) -> Option<K::Value>
     where
         K::Value: Clone,
     {
         self.get::<K>().cloned()
     }
+    /// A handle to the value of `K` that loads it on first access, for
+    /// backends where reading a value is expensive. The default reads the
+    /// value right away.
+    fn get_lazy<K: DbKey>(&self) -> Option<lazy::Lazy<K::Value>>
+    where
+        K::Value: Clone,
+    {
+        self.get_cloned::<K>().map(lazy::Lazy::ready)
+    }
     fn put<K: DbKey>(&mut self, value: K::Value) -> Option<K::Value>;
+    fn remove<K: DbKey>(&mut self) -> Option<K::Value>;
+
+    /// The value of [`ArcValue<K>`](arc_value::ArcValue), shared instead of
+    /// cloned.
+    fn get_shared<K: DbKey>(&self) -> Option<Arc<K::Value>> {
+        self.get_cloned::<arc_value::ArcValue<K>>()
+    }
+    fn put_shared<K: DbKey>(&mut self, value: Arc<K::Value>) -> Option<Arc<K::Value>> {
+        self.put::<arc_value::ArcValue<K>>(value)
+    }
+
+    /// Called before a run with every key its tasks read, so that backends
+    /// with slow storage can load the values ahead of time. Does nothing by
+    /// default.
+    fn prefetch(&mut self, _keys: &[KeyType]) {}
+
+    /// Called when a graph is built with every key its tasks read or write,
+    /// so that backends can lay out storage for them up front. Does nothing
+    /// by default.
+    fn reserve(&mut self, _keys: &[KeyType]) {}
+
+    /// Whether a value of `key` is stored, for backends that can tell
+    /// without knowing its value type. The graph uses it to check that tasks
+    /// write every key they declare. `None`, for unknown, by default.
+    fn contains(&self, _key: KeyType) -> Option<bool> {
+        None
+    }
+
+    /// Removes the value of `key` without knowing its value type, for
+    /// backends that can, returning it. The graph uses it to invalidate the
+    /// keys a task declares. Backends that cannot keep the value, the
+    /// default.
+    fn remove_key(&mut self, _key: KeyType) -> Option<Box<dyn Any>> {
+        None
+    }
+
+    /// The number of writes and removals so far, for backends that count
+    /// them, like [`VersionedDb`](history::VersionedDb). `None` by default.
+    fn revision(&self) -> Option<u64> {
+        None
+    }
+
+    /// Starts holding back writes and removals until
+    /// [`commit`](Self::commit) or [`rollback`](Self::rollback), for
+    /// backends that can. With
+    /// [`transactional_outputs`](ExecutionGraph::transactional_outputs), the
+    /// graph wraps each task's writes and output checks in one. Backends
+    /// that cannot stage apply writes right away, the default.
+    fn begin(&mut self) {}
+    /// Applies the writes held back since [`begin`](Self::begin).
+    fn commit(&mut self) {}
+    /// Drops the writes held back since [`begin`](Self::begin).
+    fn rollback(&mut self) {}
+
+    /// Starts recording the keys written and removed, for backends that
+    /// can. With [`strict_writes`](ExecutionGraph::strict_writes), the graph
+    /// records each task's writes to check them.
+    fn record_writes(&mut self) {}
+    /// The writes recorded since [`record_writes`](Self::record_writes),
+    /// ending the recording. `None` if the backend does not record them, the
+    /// default.
+    fn take_writes(&mut self) -> Option<Vec<Write>> {
+        None
+    }
+}
+
+/// A key written or removed while a database was
+/// [recording writes](DataBase::record_writes).
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Write {
+    pub key: KeyType,
+    /// Whether the key held a value before.
+    pub replaced: bool,
 }
 
+/// Values written or, if `None`, removed in the open transaction.
+type Staged = HashMap<TypeId, Option<Box<dyn Any>>>;
+
+/// Stages writes between [`DataBase::begin`] and [`DataBase::commit`]; while
+/// a transaction is open, `put` and `remove` only return values written in
+/// it, as committed values are kept in case of a rollback.
 pub struct InMemoryDb {
     data: HashMap<TypeId, Box<dyn Any>>,
+    /// Type name of the value last written to each key.
+    stored: HashMap<TypeId, &'static str>,
+    staged: Option<Staged>,
+    recording: Option<Vec<Write>>,
 }
 
 impl InMemoryDb {
     pub fn new() -> Self {
         InMemoryDb {
             data: HashMap::new(),
+            stored: HashMap::new(),
+            staged: None,
+            recording: None,
+        }
+    }
+
+    fn record(&mut self, key: KeyType) {
+        let replaced = self.recording.is_some() && self.contains(key) == Some(true);
+        if let Some(recording) = &mut self.recording {
+            recording.push(Write { key, replaced });
+        }
+    }
+
+    fn write(&mut self, key: TypeId, value: Option<Box<dyn Any>>) -> Option<Box<dyn Any>> {
+        match (&mut self.staged, value) {
+            (Some(staged), value) => staged.insert(key, value).flatten(),
+            (None, Some(value)) => self.data.insert(key, value),
+            (None, None) => self.data.remove(&key),
         }
     }
 }
 
+impl Default for InMemoryDb {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl DataBase for InMemoryDb {
     fn get<K: DbKey>(&self) -> Option<&K::Value> {
+        self.try_get::<K>().ok().flatten()
+    }
+
+    fn contains(&self, key: KeyType) -> Option<bool> {
+        let staged = self.staged.as_ref().and_then(|staged| staged.get(&key.id));
+        Some(match staged {
+            Some(value) => value.is_some(),
+            None => self.data.contains_key(&key.id),
+        })
+    }
+
+    fn try_get<K: DbKey>(&self) -> Result<Option<&K::Value>, TypeMismatch> {
         let t = TypeId::of::<K>();
-        self.data.get(&t).and_then(|v| v.downcast_ref::<K::Value>())
+        let value = match self.staged.as_ref().and_then(|staged| staged.get(&t)) {
+            Some(staged) => staged.as_ref(),
+            None => self.data.get(&t),
+        };
+        let Some(value) = value else {
+            return Ok(None);
+        };
+        value
+            .downcast_ref::<K::Value>()
+            .map(Some)
+            .ok_or_else(|| TypeMismatch {
+                key: type_name::<K>(),
+                expected: type_name::<K::Value>(),
+                stored: self.stored.get(&t).copied(),
+            })
     }
 
     fn put<K: DbKey>(&mut self, value: K::Value) -> Option<K::Value> {
-        self.data
-            .insert(TypeId::of::<K>(), Box::new(value))
+        self.record(KeyType::of::<K>());
+        self.stored
+            .insert(TypeId::of::<K>(), type_name::<K::Value>());
+        self.write(TypeId::of::<K>(), Some(Box::new(value)))
             .and_then(|v| v.downcast::<K::Value>().ok().map(|v| *v))
     }
+
+    fn remove<K: DbKey>(&mut self) -> Option<K::Value> {
+        self.record(KeyType::of::<K>());
+        self.write(TypeId::of::<K>(), None)
+            .and_then(|v| v.downcast::<K::Value>().ok().map(|v| *v))
+    }
+
+    fn remove_key(&mut self, key: KeyType) -> Option<Box<dyn Any>> {
+        self.record(key);
+        self.write(key.id, None)
+    }
+
+    /// Transactions do not nest: a `begin` inside one keeps staging into it.
+    fn begin(&mut self) {
+        self.staged.get_or_insert_with(HashMap::new);
+    }
+
+    fn commit(&mut self) {
+        for (key, value) in self.staged.take().into_iter().flatten() {
+            self.write(key, value);
+        }
+    }
+
+    fn rollback(&mut self) {
+        self.staged = None;
+    }
+
+    fn record_writes(&mut self) {
+        self.recording = Some(Vec::new());
+    }
+
+    fn take_writes(&mut self) -> Option<Vec<Write>> {
+        self.recording.take()
+    }
 }
 
-pub trait Task<Db: DataBase> {
+pub trait Task<Db: DataBase>: 'static {
     type Input: TaskInput<Db>;
     type Output: TaskOutput<Db>;
 
     fn execute(input: Self::Input) -> Self::Output;
+
+    /// The name the task is looked up by, e.g. in
+    /// [`ExecutionGraph::task_by_name`]. Defaults to the type name without its
+    /// module path.
+    fn name() -> &'static str {
+        short_type_name(type_name::<Self>())
+    }
+}
+
+/// A task that also receives the graph's shared context (see
+/// [`ExecutionGraphBuilder::with_context`]), for services such as loggers,
+/// metrics handles or configuration that are not values in the database, and
+/// its own [`Scratch`] space.
+///
+/// Every [`Task`] is a `TaskWithContext` that ignores the context.
+pub trait TaskWithContext<Db: DataBase, Ctx>: 'static {
+    type Input: TaskInput<Db>;
+    type Output: TaskOutput<Db>;
+
+    fn execute(input: Self::Input, ctx: &mut TaskContext<'_, Ctx>) -> Self::Output;
+
+    fn name() -> &'static str {
+        short_type_name(type_name::<Self>())
+    }
+}
+
+impl<Db: DataBase, Ctx, T: Task<Db>> TaskWithContext<Db, Ctx> for T {
+    type Input = T::Input;
+    type Output = T::Output;
+
+    fn execute(input: Self::Input, _ctx: &mut TaskContext<'_, Ctx>) -> Self::Output {
+        <T as Task<Db>>::execute(input)
+    }
+
+    fn name() -> &'static str {
+        <T as Task<Db>>::name()
+    }
+}
+
+fn short_type_name(name: &'static str) -> &'static str {
+    let base = name.split('<').next().unwrap_or(name);
+    match base.rfind("::") {
+        Some(i) => &name[i + 2..],
+        None => name,
+    }
 }
 
 impl DbKey for () {
@@ -57,9 +440,7 @@ impl DbKey for () {
 }
 
 impl<Db: DataBase> TaskInput<Db> for () {
-    fn from_db(_db: &Db) -> Self {
-        ()
-    }
+    fn from_db(_db: &Db) -> Self {}
 }
 
 impl<Db: DataBase> TaskOutput<Db> for () {
@@ -71,7 +452,7 @@ where
     Self: Sized + 'static,
 {
     fn from_db(db: &Db) -> Self;
-    fn dep_types() -> Vec<TypeId> {
+    fn dep_types() -> Vec<KeyType> {
         vec![]
     }
 }
@@ -81,43 +462,627 @@ where
     Self: Sized + 'static,
 {
     fn to_db(&self, db: &mut Db);
-    fn out_types() -> Vec<TypeId> {
+    fn out_types() -> Vec<KeyType> {
         vec![]
     }
 }
 
-pub struct ExecutionGraph<Db: DataBase> {
+pub(crate) struct TaskEntry<Db: DataBase, Ctx> {
+    pub(crate) id: TypeId,
+    pub(crate) name: &'static str,
+    pub(crate) input: KeyType,
+    pub(crate) output: KeyType,
+    pub(crate) input_node: NodeIndex,
+    pub(crate) output_node: NodeIndex,
+    pub(crate) deps: Vec<KeyType>,
+    pub(crate) writes: Vec<KeyType>,
+    /// Runs the task with the given id, its own.
+    pub(crate) run: fn(&mut ExecutionGraph<Db, Ctx>, TypeId) -> Result<(), ExecutionError>,
+    /// Whether the task's output is stored, given the keys it writes.
+    pub(crate) has_output: fn(&Db, &[KeyType]) -> bool,
+    /// Removes the task's output, given the keys it writes.
+    pub(crate) invalidate: fn(&mut Db, &[KeyType]),
+}
+
+impl<Db: DataBase, Ctx> TaskEntry<Db, Ctx> {
+    pub(crate) fn task_id(&self) -> TaskId {
+        TaskId {
+            id: self.id,
+            name: self.name,
+        }
+    }
+
+    pub(crate) fn output_stored(&self, db: &Db) -> bool {
+        (self.has_output)(db, &self.writes)
+    }
+
+    pub(crate) fn invalidate(&self, db: &mut Db) {
+        (self.invalidate)(db, &self.writes)
+    }
+}
+
+impl<Db: DataBase, Ctx> Clone for TaskEntry<Db, Ctx> {
+    fn clone(&self) -> Self {
+        TaskEntry {
+            id: self.id,
+            name: self.name,
+            input: self.input,
+            output: self.output,
+            input_node: self.input_node,
+            output_node: self.output_node,
+            deps: self.deps.clone(),
+            writes: self.writes.clone(),
+            run: self.run,
+            has_output: self.has_output,
+            invalidate: self.invalidate,
+        }
+    }
+}
+
+pub struct ExecutionGraph<Db: DataBase, Ctx = ()> {
     tasks: petgraph::graph::DiGraph<TypeId, fn(&mut Db)>,
+    /// A node of each type in `tasks`, for constant-time lookup.
+    nodes: HashMap<TypeId, NodeIndex>,
     db: Db,
+    ctx: Ctx,
+    scratch: HashMap<TypeId, Scratch>,
+    side_effects: HashMap<TypeId, idempotency::SideEffects>,
+    entries: Vec<TaskEntry<Db, Ctx>>,
+    names: HashMap<TypeId, &'static str>,
+    producers: HashMap<TypeId, &'static str>,
+    /// Keys set from outside the graph's tasks.
+    inputs: HashSet<TypeId>,
+    /// Type names of the values of `inputs`, whose keys, unlike task
+    /// outputs, are usually not their own value type.
+    #[cfg(feature = "web-ui")]
+    value_types: HashMap<TypeId, &'static str>,
+    validators: HashMap<TypeId, input::Validator>,
+    output_hooks: HashMap<TypeId, Vec<hooks::OutputHook<Db>>>,
+    catch_panics: bool,
+    transactional: bool,
+    strict_writes: bool,
+    immutable_inputs: bool,
+    clock: Option<Rc<dyn clock::Clock>>,
+    files: Vec<files::FileEntry<Db>>,
+    #[cfg(feature = "http")]
+    http_sources: Vec<http::HttpEntry<Db>>,
+    shared: Option<shared::SharedDb<Db>>,
+    poisoned: HashSet<TypeId>,
+    /// The last error of each failed task, with the inputs it read.
+    failures: HashMap<TypeId, (ExecutionError, provenance::Provenance)>,
+    stubs: HashMap<TypeId, Rc<dyn Fn() -> Box<dyn Any>>>,
+    swapped: HashMap<TypeId, Rc<dyn swap::DynTask<Db>>>,
+    /// Implementations of tasks whose own `execute` cannot run, like
+    /// adapters. Unlike swapped ones, they are kept by `restore_task`.
+    runners: HashMap<TypeId, Rc<dyn swap::DynTask<Db>>>,
+    /// Implementations of tasks added from a
+    /// [`TaskDescriptor::new`](descriptor::TaskDescriptor::new).
+    executables: HashMap<TypeId, descriptor::Executable<Db>>,
+    sub_tasks: HashMap<TypeId, spawn::WriteSpawned<Db>>,
+    /// The [`Spawned`](spawn::Spawned) keys, written on behalf of whichever
+    /// task spawns their sub-task.
+    spawned: HashSet<TypeId>,
+    fixpoints: HashMap<TypeId, Rc<fixpoint::Fixpoint<Db, Ctx>>>,
+    /// `(key, task)` pairs where `task` reads the previous value of `key`.
+    lazy_edges: HashSet<(TypeId, TypeId)>,
+    limits: limits::RunLimits,
+    errors: recovery::ErrorHandling,
+    offloads: offload::Offloads<Db, Ctx>,
+    reachability: reach::ReachabilityCache,
+    provenance: provenance::Provenances,
+    undo: undo::UndoHistory<Db>,
+    metadata: HashMap<TypeId, metadata::TaskMetadata>,
+    /// Tasks marked as producing final results.
+    terminal: HashSet<TypeId>,
+    evictable: Vec<retention::Evictable<Db>>,
+    evicted: HashSet<TypeId>,
+    memory: budget::MemoryBudget<Db>,
+    run: u64,
+    run_started: Option<Instant>,
+    /// The database's [revision](DataBase::revision) when the last run
+    /// started.
+    run_revision: Option<u64>,
+    records: HashMap<TypeId, status::TaskRecord>,
+    #[cfg(feature = "metrics")]
+    metrics: metrics::Metrics,
+    #[cfg(feature = "web-ui")]
+    web_ui: Option<web_ui::SharedState>,
 }
 
 impl<Db: DataBase> ExecutionGraph<Db> {
     pub fn new(db: Db) -> Self {
+        ExecutionGraph::with_context(db, ())
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraph<Db, Ctx> {
+    pub fn with_context(db: Db, ctx: Ctx) -> Self {
         ExecutionGraph {
             db,
+            ctx,
+            scratch: HashMap::new(),
+            side_effects: HashMap::new(),
             tasks: petgraph::graph::DiGraph::new(),
+            nodes: HashMap::new(),
+            entries: Vec::new(),
+            names: HashMap::new(),
+            producers: HashMap::new(),
+            inputs: HashSet::new(),
+            #[cfg(feature = "web-ui")]
+            value_types: HashMap::new(),
+            validators: HashMap::new(),
+            output_hooks: HashMap::new(),
+            catch_panics: false,
+            transactional: false,
+            strict_writes: false,
+            immutable_inputs: false,
+            clock: None,
+            files: Vec::new(),
+            #[cfg(feature = "http")]
+            http_sources: Vec::new(),
+            shared: None,
+            poisoned: HashSet::new(),
+            failures: HashMap::new(),
+            stubs: HashMap::new(),
+            swapped: HashMap::new(),
+            runners: HashMap::new(),
+            executables: HashMap::new(),
+            sub_tasks: HashMap::new(),
+            spawned: HashSet::new(),
+            fixpoints: HashMap::new(),
+            lazy_edges: HashSet::new(),
+            limits: limits::RunLimits::default(),
+            errors: recovery::ErrorHandling::default(),
+            offloads: offload::Offloads::default(),
+            reachability: reach::ReachabilityCache::default(),
+            provenance: provenance::Provenances::default(),
+            undo: undo::UndoHistory::default(),
+            metadata: HashMap::new(),
+            terminal: HashSet::new(),
+            evictable: Vec::new(),
+            evicted: HashSet::new(),
+            memory: budget::MemoryBudget::new(),
+            run: 0,
+            run_started: None,
+            run_revision: None,
+            records: HashMap::new(),
+            #[cfg(feature = "metrics")]
+            metrics: metrics::Metrics::default(),
+            #[cfg(feature = "web-ui")]
+            web_ui: None,
+        }
+    }
+
+    /// The graph's database. For a graph over a [`SharedDb`](shared::SharedDb)
+    /// this is only a placeholder outside of a run; see
+    /// [`shared_db`](Self::shared_db).
+    pub fn db(&self) -> &Db {
+        &self.db
+    }
+
+    pub fn context(&self) -> &Ctx {
+        &self.ctx
+    }
+
+    pub fn context_mut(&mut self) -> &mut Ctx {
+        &mut self.ctx
+    }
+
+    /// When enabled, a panicking task fails with
+    /// [`ExecutionError::TaskPanicked`] instead of unwinding through the
+    /// caller.
+    pub fn catch_panics(&mut self, enabled: bool) -> &mut Self {
+        self.catch_panics = enabled;
+        self
+    }
+
+    /// When enabled, each task's writes are staged, with
+    /// [`DataBase::begin`], and only committed once the task and the checks
+    /// on its outputs succeed. A failed task then leaves the keys it writes
+    /// as they were, instead of possibly half written.
+    pub fn transactional_outputs(&mut self, enabled: bool) -> &mut Self {
+        self.transactional = enabled;
+        self
+    }
+
+    /// When enabled, a task that replaces the value of a key another task or
+    /// an input produces fails with [`ExecutionError::Overwrite`], instead of
+    /// silently clobbering it. Writes are staged as with
+    /// [`transactional_outputs`](Self::transactional_outputs), so the
+    /// clobbered value is restored. Only checked on databases that
+    /// [record writes](DataBase::record_writes), like [`InMemoryDb`].
+    pub fn strict_writes(&mut self, enabled: bool) -> &mut Self {
+        self.strict_writes = enabled;
+        self
+    }
+
+    /// When enabled, a task that writes or removes an input fails with
+    /// [`ExecutionError::InputWritten`], and its writes are rolled back as
+    /// with [`transactional_outputs`](Self::transactional_outputs); inputs
+    /// then only change through [`set_input`](Self::set_input) and the like.
+    /// Only checked on databases that
+    /// [record writes](DataBase::record_writes).
+    pub fn immutable_inputs(&mut self, enabled: bool) -> &mut Self {
+        self.immutable_inputs = enabled;
+        self
+    }
+
+    /// Copies the graph's tasks, inputs, validators and hooks into a new graph
+    /// over `db`, without rebuilding it from a builder. Run state (poison,
+    /// scratch spaces, pending offloads, undo history, memory usage, an
+    /// attached web UI) is not copied, and neither is the data: inputs must be
+    /// set again on the new graph, while watched files and HTTP sources are
+    /// read again by the next refresh.
+    pub fn clone_structure(&self, db: Db) -> Self
+    where
+        Ctx: Clone,
+    {
+        ExecutionGraph {
+            tasks: self.tasks.clone(),
+            nodes: self.nodes.clone(),
+            db,
+            ctx: self.ctx.clone(),
+            scratch: HashMap::new(),
+            side_effects: HashMap::new(),
+            entries: self.entries.clone(),
+            names: self.names.clone(),
+            producers: self.producers.clone(),
+            inputs: self.inputs.clone(),
+            #[cfg(feature = "web-ui")]
+            value_types: self.value_types.clone(),
+            validators: self.validators.clone(),
+            output_hooks: self.output_hooks.clone(),
+            catch_panics: self.catch_panics,
+            transactional: self.transactional,
+            strict_writes: self.strict_writes,
+            immutable_inputs: self.immutable_inputs,
+            clock: self.clock.clone(),
+            files: self.files.clone(),
+            #[cfg(feature = "http")]
+            http_sources: self.http_sources.clone(),
+            shared: None,
+            poisoned: HashSet::new(),
+            failures: HashMap::new(),
+            stubs: self.stubs.clone(),
+            swapped: self.swapped.clone(),
+            runners: self.runners.clone(),
+            executables: self.executables.clone(),
+            sub_tasks: self.sub_tasks.clone(),
+            spawned: self.spawned.clone(),
+            fixpoints: self.fixpoints.clone(),
+            lazy_edges: self.lazy_edges.clone(),
+            limits: self.limits.clone(),
+            errors: self.errors.clone(),
+            offloads: self.offloads.without_jobs(),
+            reachability: reach::ReachabilityCache::default(),
+            provenance: provenance::Provenances::default(),
+            undo: self.undo.without_history(),
+            metadata: self.metadata.clone(),
+            terminal: self.terminal.clone(),
+            evictable: self.evictable.clone(),
+            evicted: HashSet::new(),
+            memory: self.memory.without_usage(),
+            run: 0,
+            run_started: None,
+            run_revision: None,
+            records: HashMap::new(),
+            #[cfg(feature = "metrics")]
+            metrics: metrics::Metrics::default(),
+            #[cfg(feature = "web-ui")]
+            web_ui: None,
         }
     }
 
     fn contains_node(&self, ty: &TypeId) -> Option<NodeIndex> {
-        self.tasks.node_indices().find(|i| &self.tasks[*i] == ty)
+        self.nodes.get(ty).copied()
+    }
+
+    fn name_of(&self, ty: &TypeId) -> String {
+        match self.names.get(ty) {
+            Some(name) => name.to_string(),
+            None => format!("{:?}", ty),
+        }
     }
 
-    pub fn execute<T: Task<Db>>(&mut self) -> T::Output {
-        for ty in T::Input::dep_types() {
-            if let None = self.contains_node(&ty) {
-                panic!("Missing dependency: {:?}", ty)
+    /// Registers `K` as an input set from outside the graph's tasks, by
+    /// `producer`.
+    pub(crate) fn register_input<K: DbKey>(&mut self, producer: &'static str) {
+        let key = KeyType::of::<K>();
+        self.register(key);
+        self.producers.insert(key.id, producer);
+        self.inputs.insert(key.id);
+        #[cfg(feature = "web-ui")]
+        self.value_types.insert(key.id, type_name::<K::Value>());
+    }
+
+    fn register(&mut self, key: KeyType) -> NodeIndex {
+        self.names.insert(key.id, key.name);
+        let index = self.tasks.add_node(key.id);
+        self.reachability.invalidate();
+        self.nodes.entry(key.id).or_insert(index);
+        index
+    }
+
+    fn check_dependency<T: TaskWithContext<Db, Ctx>>(
+        &self,
+        key: KeyType,
+    ) -> Result<(), MissingDependency> {
+        if self.contains_node(&key.id).is_some() || self.is_lazy_edge(key, TypeId::of::<T>()) {
+            Ok(())
+        } else {
+            Err(self.missing_dependency(key, type_name::<T>()))
+        }
+    }
+
+    pub(crate) fn missing_dependency(&self, key: KeyType, task: &'static str) -> MissingDependency {
+        let mut producers: Vec<(String, String)> = self
+            .producers
+            .iter()
+            .map(|(ty, producer)| (self.name_of(ty), producer.to_string()))
+            .collect();
+        producers.sort();
+        MissingDependency {
+            key: key.name,
+            task,
+            producers,
+        }
+    }
+
+    /// Runs `T`. If it fails, the graph's
+    /// [`GraphErrorHandler`](recovery::GraphErrorHandler) decides whether to
+    /// retry it, substitute its fallback output or return the error.
+    pub fn execute<T: TaskWithContext<Db, Ctx>>(&mut self) -> Result<T::Output, ExecutionError> {
+        // Only a skip decided for this task may let `execute_all` go on.
+        self.take_skipped();
+        let mut attempt = 1;
+        loop {
+            let error = match self.execute_once::<T>() {
+                Ok(output) => return Ok(output),
+                Err(error) => error,
+            };
+            match self.recover::<T>(&error, attempt) {
+                (recovery::Recovery::Retry, _) => attempt += 1,
+                (_, Some(fallback)) => return Ok(fallback),
+                _ => return Err(error),
             }
         }
-        let input = T::Input::from_db(&self.db);
-        let output = T::execute(input);
-        output.to_db(&mut self.db);
-        output
+    }
+
+    fn execute_once<T: TaskWithContext<Db, Ctx>>(&mut self) -> Result<T::Output, ExecutionError> {
+        self.await_offloaded(&T::Input::dep_types())?;
+        self.check_limits::<T>()?;
+        self.with_shared_db(|graph| {
+            if !graph.limits.in_run() {
+                graph.refresh_now();
+            }
+            let started = Instant::now();
+            let result = graph.run_task::<T>();
+            graph.record_result::<T, _>(started, &result);
+            result
+        })
+    }
+
+    /// Records how a run of `T` started at `started` went.
+    pub(crate) fn record_result<T: TaskWithContext<Db, Ctx>, O>(
+        &mut self,
+        started: Instant,
+        result: &Result<O, ExecutionError>,
+    ) {
+        self.record_run::<T>(started, result.is_ok());
+        match result {
+            Ok(_) => {
+                self.failures.remove(&TypeId::of::<T>());
+            }
+            Err(e) => self.record_failure(TypeId::of::<T>(), e.clone()),
+        }
+    }
+
+    fn run_task<T: TaskWithContext<Db, Ctx>>(&mut self) -> Result<T::Output, ExecutionError> {
+        for key in T::Input::dep_types() {
+            self.check_dependency::<T>(key)?;
+        }
+        self.restore_evicted::<T>()?;
+        self.check_poison::<T>()?;
+        #[cfg(feature = "web-ui")]
+        let started = self.web_ui_task_started::<T>();
+        let result = self.store_output::<T>(|graph| {
+            let id = TypeId::of::<T>();
+            let swapped = graph.swapped.get(&id).or_else(|| graph.runners.get(&id));
+            let mismatch = |_| OutputTypeMismatch {
+                task: type_name::<T>(),
+                expected: type_name::<T::Output>(),
+            };
+            match (graph.stubs.get(&id), swapped) {
+                (Some(stub), _) => Ok(*stub().downcast::<T::Output>().map_err(mismatch)?),
+                (None, Some(task)) => {
+                    let task = task.clone();
+                    let output = task.execute(&graph.db);
+                    graph.record_dynamic_deps(id, task.discovered_deps());
+                    Ok(*output.downcast::<T::Output>().map_err(mismatch)?)
+                }
+                (None, None) => {
+                    let input = T::Input::from_db(&graph.db);
+                    let mut spawner = spawn::Spawner::default();
+                    let mut ctx = TaskContext {
+                        ctx: &graph.ctx,
+                        scratch: graph.scratch.entry(id).or_default(),
+                        side_effects: graph.side_effects.entry(id).or_default(),
+                        spawner: &mut spawner,
+                    };
+                    let output = T::execute(input, &mut ctx);
+                    graph.run_spawned(type_name::<T>(), spawner)?;
+                    Ok(output)
+                }
+            }
+        });
+        #[cfg(feature = "web-ui")]
+        self.web_ui_task_finished(started);
+        result
+    }
+
+    /// Writes the output `produce` computes for `T` to the database, then
+    /// checks its writes and outputs and runs its output hooks, committing
+    /// or rolling back staged writes and poisoning its outputs on failure,
+    /// including a failure of `produce`.
+    pub(crate) fn store_output<T: TaskWithContext<Db, Ctx>>(
+        &mut self,
+        produce: impl FnOnce(&mut Self) -> Result<T::Output, ExecutionError>,
+    ) -> Result<T::Output, ExecutionError> {
+        let run = |graph: &mut Self| {
+            let output = produce(graph)?;
+            output.to_db(&mut graph.db);
+            Ok(output)
+        };
+        let checked = self.strict_writes || self.immutable_inputs;
+        // A rejected write is only undone if it was staged.
+        let staged = self.transactional || checked;
+        if staged {
+            self.db.begin();
+        }
+        if checked {
+            self.db.record_writes();
+        }
+        let output = if self.catch_panics {
+            panic::catch_task_panic(type_name::<T>(), || run(self))
+                .unwrap_or_else(|e| Err(e.into()))
+        } else {
+            run(self)
+        };
+        let writes = self.db.take_writes().unwrap_or_default();
+        let result = output.and_then(|output| {
+            self.check_writes::<T>(&writes)?;
+            self.check_outputs::<T>()?;
+            match self.run_output_hooks::<T>() {
+                Ok(()) => Ok(output),
+                Err(e) => Err(e.into()),
+            }
+        });
+        match &result {
+            _ if !staged => {}
+            Ok(_) => self.db.commit(),
+            Err(_) => self.db.rollback(),
+        }
+        match &result {
+            Ok(_) => {
+                self.side_effects.remove(&TypeId::of::<T>());
+                self.clear_outputs_poison::<T>();
+                self.clear_evicted::<T>();
+                self.touch::<T>();
+                self.enforce_budget();
+            }
+            Err(_) => self.poison_outputs::<T>(),
+        }
+        result
+    }
+
+    /// Executes every task in the order it was added to the builder, which is
+    /// always a valid dependency order.
+    pub fn execute_all(&mut self) -> Result<(), ExecutionError> {
+        self.with_shared_db(|graph| {
+            graph.run += 1;
+            let started = Instant::now();
+            graph.run_started = Some(started);
+            graph.run_revision = graph.db.revision();
+            graph.refresh_now();
+            graph.limits.start();
+            let reads = graph.planned_reads();
+            graph.db.prefetch(&reads);
+            let result = (0..graph.entries.len()).try_for_each(|i| {
+                if graph.runs_with_group(graph.entries[i].id) {
+                    return Ok(());
+                }
+                let (run, id) = (graph.entries[i].run, graph.entries[i].id);
+                match run(graph, id) {
+                    Err(_) if graph.take_skipped() => Ok(()),
+                    result => result,
+                }
+            });
+            let result = result.and(graph.await_all_offloaded());
+            graph.limits.finish();
+            #[cfg(feature = "metrics")]
+            graph.metrics.record_run(started.elapsed());
+            result
+        })
+    }
+
+    /// Fails if `T` wrote an input with
+    /// [`immutable_inputs`](Self::immutable_inputs), or replaced the value of
+    /// a key something else produces with
+    /// [`strict_writes`](Self::strict_writes).
+    fn check_writes<T: 'static>(&self, writes: &[Write]) -> Result<(), ExecutionError> {
+        let task = type_name::<T>();
+        for write in writes {
+            if self.immutable_inputs && self.is_input(write.key.id) {
+                return Err(InputWritten {
+                    task,
+                    key: write.key.name,
+                }
+                .into());
+            }
+            if !self.strict_writes || !write.replaced || self.spawned.contains(&write.key.id) {
+                continue;
+            }
+            match self.producers.get(&write.key.id) {
+                Some(&producer) if producer != task => {
+                    return Err(Overwrite {
+                        task,
+                        key: write.key.name,
+                        producer,
+                    }
+                    .into())
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Fails if `T` did not write a key its output declares.
+    fn check_outputs<T: TaskWithContext<Db, Ctx>>(&self) -> Result<(), MissingOutput> {
+        let missing = T::Output::out_types()
+            .into_iter()
+            .find(|&key| self.db.contains(key) == Some(false));
+        match missing {
+            Some(key) => Err(MissingOutput {
+                task: type_name::<T>(),
+                key: key.name,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether `key` is set from outside the graph's tasks, like the keys
+    /// added with [`add_input`](ExecutionGraphBuilder::add_input).
+    pub(crate) fn is_input(&self, key: TypeId) -> bool {
+        self.inputs.contains(&key)
+    }
+
+    /// Every key read by a task of the graph, in the order of first read.
+    fn planned_reads(&self) -> Vec<KeyType> {
+        let mut seen = HashSet::new();
+        self.entries
+            .iter()
+            .flat_map(|entry| entry.deps.iter().copied())
+            .filter(|key| seen.insert(*key))
+            .collect()
+    }
+
+    /// Lets the database [reserve](DataBase::reserve) storage for every key
+    /// the graph's tasks read or write.
+    fn reserve_keys(&mut self) {
+        let mut seen = HashSet::new();
+        let keys: Vec<_> = self
+            .entries
+            .iter()
+            .flat_map(|entry| entry.deps.iter().chain(&entry.writes).copied())
+            .filter(|key| seen.insert(*key))
+            .collect();
+        self.db.reserve(&keys);
     }
 }
 
-pub struct ExecutionGraphBuilder<Db: DataBase> {
-    graph: ExecutionGraph<Db>,
+pub struct ExecutionGraphBuilder<Db: DataBase, Ctx = ()> {
+    graph: ExecutionGraph<Db, Ctx>,
 }
 
 impl<Db: DataBase> ExecutionGraphBuilder<Db> {
@@ -126,43 +1091,76 @@ impl<Db: DataBase> ExecutionGraphBuilder<Db> {
             graph: ExecutionGraph::new(db),
         }
     }
+}
 
-    pub fn add_input<T: DbKey>(&mut self, value: T::Value) -> &mut Self {
-        self.graph.db.put::<T>(value);
-        self
+impl<Db: DataBase, Ctx> ExecutionGraphBuilder<Db, Ctx> {
+    pub fn with_context(db: Db, ctx: Ctx) -> Self {
+        ExecutionGraphBuilder {
+            graph: ExecutionGraph::with_context(db, ctx),
+        }
     }
 
-    pub fn add_task<T: Task<Db>>(&mut self) -> &mut Self {
-        let task_input_node = self.graph.tasks.add_node(TypeId::of::<T::Input>());
-        for dep_ty in T::Input::dep_types() {
-            let Some(in_node_id) = self.graph.contains_node(&dep_ty) else {
-                panic!("Missing dependency: {:?}", dep_ty)
-            };
+    pub fn add_input<T: DbKey>(&mut self, value: T::Value) -> Result<&mut Self, InvalidInput> {
+        self.graph.validate::<T>(&value)?;
+        self.graph.with_shared_db(|graph| graph.db.put::<T>(value));
+        self.graph.bump_revision(KeyType::of::<T>());
+        if self.graph.contains_node(&TypeId::of::<T>()).is_none() {
+            self.graph.register_input::<T>("add_input");
+        }
+        Ok(self)
+    }
 
-            self.graph
-                .tasks
-                .add_edge(in_node_id, task_input_node, |db| {
-                    let input = T::Input::from_db(db);
-                    db.put::<T::Input>(input);
-                });
-        }
-        let out_node = self.graph.tasks.add_node(TypeId::of::<T::Output>());
-        for out_ty in T::Output::out_types() {
-            match self.graph.contains_node(&out_ty) {
-                Some(_out_node_id) => {
-                    panic!("Output already exists: {:?}", out_ty)
-                }
-                None => {
-                    let out_ty_node = self.graph.tasks.add_node(out_ty);
-                    self.graph.tasks.add_edge(out_node, out_ty_node, |_| {});
-                }
-            }
+    /// Registers `T` as an input without giving it a value, for inputs that
+    /// are only supplied at execution time.
+    pub fn declare_input<T: DbKey>(&mut self) -> &mut Self {
+        if self.graph.contains_node(&TypeId::of::<T>()).is_none() {
+            self.graph.register_input::<T>("declare_input");
         }
         self
     }
 
-    pub fn build(self) -> ExecutionGraph<Db> {
-        self.graph
+    /// Adds task `T`, after checking that every key it reads is already an
+    /// input or the output of an added task. Nodes are looked up by type in
+    /// a hash index, so adding a task takes time proportional to the number
+    /// of keys it reads and writes, and building a graph is linear in its
+    /// size.
+    ///
+    /// # Panics
+    ///
+    /// If `T` declares an output key that is already produced; use
+    /// [`add_tasks`](Self::add_tasks) to get an error instead.
+    pub fn add_task<T: TaskWithContext<Db, Ctx>>(
+        &mut self,
+    ) -> Result<&mut Self, MissingDependency> {
+        self.add_descriptor(descriptor::TaskDescriptor::of::<T>())
+    }
+
+    pub fn build(self) -> ExecutionGraph<Db, Ctx> {
+        let mut graph = self.graph;
+        graph.reserve_keys();
+        graph
+    }
+
+    /// Builds a graph without consuming the builder, so one builder can serve
+    /// as a template for many graphs. The new graph starts from a copy of the
+    /// builder's database.
+    pub fn build_clone(&self) -> ExecutionGraph<Db, Ctx>
+    where
+        Db: Clone,
+        Ctx: Clone,
+    {
+        self.graph.with_db(|db| self.build_with(db.clone()))
+    }
+
+    /// Like [`build_clone`](Self::build_clone), but over `db` instead of a
+    /// copy of the builder's database.
+    pub fn build_with(&self, db: Db) -> ExecutionGraph<Db, Ctx>
+    where
+        Ctx: Clone,
+    {
+        let mut graph = self.graph.clone_structure(db);
+        graph.reserve_keys();
+        graph
     }
 }
 
@@ -183,6 +1181,38 @@ mod tests {
         assert_eq!(db.get::<MyKey>(), Some(&42));
     }
 
+    #[test]
+    fn test_in_memory_db_type_mismatch() {
+        let mut db = InMemoryDb::new();
+        assert_eq!(db.try_get::<MyKey>(), Ok(None));
+        db.put::<MyKey>(1);
+        assert_eq!(db.try_get::<MyKey>(), Ok(Some(&1)));
+
+        db.data.insert(TypeId::of::<MyKey>(), Box::new("one"));
+        db.stored.insert(TypeId::of::<MyKey>(), type_name::<&str>());
+        assert_eq!(db.get::<MyKey>(), None);
+        let mismatch = db.try_get::<MyKey>().unwrap_err();
+        assert_eq!(mismatch.expected, "i32");
+        assert_eq!(mismatch.stored, Some("&str"));
+        assert!(mismatch.to_string().contains("MyKey"));
+    }
+
+    #[test]
+    fn test_in_memory_db_transaction() {
+        let mut db = InMemoryDb::new();
+        db.put::<MyKey>(1);
+        db.begin();
+        assert_eq!(db.put::<MyKey>(2), None);
+        assert_eq!(db.get::<MyKey>(), Some(&2));
+        db.rollback();
+        assert_eq!(db.get::<MyKey>(), Some(&1));
+        db.begin();
+        db.remove::<MyKey>();
+        assert_eq!(db.get::<MyKey>(), None);
+        db.commit();
+        assert_eq!(db.get::<MyKey>(), None);
+    }
+
     #[test]
     fn test_in_memory_db_wrong_key() {
         let mut db = InMemoryDb::new();
@@ -235,10 +1265,254 @@ mod tests {
     #[test]
     fn test_execution_graph() {
         let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
-        builder.add_input::<MyValue>(MyValue { x: 42 });
-        builder.add_task::<MyTask>();
+        builder.add_input::<MyValue>(MyValue { x: 42 }).unwrap();
+        builder.add_task::<MyTask>().unwrap();
         let mut graph = builder.build();
-        graph.execute::<MyTask>();
+        graph.execute::<MyTask>().unwrap();
         assert_eq!(graph.db.get::<MyValue2>(), Some(&MyValue2 { x: 42 }));
     }
+
+    #[test]
+    fn test_build_with() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder.declare_input::<MyValue>();
+        builder.add_task::<MyTask>().unwrap();
+
+        let mut first = builder.build_with(InMemoryDb::new());
+        let mut second = builder.build_with(InMemoryDb::new());
+        first.set_input::<MyValue>(MyValue { x: 42 }).unwrap();
+        second.set_input::<MyValue>(MyValue { x: 1 }).unwrap();
+        first.execute_all().unwrap();
+        second.execute_all().unwrap();
+        assert_eq!(first.db.get::<MyValue2>(), Some(&MyValue2 { x: 42 }));
+        assert_eq!(second.db.get::<MyValue2>(), Some(&MyValue2 { x: 1 }));
+    }
+
+    #[test]
+    fn test_clone_structure() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder.add_input::<MyValue>(MyValue { x: 42 }).unwrap();
+        builder.add_task::<MyTask>().unwrap();
+        let graph = builder.build();
+
+        let mut copy = graph.clone_structure(InMemoryDb::new());
+        assert!(copy.db.get::<MyValue>().is_none());
+        copy.set_input::<MyValue>(MyValue { x: 7 }).unwrap();
+        copy.execute_all().unwrap();
+        assert_eq!(copy.db.get::<MyValue2>(), Some(&MyValue2 { x: 7 }));
+        assert!(graph.db.get::<MyValue2>().is_none());
+    }
+
+    struct Clobber;
+
+    impl DbKey for Clobber {
+        type Value = Clobber;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for Clobber {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<Clobber>(Clobber);
+            db.put::<MyValue2>(MyValue2 { x: 0 });
+        }
+    }
+
+    struct ClobberTask;
+
+    impl Task<InMemoryDb> for ClobberTask {
+        type Input = MyValue;
+        type Output = Clobber;
+
+        fn execute(_input: Self::Input) -> Self::Output {
+            Clobber
+        }
+    }
+
+    #[test]
+    fn test_strict_writes() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder.add_input::<MyValue>(MyValue { x: 42 }).unwrap();
+        builder.add_task::<MyTask>().unwrap();
+        builder.add_task::<ClobberTask>().unwrap();
+        let mut graph = builder.build();
+        graph.strict_writes(true);
+        graph.execute::<MyTask>().unwrap();
+        // A task may replace its own outputs.
+        graph.execute::<MyTask>().unwrap();
+
+        let Err(ExecutionError::Overwrite(err)) = graph.execute::<ClobberTask>() else {
+            panic!("expected an overwrite")
+        };
+        assert_eq!(err.key, type_name::<MyValue2>());
+        assert_eq!(err.producer, type_name::<MyTask>());
+
+        graph.strict_writes(false);
+        assert!(graph.execute::<ClobberTask>().is_ok());
+    }
+
+    struct ClobberInput;
+
+    impl DbKey for ClobberInput {
+        type Value = ClobberInput;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for ClobberInput {
+        fn to_db(&self, db: &mut Db) {
+            db.remove::<MyValue>();
+        }
+    }
+
+    struct ClobberInputTask;
+
+    impl Task<InMemoryDb> for ClobberInputTask {
+        type Input = MyValue;
+        type Output = ClobberInput;
+
+        fn execute(_input: Self::Input) -> Self::Output {
+            ClobberInput
+        }
+    }
+
+    #[test]
+    fn test_immutable_inputs() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder.add_input::<MyValue>(MyValue { x: 42 }).unwrap();
+        builder.add_task::<MyTask>().unwrap();
+        builder.add_task::<ClobberInputTask>().unwrap();
+        let mut graph = builder.build();
+        graph.immutable_inputs(true);
+        graph.execute::<MyTask>().unwrap();
+
+        let Err(ExecutionError::InputWritten(err)) = graph.execute::<ClobberInputTask>() else {
+            panic!("expected an input write")
+        };
+        assert_eq!(err.key, type_name::<MyValue>());
+        assert_eq!(err.task, type_name::<ClobberInputTask>());
+        // The rejected write was rolled back.
+        assert_eq!(graph.db.get::<MyValue>().map(|value| value.x), Some(42));
+
+        graph.set_input::<MyValue>(MyValue { x: 1 }).unwrap();
+        graph.execute::<MyTask>().unwrap();
+        assert_eq!(graph.db.get::<MyValue2>(), Some(&MyValue2 { x: 1 }));
+    }
+
+    struct Forgetful;
+
+    impl DbKey for Forgetful {
+        type Value = Forgetful;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for Forgetful {
+        fn to_db(&self, _db: &mut Db) {}
+
+        fn out_types() -> Vec<KeyType> {
+            vec![KeyType::of::<MyKey>()]
+        }
+    }
+
+    struct ForgetfulTask;
+
+    impl Task<InMemoryDb> for ForgetfulTask {
+        type Input = MyValue;
+        type Output = Forgetful;
+
+        fn execute(_input: Self::Input) -> Self::Output {
+            Forgetful
+        }
+    }
+
+    #[test]
+    fn test_missing_output() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder.add_input::<MyValue>(MyValue { x: 42 }).unwrap();
+        builder.add_task::<ForgetfulTask>().unwrap();
+        let mut graph = builder.build();
+        let Err(ExecutionError::MissingOutput(err)) = graph.execute::<ForgetfulTask>() else {
+            panic!("expected a missing output")
+        };
+        assert_eq!(err.key, type_name::<MyKey>());
+        assert!(err.to_string().contains("did not write it"));
+    }
+
+    struct Unregistered;
+
+    impl DbKey for Unregistered {
+        type Value = Unregistered;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for Unregistered {
+        fn from_db(_db: &Db) -> Self {
+            Unregistered
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<MyKey>()]
+        }
+    }
+
+    struct NeedsMyValue;
+
+    impl Task<InMemoryDb> for NeedsMyValue {
+        type Input = Unregistered;
+        type Output = ();
+
+        fn execute(_input: Self::Input) -> Self::Output {}
+    }
+
+    #[test]
+    fn test_missing_dependency() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder.add_task::<MyTask>().unwrap();
+        let Err(err) = builder.add_task::<NeedsMyValue>() else {
+            panic!("expected a missing dependency")
+        };
+        assert_eq!(err.key, type_name::<MyKey>());
+        assert_eq!(err.task, type_name::<NeedsMyValue>());
+        assert_eq!(
+            err.producers,
+            vec![(
+                type_name::<MyValue2>().to_string(),
+                type_name::<MyTask>().to_string()
+            )]
+        );
+        assert!(err
+            .to_string()
+            .contains("did you forget add_input/add_task for"));
+
+        builder.add_input::<MyKey>(1).unwrap();
+        assert!(builder.add_task::<NeedsMyValue>().is_ok());
+    }
+
+    struct Config {
+        factor: i32,
+    }
+
+    struct ScaleTask;
+
+    impl TaskWithContext<InMemoryDb, Config> for ScaleTask {
+        type Input = MyValue;
+        type Output = MyValue2;
+
+        fn execute(input: Self::Input, ctx: &mut TaskContext<'_, Config>) -> Self::Output {
+            MyValue2 {
+                x: input.x * ctx.factor,
+            }
+        }
+    }
+
+    #[test]
+    fn test_task_with_context() {
+        let mut builder =
+            ExecutionGraphBuilder::with_context(InMemoryDb::new(), Config { factor: 3 });
+        builder
+            .add_input::<MyValue>(MyValue { x: 2 })
+            .unwrap()
+            .add_task::<ScaleTask>()
+            .unwrap();
+        let mut graph = builder.build();
+        assert_eq!(graph.execute::<ScaleTask>().unwrap(), MyValue2 { x: 6 });
+
+        graph.context_mut().factor = 5;
+        graph.execute_all().unwrap();
+        assert_eq!(graph.db().get::<MyValue2>(), Some(&MyValue2 { x: 10 }));
+    }
 }
@@ -0,0 +1,175 @@
+use std::{
+    any::{type_name, TypeId},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    error::BudgetExceeded, DataBase, ExecutionGraph, ExecutionGraphBuilder, TaskWithContext,
+};
+
+/// Limits on a single [`execute_all`](ExecutionGraph::execute_all), set with
+/// [`ExecutionGraphBuilder::executor_config`]. A run over a limit fails with
+/// [`ExecutionError::BudgetExceeded`](crate::ExecutionError::BudgetExceeded)
+/// before starting its next task.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct ExecutorConfig {
+    max_tasks: Option<usize>,
+    max_wall_time: Option<Duration>,
+}
+
+impl ExecutorConfig {
+    /// The most task executions in a run, counting each iteration of a
+    /// [`FixpointGroup`](crate::fixpoint::FixpointGroup).
+    pub fn max_tasks(mut self, max_tasks: usize) -> Self {
+        self.max_tasks = Some(max_tasks);
+        self
+    }
+
+    /// The longest a run may take.
+    pub fn max_wall_time(mut self, max_wall_time: Duration) -> Self {
+        self.max_wall_time = Some(max_wall_time);
+        self
+    }
+}
+
+/// The limit a run went over.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum BudgetLimit {
+    Tasks(usize),
+    WallTime(Duration),
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct RunLimits {
+    config: ExecutorConfig,
+    /// When the current run started and how many tasks it executed, while
+    /// one is in progress.
+    current: Option<(Instant, usize)>,
+}
+
+impl RunLimits {
+    pub(crate) fn start(&mut self) {
+        self.current = Some((Instant::now(), 0));
+    }
+
+    pub(crate) fn finish(&mut self) {
+        self.current = None;
+    }
+
+    /// Whether a run is in progress.
+    pub(crate) fn in_run(&self) -> bool {
+        self.current.is_some()
+    }
+
+    /// Counts a task of the current run, returning the limit it goes over.
+    fn count(&mut self) -> Option<BudgetLimit> {
+        let (started, tasks) = self.current.as_mut()?;
+        if let Some(max) = self.config.max_wall_time {
+            if started.elapsed() > max {
+                return Some(BudgetLimit::WallTime(max));
+            }
+        }
+        if let Some(max) = self.config.max_tasks {
+            if *tasks >= max {
+                return Some(BudgetLimit::Tasks(max));
+            }
+        }
+        *tasks += 1;
+        None
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraphBuilder<Db, Ctx> {
+    pub fn executor_config(&mut self, config: ExecutorConfig) -> &mut Self {
+        self.graph.limits.config = config;
+        self
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraph<Db, Ctx> {
+    /// Fails if running `T` would take the current run over a limit of its
+    /// [`ExecutorConfig`].
+    pub(crate) fn check_limits<T: TaskWithContext<Db, Ctx>>(
+        &mut self,
+    ) -> Result<(), BudgetExceeded> {
+        let Some(limit) = self.limits.count() else {
+            return Ok(());
+        };
+        let (completed, pending) = self
+            .entries
+            .iter()
+            .filter(|entry| entry.id != TypeId::of::<T>())
+            .partition::<Vec<_>, _>(|entry| {
+                self.records
+                    .get(&entry.id)
+                    .is_some_and(|record| record.run == self.run)
+            });
+        Err(BudgetExceeded {
+            task: type_name::<T>(),
+            limit,
+            completed: completed.iter().map(|entry| entry.name).collect(),
+            pending: pending.iter().map(|entry| entry.name).collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExecutionError, InMemoryDb, Task};
+
+    struct First;
+
+    impl Task<InMemoryDb> for First {
+        type Input = ();
+        type Output = ();
+
+        fn execute(_input: Self::Input) -> Self::Output {}
+    }
+
+    struct Second;
+
+    impl Task<InMemoryDb> for Second {
+        type Input = ();
+        type Output = ();
+
+        fn execute(_input: Self::Input) -> Self::Output {}
+    }
+
+    fn graph(config: ExecutorConfig) -> ExecutionGraph<InMemoryDb> {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .executor_config(config)
+            .add_task::<First>()
+            .unwrap()
+            .add_task::<Second>()
+            .unwrap();
+        builder.build()
+    }
+
+    #[test]
+    fn test_max_tasks() {
+        let mut graph = graph(ExecutorConfig::default().max_tasks(1));
+        let Err(ExecutionError::BudgetExceeded(err)) = graph.execute_all() else {
+            panic!("expected the run to exceed its budget");
+        };
+        assert_eq!(err.task, type_name::<Second>());
+        assert_eq!(err.limit, BudgetLimit::Tasks(1));
+        assert_eq!(err.completed, ["First"]);
+        assert!(err.pending.is_empty());
+
+        // Tasks run outside of `execute_all` are not limited.
+        graph.execute::<Second>().unwrap();
+    }
+
+    #[test]
+    fn test_max_wall_time() {
+        let mut graph = graph(ExecutorConfig::default().max_wall_time(Duration::ZERO));
+        let Err(ExecutionError::BudgetExceeded(err)) = graph.execute_all() else {
+            panic!("expected the run to exceed its budget");
+        };
+        assert_eq!(err.task, type_name::<First>());
+        assert!(err.completed.is_empty());
+        assert_eq!(err.pending, ["Second"]);
+    }
+}
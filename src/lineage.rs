@@ -0,0 +1,219 @@
+use std::{
+    fmt::Write as _,
+    hash::Hash,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{fingerprint::Fingerprint, json::push_json_str, DataBase, ExecutionGraph};
+
+const PRODUCER: &str = concat!(
+    "https://crates.io/crates/computation-graph/",
+    env!("CARGO_PKG_VERSION")
+);
+const RUN_EVENT: &str = "https://openlineage.io/spec/2-0-2/OpenLineage.json#/$defs/RunEvent";
+const VERSION_FACET: &str = "https://openlineage.io/spec/facets/1-0-1/DatasetVersionDatasetFacet.json#/$defs/DatasetVersionDatasetFacet";
+
+impl<Db: DataBase, Ctx> ExecutionGraph<Db, Ctx> {
+    /// The tasks of the last [`execute_all`](Self::execute_all) as
+    /// OpenLineage run events, one JSON object per task that ran: `COMPLETE`
+    /// or `FAIL` at the time it finished, the task as the job, the keys it
+    /// read as input datasets and the keys it wrote as output datasets, all
+    /// in `namespace`.
+    ///
+    /// Graph inputs carry their [revision](Self::input_revision) at the time
+    /// of the run as the dataset version.
+    pub fn openlineage_events(&self, namespace: &str) -> Vec<String> {
+        // Records hold monotonic instants; anchor them to the wall clock.
+        let (now, wall_now) = (Instant::now(), SystemTime::now());
+        let mut events = Vec::new();
+        for entry in &self.entries {
+            let Some(record) = self.records.get(&entry.id) else {
+                continue;
+            };
+            if record.run != self.run {
+                continue;
+            }
+            let finished = wall_now - now.duration_since(record.started) + record.duration;
+            let since_epoch = finished.duration_since(UNIX_EPOCH).unwrap_or_default();
+
+            let mut out = String::from("{\"eventType\":");
+            out.push_str(if record.ok {
+                "\"COMPLETE\""
+            } else {
+                "\"FAIL\""
+            });
+            out.push_str(",\"eventTime\":");
+            push_json_str(&mut out, &rfc3339(since_epoch.as_millis() as u64));
+            let run_id = (namespace, entry.name, self.run, since_epoch.as_nanos());
+            let _ = write!(out, ",\"run\":{{\"runId\":\"{}\"}}", uuid(run_id));
+            out.push_str(",\"job\":");
+            push_dataset(&mut out, namespace, entry.name, None);
+            out.push_str(",\"inputs\":[");
+            for (i, dep) in entry.deps.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                let version = match self.writer_of(*dep) {
+                    Some(_) => None,
+                    None => Some(self.revision_behind(entry.output.id, *dep)),
+                };
+                push_dataset(&mut out, namespace, dep.name, version);
+            }
+            out.push_str("],\"outputs\":[");
+            for (i, key) in entry.writes.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                push_dataset(&mut out, namespace, key.name, None);
+            }
+            let _ = write!(
+                out,
+                "],\"producer\":\"{PRODUCER}\",\"schemaURL\":\"{RUN_EVENT}\"}}"
+            );
+            events.push(out);
+        }
+        events
+    }
+}
+
+/// A job or dataset reference, with a version facet if `version` is set.
+fn push_dataset(out: &mut String, namespace: &str, name: &str, version: Option<u64>) {
+    out.push_str("{\"namespace\":");
+    push_json_str(out, namespace);
+    out.push_str(",\"name\":");
+    push_json_str(out, name);
+    if let Some(version) = version {
+        let _ = write!(
+            out,
+            ",\"facets\":{{\"version\":{{\"_producer\":\"{PRODUCER}\",\"_schemaURL\":\"{VERSION_FACET}\",\"datasetVersion\":\"{version}\"}}}}"
+        );
+    }
+    out.push('}');
+}
+
+/// A UUID derived from `seed`, formatted as version 4 as OpenLineage
+/// requires.
+fn uuid(seed: impl Hash) -> String {
+    let (high, low) = ((&seed, 0u8).fingerprint(), (&seed, 1u8).fingerprint());
+    format!(
+        "{:08x}-{:04x}-4{:03x}-{:04x}-{:012x}",
+        high >> 32,
+        (high >> 16) & 0xffff,
+        high & 0xfff,
+        0x8000 | ((low >> 48) & 0x3fff),
+        low & 0xffff_ffff_ffff,
+    )
+}
+
+/// Formats milliseconds since the Unix epoch as an RFC 3339 UTC timestamp.
+fn rfc3339(millis: u64) -> String {
+    let (days, ms) = (millis / 86_400_000, millis % 86_400_000);
+    // Civil date from days since 1970-01-01, in eras of 400 years that start
+    // on March 1st.
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DbKey, ExecutionGraphBuilder, InMemoryDb, KeyType, Task, TaskInput, TaskOutput};
+
+    struct Orders;
+
+    impl DbKey for Orders {
+        type Value = Vec<u32>;
+    }
+
+    struct OrdersIn(Vec<u32>);
+
+    impl DbKey for OrdersIn {
+        type Value = OrdersIn;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for OrdersIn {
+        fn from_db(db: &Db) -> Self {
+            OrdersIn(db.get::<Orders>().unwrap().clone())
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Orders>()]
+        }
+    }
+
+    struct Revenue(u32);
+
+    impl DbKey for Revenue {
+        type Value = Revenue;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for Revenue {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<Revenue>(Revenue(self.0));
+        }
+    }
+
+    struct Aggregate;
+
+    impl Task<InMemoryDb> for Aggregate {
+        type Input = OrdersIn;
+        type Output = Revenue;
+
+        fn execute(input: Self::Input) -> Self::Output {
+            Revenue(input.0.iter().sum())
+        }
+    }
+
+    #[test]
+    fn test_rfc3339() {
+        assert_eq!(rfc3339(0), "1970-01-01T00:00:00.000Z");
+        assert_eq!(rfc3339(951_782_400_123), "2000-02-29T00:00:00.123Z");
+        assert_eq!(rfc3339(1_767_225_599_999), "2025-12-31T23:59:59.999Z");
+    }
+
+    #[test]
+    fn test_openlineage_events() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_input::<Orders>(vec![5, 7])
+            .unwrap()
+            .add_task::<Aggregate>()
+            .unwrap();
+        let mut graph = builder.build();
+        assert!(graph.openlineage_events("shop").is_empty());
+        graph.execute_all().unwrap();
+        assert_eq!(graph.db().get::<Revenue>().unwrap().0, 12);
+
+        let events = graph.openlineage_events("shop");
+        assert_eq!(events.len(), 1);
+        let event = &events[0];
+        assert!(event.starts_with("{\"eventType\":\"COMPLETE\",\"eventTime\":\"20"));
+        assert!(event.contains(&format!(
+            "\"job\":{{\"namespace\":\"shop\",\"name\":\"{}\"}}",
+            <Aggregate as Task<InMemoryDb>>::name()
+        )));
+        assert!(event.contains(&format!(
+            "\"inputs\":[{{\"namespace\":\"shop\",\"name\":\"{}\",\"facets\":{{\"version\":",
+            KeyType::of::<Orders>().name
+        )));
+        assert!(event.contains("\"datasetVersion\":\"1\""));
+        assert!(event.contains(&format!(
+            "\"outputs\":[{{\"namespace\":\"shop\",\"name\":\"{}\"}}]",
+            KeyType::of::<Revenue>().name
+        )));
+    }
+}
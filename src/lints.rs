@@ -0,0 +1,166 @@
+use std::{any::TypeId, collections::HashSet, fmt};
+
+use crate::{DataBase, ExecutionGraph, ExecutionGraphBuilder, KeyType, TaskId, TaskWithContext};
+
+/// A node of a graph that is likely dead, found by [`ExecutionGraph::lints`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Lint {
+    /// An input no task reads.
+    UnusedInput(KeyType),
+    /// A task whose outputs no task reads, and which is not marked
+    /// [terminal](ExecutionGraphBuilder::terminal).
+    UnusedTask(TaskId),
+    /// A key that nothing produces, e.g. left behind by a removed task.
+    Unproduced(KeyType),
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Lint::UnusedInput(key) => write!(f, "input `{key}` is not read by any task"),
+            Lint::UnusedTask(task) => write!(
+                f,
+                "no task reads the outputs of `{task}`; mark it terminal if it is a final result"
+            ),
+            Lint::Unproduced(key) => write!(f, "nothing produces `{key}`"),
+        }
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraphBuilder<Db, Ctx> {
+    /// Marks `T` as producing a final result of the graph, so that
+    /// [`lints`](ExecutionGraph::lints) does not report it for having no
+    /// readers.
+    pub fn terminal<T: TaskWithContext<Db, Ctx>>(&mut self) -> &mut Self {
+        self.graph.terminal.insert(TypeId::of::<T>());
+        self
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraph<Db, Ctx> {
+    /// Reports unused inputs, unused tasks and keys without a producer, in the
+    /// order they were added.
+    pub fn lints(&self) -> Vec<Lint> {
+        let reads: HashSet<TypeId> = self
+            .entries
+            .iter()
+            .flat_map(|entry| entry.deps.iter().map(|key| key.id))
+            .collect();
+        let task_inputs: HashSet<TypeId> =
+            self.entries.iter().map(|entry| entry.input.id).collect();
+        let mut seen = HashSet::new();
+        let mut lints = Vec::new();
+        for &id in self.tasks.node_weights() {
+            if !seen.insert(id) || task_inputs.contains(&id) {
+                continue;
+            }
+            let key = KeyType {
+                id,
+                name: self.names[&id],
+            };
+            if !self.producers.contains_key(&id) {
+                lints.push(Lint::Unproduced(key));
+            } else if self.is_input(id) && !reads.contains(&id) {
+                lints.push(Lint::UnusedInput(key));
+            }
+        }
+        for entry in &self.entries {
+            let read = reads.contains(&entry.output.id)
+                || entry.writes.iter().any(|key| reads.contains(&key.id));
+            if !read && !self.terminal.contains(&entry.id) {
+                lints.push(Lint::UnusedTask(entry.task_id()));
+            }
+        }
+        lints
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DbKey, InMemoryDb, Task, TaskInput, TaskOutput};
+
+    struct Source;
+
+    impl DbKey for Source {
+        type Value = u32;
+    }
+
+    struct Stale;
+
+    impl DbKey for Stale {
+        type Value = u32;
+    }
+
+    struct SourceIn(u32);
+
+    impl DbKey for SourceIn {
+        type Value = SourceIn;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for SourceIn {
+        fn from_db(db: &Db) -> Self {
+            SourceIn(*db.get::<Source>().unwrap())
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Source>()]
+        }
+    }
+
+    struct Doubled(u32);
+
+    impl DbKey for Doubled {
+        type Value = Doubled;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for Doubled {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<Doubled>(Doubled(self.0));
+        }
+    }
+
+    struct Double;
+
+    impl Task<InMemoryDb> for Double {
+        type Input = SourceIn;
+        type Output = Doubled;
+
+        fn execute(input: Self::Input) -> Self::Output {
+            Doubled(input.0 * 2)
+        }
+    }
+
+    struct Halve;
+
+    impl Task<InMemoryDb> for Halve {
+        type Input = SourceIn;
+        type Output = ();
+
+        fn execute(_input: Self::Input) -> Self::Output {}
+    }
+
+    #[test]
+    fn test_lints() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_input::<Source>(1)
+            .unwrap()
+            .add_input::<Stale>(2)
+            .unwrap()
+            .add_task::<Double>()
+            .unwrap()
+            .add_task::<Halve>()
+            .unwrap()
+            .terminal::<Halve>();
+        let graph = builder.build();
+        let lints = graph.lints();
+        assert_eq!(lints.len(), 2);
+        assert_eq!(lints[0], Lint::UnusedInput(KeyType::of::<Stale>()));
+        assert!(matches!(lints[1], Lint::UnusedTask(task) if task.name() == "Double"));
+        assert_eq!(
+            lints[0].to_string(),
+            format!("input `{}` is not read by any task", KeyType::of::<Stale>())
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use std::{marker::PhantomData, num::NonZeroUsize, thread};
+
+use crate::{
+    adapter::{AdaptFrom, AdaptTo},
+    DataBase, DbKey, ExecutionGraphBuilder, MissingDependency, Task,
+};
+
+/// A computation run on each item of a collection by [`MapTask`].
+pub trait ItemTask: 'static {
+    type Item: Clone + Sync + 'static;
+    type Out: Clone + Send + 'static;
+
+    fn execute(item: &Self::Item) -> Self::Out;
+}
+
+/// Runs `T` on every item of `In` and collects the results, in order, into
+/// `Out`. Added with [`ExecutionGraphBuilder::add_map`].
+pub struct MapTask<In, T, Out>(PhantomData<fn(In, T) -> Out>);
+
+impl<Db, In, T, Out> Task<Db> for MapTask<In, T, Out>
+where
+    Db: DataBase,
+    T: ItemTask,
+    In: DbKey<Value = Vec<T::Item>>,
+    Out: DbKey<Value = Vec<T::Out>>,
+{
+    type Input = AdaptFrom<In>;
+    type Output = AdaptTo<Out>;
+
+    fn execute(input: Self::Input) -> Self::Output {
+        AdaptTo(map_parallel(&input.0, T::execute))
+    }
+}
+
+/// Applies `f` to every item, splitting `items` into one chunk per available
+/// core.
+pub(crate) fn map_parallel<I: Sync, O: Send>(items: &[I], f: impl Fn(&I) -> O + Sync) -> Vec<O> {
+    let chunk = items.len().div_ceil(available_workers()).max(1);
+    map_chunks(items, chunk, |chunk| {
+        chunk.iter().map(&f).collect::<Vec<_>>()
+    })
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+pub(crate) fn available_workers() -> usize {
+    thread::available_parallelism().map_or(1, NonZeroUsize::get)
+}
+
+/// Applies `f` to each chunk of `chunk` items on its own scoped thread,
+/// returning the results in order. A single chunk runs on the calling thread.
+pub(crate) fn map_chunks<I: Sync, O: Send>(
+    items: &[I],
+    chunk: usize,
+    f: impl Fn(&[I]) -> O + Sync,
+) -> Vec<O> {
+    if items.len() <= chunk {
+        return vec![f(items)];
+    }
+    let f = &f;
+    thread::scope(|scope| {
+        let handles: Vec<_> = items
+            .chunks(chunk)
+            .map(|chunk| scope.spawn(move || f(chunk)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect()
+    })
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraphBuilder<Db, Ctx> {
+    /// Adds a [`MapTask`] running `T` over each item of `In`, writing the
+    /// results to `Out`. Items are processed in parallel when more than one
+    /// core is available.
+    pub fn add_map<In, T, Out>(&mut self) -> Result<&mut Self, MissingDependency>
+    where
+        T: ItemTask,
+        In: DbKey<Value = Vec<T::Item>>,
+        Out: DbKey<Value = Vec<T::Out>>,
+    {
+        self.add_task::<MapTask<In, T, Out>>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemoryDb;
+
+    struct Paths;
+
+    impl DbKey for Paths {
+        type Value = Vec<String>;
+    }
+
+    struct Extensions;
+
+    impl DbKey for Extensions {
+        type Value = Vec<Option<String>>;
+    }
+
+    struct Extension;
+
+    impl ItemTask for Extension {
+        type Item = String;
+        type Out = Option<String>;
+
+        fn execute(item: &String) -> Option<String> {
+            item.rsplit_once('.').map(|(_, ext)| ext.to_owned())
+        }
+    }
+
+    #[test]
+    fn test_map_task() {
+        let paths: Vec<String> = (0..100)
+            .map(|i| format!("file{i}.{}", if i % 2 == 0 { "rs" } else { "md" }))
+            .chain(["Makefile".to_owned()])
+            .collect();
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_input::<Paths>(paths)
+            .unwrap()
+            .add_map::<Paths, Extension, Extensions>()
+            .unwrap();
+        let mut graph = builder.build();
+        graph.execute_all().unwrap();
+
+        let extensions = graph.db().get::<Extensions>().unwrap();
+        assert_eq!(extensions.len(), 101);
+        assert_eq!(extensions[0].as_deref(), Some("rs"));
+        assert_eq!(extensions[99].as_deref(), Some("md"));
+        assert_eq!(extensions[100], None);
+    }
+}
@@ -0,0 +1,173 @@
+use std::{any::TypeId, marker::PhantomData, rc::Rc};
+
+use crate::{
+    adapter::{AdaptFrom, AdaptTo},
+    map::{available_workers, map_chunks, ItemTask},
+    reduce::Reducer,
+    swap::FnTask,
+    DataBase, DbKey, ExecutionGraphBuilder, MissingDependency, Task,
+};
+
+/// How [`ExecutionGraphBuilder::add_map_reduce`] splits its input between
+/// workers. Each partition is mapped and reduced on its own thread.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Partitioning {
+    /// One partition per available core.
+    #[default]
+    PerCore,
+    /// This many partitions of equal size.
+    Workers(usize),
+    /// Partitions of this many items.
+    ChunkSize(usize),
+}
+
+impl Partitioning {
+    fn chunk_size(self, len: usize) -> usize {
+        match self {
+            Partitioning::PerCore => len.div_ceil(available_workers()),
+            Partitioning::Workers(workers) => len.div_ceil(workers.max(1)),
+            Partitioning::ChunkSize(size) => size,
+        }
+        .max(1)
+    }
+}
+
+/// Maps the items of `In` with `M`, reduces each partition with `R` and
+/// merges the partial aggregates, in order, into `Out`. Added with
+/// [`ExecutionGraphBuilder::add_map_reduce`].
+pub struct MapReduceTask<In, M, R, Out>(PhantomData<fn(In, M, R) -> Out>);
+
+impl<In, M, R, Out> MapReduceTask<In, M, R, Out>
+where
+    M: ItemTask<Out = R::Item>,
+    R: Reducer,
+    R::Acc: Send,
+    Out: DbKey<Value = R::Acc>,
+{
+    /// The one code path of the task, whether it was added with
+    /// [`add_map_reduce`](ExecutionGraphBuilder::add_map_reduce), which
+    /// picks `partitioning`, or as a plain task, which partitions per core.
+    fn run(items: &[M::Item], partitioning: Partitioning) -> AdaptTo<Out> {
+        let partials = map_chunks(items, partitioning.chunk_size(items.len()), |partition| {
+            partition
+                .iter()
+                .fold(R::empty(), |acc, item| R::fold(acc, &M::execute(item)))
+        });
+        AdaptTo(
+            partials
+                .iter()
+                .fold(R::empty(), |acc, partial| R::combine(&acc, partial)),
+        )
+    }
+}
+
+impl<Db, In, M, R, Out> Task<Db> for MapReduceTask<In, M, R, Out>
+where
+    Db: DataBase,
+    M: ItemTask<Out = R::Item>,
+    R: Reducer,
+    R::Acc: Send,
+    In: DbKey<Value = Vec<M::Item>>,
+    Out: DbKey<Value = R::Acc>,
+{
+    type Input = AdaptFrom<In>;
+    type Output = AdaptTo<Out>;
+
+    fn execute(input: Self::Input) -> Self::Output {
+        Self::run(&input.0, Partitioning::default())
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraphBuilder<Db, Ctx> {
+    /// Adds a [`MapReduceTask`] mapping the items of `In` with `M` and
+    /// reducing the results with `R` into `Out`, partitioned across worker
+    /// threads as `partitioning` says.
+    pub fn add_map_reduce<In, M, R, Out>(
+        &mut self,
+        partitioning: Partitioning,
+    ) -> Result<&mut Self, MissingDependency>
+    where
+        M: ItemTask<Out = R::Item>,
+        R: Reducer,
+        R::Acc: Send,
+        In: DbKey<Value = Vec<M::Item>>,
+        Out: DbKey<Value = R::Acc>,
+    {
+        self.add_task::<MapReduceTask<In, M, R, Out>>()?;
+        self.graph.runners.insert(
+            TypeId::of::<MapReduceTask<In, M, R, Out>>(),
+            Rc::new(FnTask::new(move |input: AdaptFrom<In>| {
+                MapReduceTask::<In, M, R, Out>::run(&input.0, partitioning)
+            })),
+        );
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemoryDb;
+
+    struct Lines;
+
+    impl DbKey for Lines {
+        type Value = Vec<String>;
+    }
+
+    struct LongestLine;
+
+    impl DbKey for LongestLine {
+        type Value = usize;
+    }
+
+    struct Length;
+
+    impl ItemTask for Length {
+        type Item = String;
+        type Out = usize;
+
+        fn execute(item: &String) -> usize {
+            item.len()
+        }
+    }
+
+    struct Max;
+
+    impl Reducer for Max {
+        type Item = usize;
+        type Acc = usize;
+
+        fn empty() -> usize {
+            0
+        }
+
+        fn fold(acc: usize, item: &usize) -> usize {
+            acc.max(*item)
+        }
+
+        fn combine(a: &usize, b: &usize) -> usize {
+            *a.max(b)
+        }
+    }
+
+    #[test]
+    fn test_map_reduce() {
+        let lines: Vec<String> = (0..50).map(|i| "x".repeat(i % 17)).collect();
+        for partitioning in [
+            Partitioning::PerCore,
+            Partitioning::Workers(3),
+            Partitioning::ChunkSize(7),
+        ] {
+            let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+            builder
+                .add_input::<Lines>(lines.clone())
+                .unwrap()
+                .add_map_reduce::<Lines, Length, Max, LongestLine>(partitioning)
+                .unwrap();
+            let mut graph = builder.build();
+            graph.execute_all().unwrap();
+            assert_eq!(graph.db().get::<LongestLine>(), Some(&16));
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+};
+
+use crate::{DbKey, InMemoryDb};
+
+/// Which side wins a conflict no resolver is registered for.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Prefer {
+    Ours,
+    Theirs,
+}
+
+type Resolve = Box<dyn Fn(Box<dyn Any>, Box<dyn Any>) -> Box<dyn Any>>;
+
+/// Decides the value of each key both databases hold when merging them with
+/// [`InMemoryDb::merge`].
+pub struct MergeResolver {
+    resolvers: HashMap<TypeId, Resolve>,
+    default: Prefer,
+}
+
+impl MergeResolver {
+    /// Resolves conflicts by keeping the value of the `default` side, except
+    /// for keys given a resolver with [`key`](Self::key).
+    pub fn new(default: Prefer) -> Self {
+        MergeResolver {
+            resolvers: HashMap::new(),
+            default,
+        }
+    }
+
+    /// Resolves conflicts on `K` with `resolve(ours, theirs)`, which can pick
+    /// either value or combine them.
+    pub fn key<K: DbKey>(
+        mut self,
+        resolve: impl Fn(K::Value, K::Value) -> K::Value + 'static,
+    ) -> Self {
+        self.resolvers.insert(
+            TypeId::of::<K>(),
+            Box::new(move |ours, theirs| {
+                let ours = *ours.downcast::<K::Value>().unwrap();
+                let theirs = *theirs.downcast::<K::Value>().unwrap();
+                Box::new(resolve(ours, theirs))
+            }),
+        );
+        self
+    }
+
+    fn resolve(&self, key: TypeId, ours: Box<dyn Any>, theirs: Box<dyn Any>) -> Box<dyn Any> {
+        match (self.resolvers.get(&key), self.default) {
+            (Some(resolve), _) => resolve(ours, theirs),
+            (None, Prefer::Ours) => ours,
+            (None, Prefer::Theirs) => theirs,
+        }
+    }
+}
+
+impl InMemoryDb {
+    /// Moves every value of `other` into this database. Keys only `other`
+    /// holds are taken as they are; keys both hold get the value `resolver`
+    /// decides on.
+    pub fn merge(&mut self, other: InMemoryDb, resolver: &MergeResolver) {
+        self.stored.extend(other.stored);
+        for (key, theirs) in other.data {
+            let merged = match self.data.remove(&key) {
+                Some(ours) => resolver.resolve(key, ours, theirs),
+                None => theirs,
+            };
+            self.data.insert(key, merged);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DataBase;
+
+    struct Title;
+
+    impl DbKey for Title {
+        type Value = String;
+    }
+
+    struct Tags;
+
+    impl DbKey for Tags {
+        type Value = Vec<&'static str>;
+    }
+
+    struct Draft;
+
+    impl DbKey for Draft {
+        type Value = bool;
+    }
+
+    struct Author;
+
+    impl DbKey for Author {
+        type Value = &'static str;
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut ours = InMemoryDb::new();
+        ours.put::<Title>("Ours".to_string());
+        ours.put::<Tags>(vec!["a", "b"]);
+        ours.put::<Draft>(true);
+        let mut theirs = InMemoryDb::new();
+        theirs.put::<Title>("Theirs".to_string());
+        theirs.put::<Tags>(vec!["b", "c"]);
+        theirs.put::<Author>("ana");
+
+        let resolver = MergeResolver::new(Prefer::Theirs).key::<Tags>(|mut ours, theirs| {
+            for tag in theirs {
+                if !ours.contains(&tag) {
+                    ours.push(tag);
+                }
+            }
+            ours
+        });
+        ours.merge(theirs, &resolver);
+        assert_eq!(ours.get::<Title>().unwrap(), "Theirs");
+        assert_eq!(ours.get::<Tags>(), Some(&vec!["a", "b", "c"]));
+        assert_eq!(ours.get::<Draft>(), Some(&true));
+        assert_eq!(ours.get::<Author>(), Some(&"ana"));
+    }
+}
@@ -0,0 +1,81 @@
+use std::{any::TypeId, collections::BTreeMap};
+
+use crate::{handle::TaskHandle, DataBase, ExecutionGraph, ExecutionGraphBuilder, TaskWithContext};
+
+/// Free-form annotations on a task (owner, cost estimate, description,
+/// tags), set with [`ExecutionGraphBuilder::annotate`]. They have no effect
+/// on execution and are carried into [`describe`](ExecutionGraph::describe)
+/// and the web UI.
+pub type TaskMetadata = BTreeMap<String, String>;
+
+impl<Db: DataBase, Ctx> ExecutionGraphBuilder<Db, Ctx> {
+    /// Sets the annotation `key` of task `T` to `value`, replacing any earlier
+    /// value. `T` does not need to be added yet.
+    pub fn annotate<T: TaskWithContext<Db, Ctx>>(
+        &mut self,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) -> &mut Self {
+        self.graph
+            .metadata
+            .entry(TypeId::of::<T>())
+            .or_default()
+            .insert(key.into(), value.into());
+        self
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraph<Db, Ctx> {
+    pub fn metadata<T: TaskWithContext<Db, Ctx>>(&self) -> Option<&TaskMetadata> {
+        self.metadata.get(&TypeId::of::<T>())
+    }
+}
+
+impl<Db: DataBase, Ctx> TaskHandle<'_, Db, Ctx> {
+    pub fn metadata(&self) -> Option<&TaskMetadata> {
+        self.graph.metadata.get(&self.entry().id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemoryDb, Task};
+
+    struct Nightly;
+
+    impl Task<InMemoryDb> for Nightly {
+        type Input = ();
+        type Output = ();
+
+        fn execute(_input: Self::Input) -> Self::Output {}
+    }
+
+    #[test]
+    fn test_annotations() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_task::<Nightly>()
+            .unwrap()
+            .annotate::<Nightly>("owner", "data-platform")
+            .annotate::<Nightly>("cost", "3m");
+        let mut graph = builder.build();
+
+        assert_eq!(
+            graph.metadata::<Nightly>().unwrap()["owner"],
+            "data-platform"
+        );
+        let handle = graph.task_by_name("Nightly").unwrap();
+        assert_eq!(handle.metadata().unwrap()["cost"], "3m");
+
+        let desc = graph.describe();
+        assert!(desc
+            .annotations
+            .contains(&(1, "owner".to_string(), "data-platform".to_string())));
+        assert_eq!(
+            desc.to_string()
+                .parse::<crate::describe::GraphDescription>(),
+            Ok(desc)
+        );
+    }
+}
@@ -0,0 +1,162 @@
+use std::{collections::BTreeMap, fmt::Write as _, time::Duration};
+
+use crate::{DataBase, ExecutionGraph};
+
+/// Upper bounds, in seconds, of the duration histogram buckets. These are the
+/// Prometheus client defaults.
+const BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+#[derive(Default)]
+struct Histogram {
+    // Cumulative, as in the exposition format.
+    buckets: [u64; BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, value: Duration) {
+        let seconds = value.as_secs_f64();
+        for (bucket, bound) in self.buckets.iter_mut().zip(BUCKETS) {
+            if seconds <= bound {
+                *bucket += 1;
+            }
+        }
+        self.sum += seconds;
+        self.count += 1;
+    }
+
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        let sep = if labels.is_empty() { "" } else { "," };
+        for (count, bound) in self.buckets.iter().zip(BUCKETS) {
+            let _ = writeln!(out, "{name}_bucket{{{labels}{sep}le=\"{bound}\"}} {count}");
+        }
+        let _ = writeln!(
+            out,
+            "{name}_bucket{{{labels}{sep}le=\"+Inf\"}} {}",
+            self.count
+        );
+        let labels = if labels.is_empty() {
+            String::new()
+        } else {
+            format!("{{{labels}}}")
+        };
+        let _ = writeln!(out, "{name}_sum{labels} {}", self.sum);
+        let _ = writeln!(out, "{name}_count{labels} {}", self.count);
+    }
+}
+
+#[derive(Default)]
+struct TaskMetrics {
+    executions: u64,
+    failures: u64,
+    duration: Histogram,
+}
+
+#[derive(Default)]
+pub(crate) struct Metrics {
+    tasks: BTreeMap<&'static str, TaskMetrics>,
+    runs: Histogram,
+}
+
+impl Metrics {
+    pub(crate) fn record_task(&mut self, task: &'static str, duration: Duration, ok: bool) {
+        let task = self.tasks.entry(task).or_default();
+        task.executions += 1;
+        if !ok {
+            task.failures += 1;
+        }
+        task.duration.observe(duration);
+    }
+
+    pub(crate) fn record_run(&mut self, duration: Duration) {
+        self.runs.observe(duration);
+    }
+}
+
+fn label(task: &str) -> String {
+    let escaped = task
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n");
+    format!("task=\"{escaped}\"")
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraph<Db, Ctx> {
+    /// The graph's metrics since it was built, in the Prometheus text
+    /// exposition format, ready to be served from a `/metrics` endpoint.
+    ///
+    /// Tasks are labelled by [`name`](crate::Task::name). There is no
+    /// `cache_hits_total`: every run executes every task, so there are no
+    /// cache hits to count.
+    pub fn metrics_text(&self) -> String {
+        let mut out = String::new();
+        out.push_str("# HELP task_executions_total Task executions, successful or not.\n");
+        out.push_str("# TYPE task_executions_total counter\n");
+        for (task, metrics) in &self.metrics.tasks {
+            let _ = writeln!(
+                out,
+                "task_executions_total{{{}}} {}",
+                label(task),
+                metrics.executions
+            );
+        }
+        out.push_str("# HELP task_failures_total Task executions that failed.\n");
+        out.push_str("# TYPE task_failures_total counter\n");
+        for (task, metrics) in &self.metrics.tasks {
+            let _ = writeln!(
+                out,
+                "task_failures_total{{{}}} {}",
+                label(task),
+                metrics.failures
+            );
+        }
+        out.push_str("# HELP task_duration_seconds Time spent executing a task.\n");
+        out.push_str("# TYPE task_duration_seconds histogram\n");
+        for (task, metrics) in &self.metrics.tasks {
+            metrics
+                .duration
+                .render(&mut out, "task_duration_seconds", &label(task));
+        }
+        out.push_str("# HELP graph_run_duration_seconds Time spent in execute_all.\n");
+        out.push_str("# TYPE graph_run_duration_seconds histogram\n");
+        self.metrics
+            .runs
+            .render(&mut out, "graph_run_duration_seconds", "");
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ExecutionGraphBuilder, InMemoryDb, Task};
+
+    struct Ping;
+
+    impl Task<InMemoryDb> for Ping {
+        type Input = ();
+        type Output = ();
+
+        fn execute(_input: Self::Input) -> Self::Output {}
+    }
+
+    #[test]
+    fn test_metrics_text() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder.add_task::<Ping>().unwrap();
+        let mut graph = builder.build();
+        graph.execute_all().unwrap();
+        graph.execute_all().unwrap();
+
+        let text = graph.metrics_text();
+        assert!(
+            text.contains("task_executions_total{task=\"Ping\"} 2\n"),
+            "{text}"
+        );
+        assert!(text.contains("task_failures_total{task=\"Ping\"} 0\n"));
+        assert!(text.contains("task_duration_seconds_bucket{task=\"Ping\",le=\"+Inf\"} 2\n"));
+        assert!(text.contains("graph_run_duration_seconds_count 2\n"));
+    }
+}
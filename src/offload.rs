@@ -0,0 +1,419 @@
+use std::{
+    any::{type_name, Any, TypeId},
+    collections::HashMap,
+    rc::Rc,
+    sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    descriptor::TaskDescriptor, DataBase, ExecutionError, ExecutionGraph, ExecutionGraphBuilder,
+    KeyType, MissingDependency, Task, TaskInput, TaskPanicked,
+};
+
+type Output = Box<dyn Any + Send>;
+
+/// Stores the output of an offloaded job, or fails its task with the reason
+/// it has none.
+type Finish<Db, Ctx> =
+    fn(&mut ExecutionGraph<Db, Ctx>, Result<Output, String>, Instant) -> Result<(), ExecutionError>;
+
+/// The work of one run of an offloaded task, handed to an [`Offloader`].
+pub struct OffloadJob {
+    task: &'static str,
+    work: Box<dyn FnOnce() -> Output + Send>,
+    done: Sender<Output>,
+}
+
+impl OffloadJob {
+    /// Type name of the offloaded task.
+    pub fn task(&self) -> &'static str {
+        self.task
+    }
+
+    /// Runs the task's work and reports its output to the graph. Dropping
+    /// the job without running it fails the task.
+    pub fn run(self) {
+        let output = (self.work)();
+        // The graph may have been dropped since, with nothing left to wait.
+        let _ = self.done.send(output);
+    }
+}
+
+/// Runs the jobs of offloaded tasks somewhere other than the thread running
+/// the graph: a GPU queue, a device driver or a remote service. Set per task
+/// with [`ExecutionGraphBuilder::add_offloaded`].
+pub trait Offloader {
+    /// Starts `job` and returns without waiting for it.
+    fn submit(&self, job: OffloadJob);
+}
+
+struct Pending<Db: DataBase, Ctx> {
+    writes: Vec<KeyType>,
+    started: Instant,
+    done: Receiver<Output>,
+    finish: Finish<Db, Ctx>,
+}
+
+pub(crate) struct Offloads<Db: DataBase, Ctx> {
+    offloaders: HashMap<TypeId, Rc<dyn Offloader>>,
+    pending: Vec<Pending<Db, Ctx>>,
+    timeout: Option<Duration>,
+}
+
+impl<Db: DataBase, Ctx> Default for Offloads<Db, Ctx> {
+    fn default() -> Self {
+        Offloads {
+            offloaders: HashMap::new(),
+            pending: Vec::new(),
+            timeout: None,
+        }
+    }
+}
+
+impl<Db: DataBase, Ctx> Offloads<Db, Ctx> {
+    pub(crate) fn forget(&mut self, task: TypeId) {
+        self.offloaders.remove(&task);
+    }
+
+    /// Copies the offloaders and timeout, but not the pending jobs.
+    pub(crate) fn without_jobs(&self) -> Self {
+        Offloads {
+            offloaders: self.offloaders.clone(),
+            pending: Vec::new(),
+            timeout: self.timeout,
+        }
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraphBuilder<Db, Ctx> {
+    /// Adds `T`, whose work [`execute_all`](ExecutionGraph::execute_all)
+    /// hands to `offloader` instead of running it. The run goes on with the
+    /// following tasks and only waits for `T`'s output once a task reads it,
+    /// or at the end of the run.
+    ///
+    /// [`ExecutionGraph::execute`] still runs `T` on the calling thread.
+    pub fn add_offloaded<T>(
+        &mut self,
+        offloader: impl Offloader + 'static,
+    ) -> Result<&mut Self, MissingDependency>
+    where
+        T: Task<Db>,
+        T::Input: Send,
+        T::Output: Send,
+    {
+        let mut task = TaskDescriptor::of::<T>();
+        task.entry.run = |graph, _| graph.submit_offloaded::<T>();
+        self.add_descriptor(task)?;
+        self.graph
+            .offloads
+            .offloaders
+            .insert(TypeId::of::<T>(), Rc::new(offloader));
+        Ok(self)
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraph<Db, Ctx> {
+    /// Fails an offloaded task whose job has not reported its output
+    /// `timeout` after it was submitted, instead of waiting for a hung
+    /// offloader forever. A late output is discarded.
+    pub fn offload_timeout(&mut self, timeout: Option<Duration>) -> &mut Self {
+        self.offloads.timeout = timeout;
+        self
+    }
+
+    fn submit_offloaded<T>(&mut self) -> Result<(), ExecutionError>
+    where
+        T: Task<Db>,
+        T::Input: Send,
+        T::Output: Send,
+    {
+        self.await_offloaded(&T::Input::dep_types())?;
+        self.check_limits::<T>()?;
+        let input = self.with_shared_db(|graph| T::Input::from_db(&graph.db));
+        let (done, receiver) = channel();
+        let writes = self.entries.iter().find(|e| e.id == TypeId::of::<T>());
+        self.offloads.pending.push(Pending {
+            writes: writes.map(|e| e.writes.clone()).unwrap_or_default(),
+            started: Instant::now(),
+            done: receiver,
+            finish: finish_offloaded::<Db, Ctx, T>,
+        });
+        self.offloads.offloaders[&TypeId::of::<T>()].submit(OffloadJob {
+            task: type_name::<T>(),
+            work: Box::new(move || Box::new(T::execute(input))),
+            done,
+        });
+        Ok(())
+    }
+
+    /// Waits for the offloaded tasks writing any of `keys` and stores their
+    /// outputs.
+    pub(crate) fn await_offloaded(&mut self, keys: &[KeyType]) -> Result<(), ExecutionError> {
+        let (ready, pending) = std::mem::take(&mut self.offloads.pending)
+            .into_iter()
+            .partition(|pending| pending.writes.iter().any(|key| keys.contains(key)));
+        self.offloads.pending = pending;
+        self.finish_all(ready)
+    }
+
+    /// Waits for every offloaded task still running.
+    pub(crate) fn await_all_offloaded(&mut self) -> Result<(), ExecutionError> {
+        let pending = std::mem::take(&mut self.offloads.pending);
+        self.finish_all(pending)
+    }
+
+    fn finish_all(&mut self, pending: Vec<Pending<Db, Ctx>>) -> Result<(), ExecutionError> {
+        let mut result = Ok(());
+        for pending in pending {
+            let output = match self.offloads.timeout {
+                Some(timeout) => {
+                    let left = timeout.saturating_sub(pending.started.elapsed());
+                    pending.done.recv_timeout(left).map_err(|e| match e {
+                        RecvTimeoutError::Timeout => {
+                            format!("offloaded job did not complete within {timeout:?}")
+                        }
+                        RecvTimeoutError::Disconnected => DROPPED.to_string(),
+                    })
+                }
+                None => pending.done.recv().map_err(|_| DROPPED.to_string()),
+            };
+            result = result.and((pending.finish)(self, output, pending.started));
+        }
+        result
+    }
+}
+
+const DROPPED: &str = "offloaded job was dropped before completing";
+
+/// Stores the output of an offloaded run of `T`, with the same checks as a
+/// run on the graph's thread.
+fn finish_offloaded<Db: DataBase, Ctx, T: Task<Db>>(
+    graph: &mut ExecutionGraph<Db, Ctx>,
+    output: Result<Output, String>,
+    started: Instant,
+) -> Result<(), ExecutionError> {
+    graph.with_shared_db(|graph| {
+        let result = match output {
+            Ok(output) => graph.store_output::<T>(|_| {
+                Ok(*output
+                    .downcast::<T::Output>()
+                    .expect("offloaded output type mismatch"))
+            }),
+            Err(payload) => {
+                graph.poison_outputs::<T>();
+                Err(TaskPanicked {
+                    task: type_name::<T>(),
+                    payload,
+                    backtrace: String::new(),
+                }
+                .into())
+            }
+        };
+        graph.record_result::<T, _>(started, &result);
+        result.map(drop)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+    use crate::{DbKey, ExecutionError, InMemoryDb, TaskOutput};
+
+    struct Samples;
+
+    impl DbKey for Samples {
+        type Value = Vec<f32>;
+    }
+
+    struct Energy;
+
+    impl DbKey for Energy {
+        type Value = f32;
+    }
+
+    struct SamplesIn(Vec<f32>);
+
+    impl DbKey for SamplesIn {
+        type Value = SamplesIn;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for SamplesIn {
+        fn from_db(db: &Db) -> Self {
+            SamplesIn(db.get::<Samples>().unwrap().clone())
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Samples>()]
+        }
+    }
+
+    struct EnergyOut(f32);
+
+    impl DbKey for EnergyOut {
+        type Value = EnergyOut;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for EnergyOut {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<Energy>(self.0);
+        }
+
+        fn out_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Energy>()]
+        }
+    }
+
+    struct Kernel;
+
+    impl Task<InMemoryDb> for Kernel {
+        type Input = SamplesIn;
+        type Output = EnergyOut;
+
+        fn execute(input: Self::Input) -> Self::Output {
+            EnergyOut(input.0.iter().map(|s| s * s).sum())
+        }
+    }
+
+    struct Threaded;
+
+    impl Offloader for Threaded {
+        fn submit(&self, job: OffloadJob) {
+            thread::spawn(move || job.run());
+        }
+    }
+
+    struct Dropping;
+
+    /// Keeps its jobs without ever running them, like a hung worker.
+    #[derive(Default)]
+    struct Hung(std::cell::RefCell<Vec<OffloadJob>>);
+
+    impl Offloader for Hung {
+        fn submit(&self, job: OffloadJob) {
+            self.0.borrow_mut().push(job);
+        }
+    }
+
+    impl Offloader for Dropping {
+        fn submit(&self, _job: OffloadJob) {}
+    }
+
+    #[test]
+    fn test_offloaded_task() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_input::<Samples>(vec![1.0, 2.0])
+            .unwrap()
+            .add_offloaded::<Kernel>(Threaded)
+            .unwrap();
+        let mut graph = builder.build();
+        graph.execute_all().unwrap();
+        assert_eq!(graph.db().get::<Energy>(), Some(&5.0));
+        assert_eq!(graph.task_status::<Kernel>(), crate::TaskStatus::Recomputed);
+    }
+
+    #[test]
+    fn test_dropped_job_fails() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_input::<Samples>(vec![1.0])
+            .unwrap()
+            .add_offloaded::<Kernel>(Dropping)
+            .unwrap()
+            .add_task::<Report>()
+            .unwrap();
+        let mut graph = builder.build();
+        let err = graph.execute_all().err().unwrap();
+        assert_eq!(err.task(), type_name::<Kernel>());
+
+        let err = graph.execute::<Report>().err().unwrap();
+        assert!(matches!(err, ExecutionError::Poisoned(_)));
+        assert_eq!(
+            graph.explain(err).root_cause().task(),
+            type_name::<Kernel>()
+        );
+    }
+
+    #[test]
+    fn test_hung_job_times_out() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_input::<Samples>(vec![1.0])
+            .unwrap()
+            .add_offloaded::<Kernel>(Hung::default())
+            .unwrap();
+        let mut graph = builder.build();
+        graph.offload_timeout(Some(Duration::from_millis(10)));
+        let Err(ExecutionError::TaskPanicked(err)) = graph.execute_all() else {
+            panic!("expected a timeout")
+        };
+        assert!(err.payload.contains("did not complete within"));
+    }
+
+    struct EnergyIn;
+
+    impl DbKey for EnergyIn {
+        type Value = EnergyIn;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for EnergyIn {
+        fn from_db(_db: &Db) -> Self {
+            EnergyIn
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Energy>()]
+        }
+    }
+
+    struct Report;
+
+    impl Task<InMemoryDb> for Report {
+        type Input = EnergyIn;
+        type Output = ();
+
+        fn execute(_input: Self::Input) -> Self::Output {}
+    }
+
+    struct NoEnergy;
+
+    impl DbKey for NoEnergy {
+        type Value = NoEnergy;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for NoEnergy {
+        fn to_db(&self, _db: &mut Db) {}
+
+        fn out_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Energy>()]
+        }
+    }
+
+    struct Forgetful;
+
+    impl Task<InMemoryDb> for Forgetful {
+        type Input = SamplesIn;
+        type Output = NoEnergy;
+
+        fn execute(_input: Self::Input) -> Self::Output {
+            NoEnergy
+        }
+    }
+
+    #[test]
+    fn test_offloaded_output_checked() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_input::<Samples>(vec![1.0])
+            .unwrap()
+            .add_offloaded::<Forgetful>(Threaded)
+            .unwrap();
+        let mut graph = builder.build();
+        let err = graph.execute_all().err().unwrap();
+        assert!(matches!(err, ExecutionError::MissingOutput(_)));
+        assert!(graph.is_poisoned::<Energy>());
+    }
+}
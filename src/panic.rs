@@ -0,0 +1,118 @@
+use std::{
+    backtrace::Backtrace,
+    cell::RefCell,
+    panic::{self, AssertUnwindSafe},
+    sync::Once,
+};
+
+use crate::TaskPanicked;
+
+thread_local! {
+    // `Some` while a task is running under `catch_task_panic` on this thread;
+    // the hook fills in the backtrace of the panic.
+    static CAPTURED_BACKTRACE: RefCell<Option<Option<String>>> = const { RefCell::new(None) };
+}
+
+static INSTALL_HOOK: Once = Once::new();
+
+fn install_hook() {
+    INSTALL_HOOK.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            let captured = CAPTURED_BACKTRACE.with(|slot| match slot.borrow_mut().as_mut() {
+                Some(backtrace) => {
+                    *backtrace = Some(Backtrace::force_capture().to_string());
+                    true
+                }
+                None => false,
+            });
+            if !captured {
+                previous(info);
+            }
+        }));
+    });
+}
+
+pub(crate) fn catch_task_panic<R>(
+    task: &'static str,
+    f: impl FnOnce() -> R,
+) -> Result<R, TaskPanicked> {
+    install_hook();
+    let outer = CAPTURED_BACKTRACE.with(|slot| slot.borrow_mut().replace(None));
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    let backtrace =
+        CAPTURED_BACKTRACE.with(|slot| std::mem::replace(&mut *slot.borrow_mut(), outer));
+    result.map_err(|payload| TaskPanicked {
+        task,
+        payload: match payload.downcast::<String>() {
+            Ok(message) => *message,
+            Err(payload) => match payload.downcast::<&'static str>() {
+                Ok(message) => message.to_string(),
+                Err(_) => "<non-string panic payload>".to_string(),
+            },
+        },
+        backtrace: backtrace.flatten().unwrap_or_default(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::any::type_name;
+
+    use crate::{
+        DataBase, DbKey, ExecutionError, ExecutionGraphBuilder, InMemoryDb, Task, TaskInput,
+    };
+
+    struct Divisor;
+
+    impl DbKey for Divisor {
+        type Value = u32;
+    }
+
+    struct Division(u32);
+
+    impl DbKey for Division {
+        type Value = Division;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for Division {
+        fn from_db(db: &Db) -> Self {
+            Division(*db.get::<Divisor>().unwrap())
+        }
+    }
+
+    struct Divide;
+
+    impl Task<InMemoryDb> for Divide {
+        type Input = Division;
+        type Output = ();
+
+        fn execute(input: Self::Input) -> Self::Output {
+            if input.0 == 0 {
+                panic!("division by zero");
+            }
+        }
+    }
+
+    #[test]
+    fn test_catch_panics() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_input::<Divisor>(0)
+            .unwrap()
+            .add_task::<Divide>()
+            .unwrap();
+        let mut graph = builder.build();
+        graph.catch_panics(true);
+
+        let Err(ExecutionError::TaskPanicked(panicked)) = graph.execute_all() else {
+            panic!("expected the task to panic")
+        };
+        assert_eq!(panicked.task, type_name::<Divide>());
+        assert_eq!(panicked.payload, "division by zero");
+        assert!(!panicked.backtrace.is_empty());
+
+        graph.set_input::<Divisor>(2).unwrap();
+        assert!(graph.execute_all().is_ok());
+    }
+}
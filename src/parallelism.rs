@@ -0,0 +1,167 @@
+use std::{cmp::Reverse, time::Duration};
+
+use crate::{critical_path::Schedule, DataBase, ExecutionGraph, TaskId};
+
+/// How much a run of the graph gains from more workers, returned by
+/// [`ExecutionGraph::parallelism_with`].
+#[derive(Clone, PartialEq, Debug)]
+pub struct ParallelismReport {
+    /// Sum of the costs of all tasks, the length of a run on one worker.
+    pub work: Duration,
+    /// Length of the critical path, the length of a run on unlimited workers.
+    pub span: Duration,
+    /// `work / span`, the most any number of workers can speed a run up.
+    pub speedup: f64,
+    /// Most tasks running at once when each starts as early as it can.
+    /// Workers beyond this count stay idle.
+    pub max_workers: usize,
+    /// The tasks of the critical path, costliest first: shortening any of
+    /// them shortens the run, whatever the number of workers.
+    pub bottlenecks: Vec<(TaskId, Duration)>,
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraph<Db, Ctx> {
+    /// Analyses the graph's parallelism by the costs `cost` estimates. Tasks
+    /// without an estimate cost nothing.
+    pub fn parallelism_with(&self, cost: impl Fn(TaskId) -> Option<Duration>) -> ParallelismReport {
+        let path = self.critical_path_with(&cost);
+        let Schedule { costs, finish, .. } = self.schedule(&cost);
+        let work = costs.iter().sum();
+
+        // A task leaving frees its worker for one starting at the same time.
+        let mut events: Vec<(Duration, isize)> = costs
+            .iter()
+            .zip(&finish)
+            .filter(|(cost, _)| !cost.is_zero())
+            .flat_map(|(&cost, &finish)| [(finish - cost, 1), (finish, -1)])
+            .collect();
+        events.sort();
+        let mut running = 0;
+        let mut max_workers = 0;
+        for (_, change) in events {
+            running += change;
+            max_workers = max_workers.max(running as usize);
+        }
+
+        let mut bottlenecks: Vec<(TaskId, Duration)> = path
+            .tasks
+            .iter()
+            .map(|&task| {
+                let i = self.entries.iter().position(|e| e.id == task.id).unwrap();
+                (task, costs[i])
+            })
+            .filter(|(_, cost)| !cost.is_zero())
+            .collect();
+        bottlenecks.sort_by_key(|&(_, cost)| Reverse(cost));
+
+        ParallelismReport {
+            work,
+            span: path.length,
+            speedup: if path.length.is_zero() {
+                1.0
+            } else {
+                work.as_secs_f64() / path.length.as_secs_f64()
+            },
+            max_workers,
+            bottlenecks,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DbKey, ExecutionGraphBuilder, InMemoryDb, KeyType, Task, TaskInput, TaskOutput};
+
+    macro_rules! key {
+        ($name:ident) => {
+            struct $name;
+
+            impl DbKey for $name {
+                type Value = $name;
+            }
+
+            impl<Db: DataBase> TaskOutput<Db> for $name {
+                fn to_db(&self, db: &mut Db) {
+                    db.put::<$name>($name);
+                }
+            }
+        };
+    }
+
+    key!(Compiled);
+    key!(Linted);
+    key!(Documented);
+    key!(Packaged);
+
+    struct PackageIn;
+
+    impl DbKey for PackageIn {
+        type Value = PackageIn;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for PackageIn {
+        fn from_db(_db: &Db) -> Self {
+            PackageIn
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![
+                KeyType::of::<Compiled>(),
+                KeyType::of::<Linted>(),
+                KeyType::of::<Documented>(),
+            ]
+        }
+    }
+
+    macro_rules! task {
+        ($name:ident, $input:ty, $output:ident) => {
+            struct $name;
+
+            impl Task<InMemoryDb> for $name {
+                type Input = $input;
+                type Output = $output;
+
+                fn execute(_input: Self::Input) -> Self::Output {
+                    $output
+                }
+            }
+        };
+    }
+
+    task!(Compile, (), Compiled);
+    task!(Lint, (), Linted);
+    task!(Document, (), Documented);
+    task!(Package, PackageIn, Packaged);
+
+    #[test]
+    fn test_parallelism() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_task::<Compile>()
+            .unwrap()
+            .add_task::<Lint>()
+            .unwrap()
+            .add_task::<Document>()
+            .unwrap()
+            .add_task::<Package>()
+            .unwrap();
+        let report = builder.build().parallelism_with(|task| {
+            Some(Duration::from_secs(match task.name() {
+                "Compile" => 60,
+                "Lint" => 20,
+                "Document" => 10,
+                _ => 10,
+            }))
+        });
+        assert_eq!(report.work, Duration::from_secs(100));
+        assert_eq!(report.span, Duration::from_secs(70));
+        assert_eq!(report.max_workers, 3);
+        let bottlenecks: Vec<_> = report
+            .bottlenecks
+            .iter()
+            .map(|(task, cost)| (task.name(), cost.as_secs()))
+            .collect();
+        assert_eq!(bottlenecks, [("Compile", 60), ("Package", 10)]);
+    }
+}
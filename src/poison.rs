@@ -0,0 +1,230 @@
+use std::any::{type_name, TypeId};
+
+use crate::{
+    DataBase, DbKey, ExecutionError, ExecutionGraph, GraphRunError, KeyType, Poisoned, TaskInput,
+    TaskOutput, TaskWithContext,
+};
+
+impl<Db: DataBase, Ctx> ExecutionGraph<Db, Ctx> {
+    /// When a task fails after it started running (a failed output assertion
+    /// or a caught panic), the keys it writes may hold stale or half-written
+    /// values. Those keys are poisoned, and tasks reading them are refused
+    /// until the failed task succeeds again or the poison is cleared.
+    pub fn is_poisoned<K: DbKey>(&self) -> bool {
+        self.poisoned.contains(&TypeId::of::<K>())
+    }
+
+    /// Clears the poison on `K`, e.g. after repairing its value with
+    /// [`set_input`](Self::set_input).
+    pub fn clear_poison<K: DbKey>(&mut self) -> &mut Self {
+        self.poisoned.remove(&TypeId::of::<K>());
+        self
+    }
+
+    /// Follows `error` back through poisoned inputs to the failure that
+    /// caused it, using the last error of each task involved.
+    pub fn explain(&self, error: ExecutionError) -> GraphRunError {
+        let inputs = self
+            .names
+            .iter()
+            .find(|(_, name)| **name == error.task())
+            .and_then(|(id, _)| self.failures.get(id))
+            .filter(|(failure, _)| *failure == error)
+            .map(|(_, inputs)| inputs.clone())
+            .unwrap_or_default();
+        let mut explained = GraphRunError {
+            chain: vec![error],
+            inputs: vec![inputs],
+        };
+        while let Some(ExecutionError::Poisoned(poisoned)) = explained.chain.last() {
+            let cause = self
+                .entries
+                .iter()
+                .find(|entry| entry.writes.contains(&poisoned.key))
+                .and_then(|entry| self.failures.get(&entry.id));
+            match cause {
+                Some((cause, inputs)) if !explained.chain.contains(cause) => {
+                    explained.chain.push(cause.clone());
+                    explained.inputs.push(inputs.clone());
+                }
+                _ => break,
+            }
+        }
+        explained
+    }
+
+    /// Records the failure of `task`, along with the inputs it read.
+    pub(crate) fn record_failure(&mut self, task: TypeId, error: ExecutionError) {
+        let inputs = self.task_provenance(task);
+        self.failures.insert(task, (error, inputs));
+    }
+
+    pub(crate) fn check_poison<T: TaskWithContext<Db, Ctx>>(&self) -> Result<(), Poisoned> {
+        let read = T::Input::dep_types().into_iter().chain([KeyType {
+            id: TypeId::of::<T::Input>(),
+            name: type_name::<T::Input>(),
+        }]);
+        for key in read {
+            if self.poisoned.contains(&key.id) {
+                return Err(Poisoned {
+                    task: type_name::<T>(),
+                    key,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn written<T: TaskWithContext<Db, Ctx>>() -> impl Iterator<Item = TypeId> {
+        std::iter::once(TypeId::of::<T::Output>())
+            .chain(T::Output::out_types().into_iter().map(|key| key.id))
+    }
+
+    pub(crate) fn poison_outputs<T: TaskWithContext<Db, Ctx>>(&mut self) {
+        self.poisoned.extend(Self::written::<T>());
+    }
+
+    pub(crate) fn clear_outputs_poison<T: TaskWithContext<Db, Ctx>>(&mut self) {
+        for id in Self::written::<T>() {
+            self.poisoned.remove(&id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExecutionGraphBuilder, InMemoryDb, OutputAssertionFailed, Task};
+
+    struct Count;
+
+    impl DbKey for Count {
+        type Value = u32;
+    }
+
+    struct CountIn(u32);
+
+    impl DbKey for CountIn {
+        type Value = CountIn;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for CountIn {
+        fn from_db(db: &Db) -> Self {
+            CountIn(*db.get::<Count>().unwrap())
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Count>()]
+        }
+    }
+
+    #[derive(Clone)]
+    struct Checked(u32);
+
+    impl DbKey for Checked {
+        type Value = Checked;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for Checked {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<Checked>(self.clone());
+        }
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for Checked {
+        fn from_db(db: &Db) -> Self {
+            db.get::<Checked>().unwrap().clone()
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Checked>()]
+        }
+    }
+
+    struct Check;
+
+    impl Task<InMemoryDb> for Check {
+        type Input = CountIn;
+        type Output = Checked;
+
+        fn execute(input: Self::Input) -> Self::Output {
+            Checked(input.0)
+        }
+    }
+
+    struct Publish;
+
+    impl Task<InMemoryDb> for Publish {
+        type Input = Checked;
+        type Output = ();
+
+        fn execute(_input: Self::Input) -> Self::Output {}
+    }
+
+    fn graph() -> crate::ExecutionGraph<InMemoryDb> {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_input::<Count>(0)
+            .unwrap()
+            .add_task::<Check>()
+            .unwrap()
+            .add_task::<Publish>()
+            .unwrap()
+            .on_output::<Checked>(|c| match c.0 {
+                0 => Err("count must be > 0".to_string()),
+                _ => Ok(()),
+            });
+        builder.build()
+    }
+
+    #[test]
+    fn test_failure_poisons_dependents() {
+        let mut graph = graph();
+        assert!(graph.execute::<Check>().is_err());
+        assert!(graph.is_poisoned::<Checked>());
+        assert!(matches!(
+            graph.execute::<Publish>(),
+            Err(ExecutionError::Poisoned(Poisoned { key, .. })) if key == KeyType::of::<Checked>()
+        ));
+
+        graph.clear_poison::<Checked>();
+        assert!(graph.execute::<Publish>().is_ok());
+    }
+
+    #[test]
+    fn test_rerun_clears_poison() {
+        let mut graph = graph();
+        assert!(graph.execute_all().is_err());
+        graph.set_input::<Count>(3).unwrap();
+        graph.execute_all().unwrap();
+        assert!(!graph.is_poisoned::<Checked>());
+    }
+
+    #[test]
+    fn test_explain_follows_poison() {
+        let mut graph = graph();
+        assert!(graph.execute::<Check>().is_err());
+        let err = graph.execute::<Publish>().unwrap_err();
+        let explained = graph.explain(err);
+        assert_eq!(
+            explained.tasks().collect::<Vec<_>>(),
+            vec![type_name::<Publish>(), type_name::<Check>()]
+        );
+        assert_eq!(explained.chain().len(), 2);
+        assert!(matches!(
+            explained.root_cause(),
+            ExecutionError::OutputAssertionFailed(OutputAssertionFailed { .. })
+        ));
+        assert!(explained.to_string().starts_with(&format!(
+            "task `{}` failed because output",
+            type_name::<Publish>()
+        )));
+        let count = (KeyType::of::<Count>(), graph.input_revision::<Count>());
+        assert_eq!(explained.inputs()[1].inputs, vec![count]);
+        assert!(explained.to_string().ends_with(&format!(
+            "(from `{}` at revision {})",
+            type_name::<Count>(),
+            count.1
+        )));
+    }
+}
@@ -0,0 +1,286 @@
+use std::{
+    any::TypeId,
+    collections::{BTreeMap, HashMap},
+};
+
+use crate::{DataBase, DbKey, ExecutionGraph, KeyType, TaskWithContext};
+
+/// The inputs a value was computed from, returned by
+/// [`ExecutionGraph::provenance_of`].
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Provenance {
+    /// Each input that transitively contributed to the value, with its
+    /// [revision](ExecutionGraph::input_revision) at the time, by name.
+    pub inputs: Vec<(KeyType, u64)>,
+}
+
+#[derive(Default)]
+pub(crate) struct Provenances {
+    revisions: HashMap<TypeId, u64>,
+    computed: HashMap<TypeId, BTreeMap<&'static str, (KeyType, u64)>>,
+}
+
+impl Provenances {
+    pub(crate) fn forget(&mut self, key: TypeId) {
+        self.computed.remove(&key);
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraph<Db, Ctx> {
+    /// Counts a new value of input `key`.
+    pub(crate) fn bump_revision(&mut self, key: KeyType) {
+        *self.provenance.revisions.entry(key.id).or_default() += 1;
+    }
+
+    /// How many times input `K` was given a value through the graph, by
+    /// [`add_input`](crate::ExecutionGraphBuilder::add_input),
+    /// [`set_input`](ExecutionGraph::set_input), a changed file or a
+    /// per-run input.
+    pub fn input_revision<K: DbKey>(&self) -> u64 {
+        self.revision_of(TypeId::of::<K>())
+    }
+
+    fn revision_of(&self, key: TypeId) -> u64 {
+        self.provenance
+            .revisions
+            .get(&key)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Records that the keys `T` writes were just computed from the current
+    /// revisions of the inputs behind the keys it reads.
+    pub(crate) fn record_provenance<T: TaskWithContext<Db, Ctx>>(&mut self) {
+        let Some(entry) = self.entries.iter().find(|e| e.id == TypeId::of::<T>()) else {
+            return;
+        };
+        let inputs = self.inputs_behind(&entry.deps);
+        let written: Vec<TypeId> = entry
+            .writes
+            .iter()
+            .map(|key| key.id)
+            .chain([entry.output.id])
+            .collect();
+        for key in written {
+            self.provenance.computed.insert(key, inputs.clone());
+        }
+    }
+
+    /// The inputs behind `deps`, with the revisions the values of `deps`
+    /// were computed from.
+    fn inputs_behind(&self, deps: &[KeyType]) -> BTreeMap<&'static str, (KeyType, u64)> {
+        let mut inputs = BTreeMap::new();
+        for &dep in deps {
+            if self.writer_of(dep).is_some() {
+                if let Some(upstream) = self.provenance.computed.get(&dep.id) {
+                    inputs.extend(upstream.iter().map(|(name, input)| (*name, *input)));
+                }
+            } else {
+                inputs.insert(dep.name, (dep, self.revision_of(dep.id)));
+            }
+        }
+        inputs
+    }
+
+    /// Whether an input behind the current value of `value` changed since
+    /// it was computed.
+    #[cfg(feature = "web-ui")]
+    pub(crate) fn is_outdated(&self, value: TypeId) -> bool {
+        self.provenance.computed.get(&value).is_some_and(|inputs| {
+            inputs
+                .values()
+                .any(|(input, revision)| self.revision_of(input.id) != *revision)
+        })
+    }
+
+    /// The inputs behind the keys `task` reads, as of now.
+    pub(crate) fn task_provenance(&self, task: TypeId) -> Provenance {
+        let deps = self
+            .entries
+            .iter()
+            .find(|e| e.id == task)
+            .map(|e| &e.deps[..]);
+        Provenance {
+            inputs: self
+                .inputs_behind(deps.unwrap_or_default())
+                .into_values()
+                .collect(),
+        }
+    }
+
+    /// The revision of input `input` that `value` was computed from, or its
+    /// current one if none was recorded.
+    pub(crate) fn revision_behind(&self, value: TypeId, input: KeyType) -> u64 {
+        self.provenance
+            .computed
+            .get(&value)
+            .and_then(|inputs| inputs.get(input.name))
+            .map_or_else(|| self.revision_of(input.id), |(_, revision)| *revision)
+    }
+
+    /// The inputs the current value of `K` was computed from, with their
+    /// revisions then, or `None` if no task computed it. An input is its own
+    /// provenance.
+    pub fn provenance_of<K: DbKey>(&self) -> Option<Provenance> {
+        let key = KeyType::of::<K>();
+        if let Some(inputs) = self.provenance.computed.get(&key.id) {
+            return Some(Provenance {
+                inputs: inputs.values().copied().collect(),
+            });
+        }
+        if self.writer_of(key).is_some() || self.contains_node(&key.id).is_none() {
+            return None;
+        }
+        Some(Provenance {
+            inputs: vec![(key, self.revision_of(key.id))],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExecutionGraphBuilder, InMemoryDb, Task, TaskInput, TaskOutput};
+
+    struct Sales;
+
+    impl DbKey for Sales {
+        type Value = Vec<u32>;
+    }
+
+    struct Rate;
+
+    impl DbKey for Rate {
+        type Value = u32;
+    }
+
+    struct Logo;
+
+    impl DbKey for Logo {
+        type Value = ();
+    }
+
+    struct Total;
+
+    impl DbKey for Total {
+        type Value = u32;
+    }
+
+    struct SalesIn(Vec<u32>);
+
+    impl DbKey for SalesIn {
+        type Value = SalesIn;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for SalesIn {
+        fn from_db(db: &Db) -> Self {
+            SalesIn(db.get::<Sales>().unwrap().clone())
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Sales>()]
+        }
+    }
+
+    struct TotalOut(u32);
+
+    impl DbKey for TotalOut {
+        type Value = TotalOut;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for TotalOut {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<Total>(self.0);
+        }
+
+        fn out_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Total>()]
+        }
+    }
+
+    struct Sum;
+
+    impl Task<InMemoryDb> for Sum {
+        type Input = SalesIn;
+        type Output = TotalOut;
+
+        fn execute(input: Self::Input) -> Self::Output {
+            TotalOut(input.0.iter().sum())
+        }
+    }
+
+    struct ReportIn(u32, u32);
+
+    impl DbKey for ReportIn {
+        type Value = ReportIn;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for ReportIn {
+        fn from_db(db: &Db) -> Self {
+            ReportIn(*db.get::<Total>().unwrap(), *db.get::<Rate>().unwrap())
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Total>(), KeyType::of::<Rate>()]
+        }
+    }
+
+    struct Report(u32);
+
+    impl DbKey for Report {
+        type Value = Report;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for Report {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<Report>(Report(self.0));
+        }
+    }
+
+    struct Convert;
+
+    impl Task<InMemoryDb> for Convert {
+        type Input = ReportIn;
+        type Output = Report;
+
+        fn execute(input: Self::Input) -> Self::Output {
+            Report(input.0 * input.1)
+        }
+    }
+
+    #[test]
+    fn test_provenance() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_input::<Sales>(vec![1, 2])
+            .unwrap()
+            .add_input::<Rate>(3)
+            .unwrap()
+            .add_input::<Logo>(())
+            .unwrap()
+            .add_task::<Sum>()
+            .unwrap()
+            .add_task::<Convert>()
+            .unwrap();
+        let mut graph = builder.build();
+        assert_eq!(graph.provenance_of::<Report>(), None);
+        graph.execute_all().unwrap();
+        assert_eq!(graph.db().get::<Report>().unwrap().0, 9);
+        graph.set_input::<Rate>(4).unwrap();
+        assert_eq!(graph.input_revision::<Rate>(), 2);
+
+        let provenance = graph.provenance_of::<Report>().unwrap();
+        assert_eq!(
+            provenance.inputs,
+            [(KeyType::of::<Rate>(), 1), (KeyType::of::<Sales>(), 1)]
+        );
+        assert_eq!(
+            graph.provenance_of::<Total>().unwrap().inputs,
+            [(KeyType::of::<Sales>(), 1)]
+        );
+        assert_eq!(
+            graph.provenance_of::<Rate>().unwrap().inputs,
+            [(KeyType::of::<Rate>(), 2)]
+        );
+    }
+}
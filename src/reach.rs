@@ -0,0 +1,344 @@
+use std::{cell::RefCell, collections::HashSet};
+
+use petgraph::{graph::EdgeIndex, visit::Bfs};
+
+use crate::{DataBase, DbKey, ExecutionGraph, KeyType};
+
+/// Which nodes each node reaches, as one bit set per node, computed on the
+/// first query and kept until the graph changes.
+pub(crate) struct Reachability {
+    /// The [generation](ReachabilityCache::invalidate) of the graph the sets
+    /// were computed for.
+    generation: u64,
+    reaches: Vec<Vec<u64>>,
+}
+
+impl Reachability {
+    fn reaches(&self, from: usize, to: usize) -> bool {
+        self.reaches[from][to / 64] & (1 << (to % 64)) != 0
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct ReachabilityCache {
+    generation: u64,
+    cached: RefCell<Option<Reachability>>,
+}
+
+impl ReachabilityCache {
+    /// Drops the cached sets; called on every change to the graph's nodes
+    /// or edges.
+    pub(crate) fn invalidate(&mut self) {
+        self.generation += 1;
+    }
+
+    /// Counts the changes to the graph's nodes and edges.
+    #[cfg(feature = "web-ui")]
+    pub(crate) fn generation(&self) -> u64 {
+        self.generation
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraph<Db, Ctx> {
+    fn with_reachability<R>(&self, f: impl FnOnce(&Reachability) -> R) -> R {
+        let generation = self.reachability.generation;
+        let mut cache = self.reachability.cached.borrow_mut();
+        if cache
+            .as_ref()
+            .is_none_or(|cached| cached.generation != generation)
+        {
+            let graph = self.flow_graph();
+            let words = graph.node_count().div_ceil(64);
+            let reaches = graph
+                .node_indices()
+                .map(|n| {
+                    let mut set = vec![0u64; words];
+                    let mut bfs = Bfs::new(&graph, n);
+                    while let Some(m) = bfs.next(&graph) {
+                        set[m.index() / 64] |= 1 << (m.index() % 64);
+                    }
+                    set
+                })
+                .collect();
+            *cache = Some(Reachability {
+                generation,
+                reaches,
+            });
+        }
+        f(cache.as_ref().unwrap())
+    }
+
+    /// Whether a change to `from` can affect `to`, i.e. `to` is computed,
+    /// through any number of tasks, from `from`. A key reaches itself.
+    ///
+    /// The first query after the graph changes takes time quadratic in its
+    /// size; later ones take constant time.
+    pub fn is_reachable(&self, from: KeyType, to: KeyType) -> bool {
+        let (Some(from), Some(to)) = (self.contains_node(&from.id), self.contains_node(&to.id))
+        else {
+            return false;
+        };
+        self.with_reachability(|reach| reach.reaches(from.index(), to.index()))
+    }
+
+    /// Every key computed, through any number of tasks, from `K`: what a
+    /// change to `K` may affect. Task inputs and outputs are keys too.
+    pub fn impact_set<K: DbKey>(&self) -> Vec<KeyType> {
+        let Some(from) = self.contains_node(&KeyType::of::<K>().id) else {
+            return Vec::new();
+        };
+        let mut keys: Vec<KeyType> = self.with_reachability(|reach| {
+            self.tasks
+                .node_indices()
+                .filter(|&n| n != from && reach.reaches(from.index(), n.index()))
+                .map(|n| {
+                    let id = self.tasks[n];
+                    KeyType {
+                        id,
+                        name: self.names[&id],
+                    }
+                })
+                .collect()
+        });
+        keys.dedup();
+        keys
+    }
+
+    /// Removes the dependency edges implied by longer paths, and duplicate
+    /// ones, returning how many were removed. Which keys reach which is
+    /// unchanged, and so are the keys tasks read and invalidation, which go
+    /// by the tasks' declared dependencies.
+    pub fn transitive_reduction(&mut self) -> usize {
+        let flow = self.flow_graph();
+        let redundant: Vec<EdgeIndex> = self.with_reachability(|reach| {
+            let mut seen = HashSet::new();
+            self.tasks
+                .edge_indices()
+                .filter(|&edge| {
+                    let (u, v) = self.tasks.edge_endpoints(edge).unwrap();
+                    // A path through `w` that does not come back to `u`
+                    // cannot use the edge itself, even on a cycle.
+                    !seen.insert((u, v))
+                        || flow.neighbors(u).any(|w| {
+                            w != v
+                                && reach.reaches(w.index(), v.index())
+                                && !reach.reaches(w.index(), u.index())
+                        })
+                })
+                .collect()
+        });
+        // Removing an edge moves the last edge into its slot.
+        for &edge in redundant.iter().rev() {
+            self.tasks.remove_edge(edge);
+        }
+        self.reachability.invalidate();
+        redundant.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExecutionGraphBuilder, InMemoryDb, Task, TaskInput, TaskOutput};
+
+    struct Source;
+
+    impl DbKey for Source {
+        type Value = String;
+    }
+
+    struct Config;
+
+    impl DbKey for Config {
+        type Value = bool;
+    }
+
+    struct Tokens;
+
+    impl DbKey for Tokens {
+        type Value = Vec<String>;
+    }
+
+    struct SourceIn(String);
+
+    impl DbKey for SourceIn {
+        type Value = SourceIn;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for SourceIn {
+        fn from_db(db: &Db) -> Self {
+            SourceIn(db.get::<Source>().unwrap().clone())
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Source>()]
+        }
+    }
+
+    struct TokensOut(Vec<String>);
+
+    impl DbKey for TokensOut {
+        type Value = TokensOut;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for TokensOut {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<Tokens>(self.0.clone());
+        }
+
+        fn out_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Tokens>()]
+        }
+    }
+
+    struct Tokenize;
+
+    impl Task<InMemoryDb> for Tokenize {
+        type Input = SourceIn;
+        type Output = TokensOut;
+
+        fn execute(input: Self::Input) -> Self::Output {
+            TokensOut(input.0.split_whitespace().map(String::from).collect())
+        }
+    }
+
+    #[test]
+    fn test_reachability() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_input::<Source>(String::new())
+            .unwrap()
+            .add_input::<Config>(false)
+            .unwrap()
+            .add_task::<Tokenize>()
+            .unwrap();
+        let graph = builder.build();
+        let (source, config, tokens) = (
+            KeyType::of::<Source>(),
+            KeyType::of::<Config>(),
+            KeyType::of::<Tokens>(),
+        );
+        assert!(graph.is_reachable(source, tokens));
+        assert!(!graph.is_reachable(tokens, source));
+        assert!(!graph.is_reachable(config, tokens));
+        assert_eq!(
+            graph.impact_set::<Source>(),
+            [
+                KeyType::of::<SourceIn>(),
+                KeyType::of::<TokensOut>(),
+                tokens
+            ]
+        );
+        assert!(graph.impact_set::<Config>().is_empty());
+    }
+
+    struct CountIn;
+
+    impl DbKey for CountIn {
+        type Value = CountIn;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for CountIn {
+        fn from_db(_db: &Db) -> Self {
+            CountIn
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Source>(), KeyType::of::<Tokens>()]
+        }
+    }
+
+    struct Count;
+
+    impl DbKey for Count {
+        type Value = Count;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for Count {
+        fn to_db(&self, _db: &mut Db) {}
+    }
+
+    struct CountTokens;
+
+    impl Task<InMemoryDb> for CountTokens {
+        type Input = CountIn;
+        type Output = Count;
+
+        fn execute(_input: Self::Input) -> Self::Output {
+            Count
+        }
+    }
+
+    #[test]
+    fn test_transitive_reduction() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_input::<Source>(String::new())
+            .unwrap()
+            .add_task::<Tokenize>()
+            .unwrap()
+            .add_task::<CountTokens>()
+            .unwrap();
+        let mut graph = builder.build();
+        let before = graph.impact_set::<Source>();
+        // Source -> CountIn is implied by Source -> ... -> Tokens -> CountIn.
+        assert_eq!(graph.transitive_reduction(), 1);
+        assert_eq!(graph.transitive_reduction(), 0);
+        assert_eq!(graph.impact_set::<Source>(), before);
+        assert!(graph.is_reachable(KeyType::of::<Source>(), KeyType::of::<CountIn>()));
+    }
+
+    struct ConfigIn;
+
+    impl DbKey for ConfigIn {
+        type Value = ConfigIn;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for ConfigIn {
+        fn from_db(_db: &Db) -> Self {
+            ConfigIn
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Config>()]
+        }
+    }
+
+    struct DefaultTokens;
+
+    impl Task<InMemoryDb> for DefaultTokens {
+        type Input = ConfigIn;
+        type Output = TokensOut;
+
+        fn execute(_input: Self::Input) -> Self::Output {
+            TokensOut(Vec::new())
+        }
+    }
+
+    #[test]
+    fn test_reachability_after_same_size_edit() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_input::<Source>(String::new())
+            .unwrap()
+            .add_input::<Config>(false)
+            .unwrap()
+            .add_task::<Tokenize>()
+            .unwrap();
+        let (source, config, tokens) = (
+            KeyType::of::<Source>(),
+            KeyType::of::<Config>(),
+            KeyType::of::<Tokens>(),
+        );
+        assert!(builder.graph.is_reachable(source, tokens));
+
+        // Same node and edge counts as before, wired differently.
+        builder
+            .remove_task::<Tokenize>()
+            .unwrap()
+            .add_task::<DefaultTokens>()
+            .unwrap();
+        assert!(builder.graph.is_reachable(config, tokens));
+        assert!(!builder.graph.is_reachable(source, tokens));
+    }
+}
@@ -0,0 +1,289 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    rc::Rc,
+};
+
+use crate::{
+    DataBase, ExecutionError, ExecutionGraph, ExecutionGraphBuilder, TaskOutput, TaskWithContext,
+};
+
+/// What to do about a failed task, decided by a [`GraphErrorHandler`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Recovery {
+    /// Run the task again.
+    Retry,
+    /// Leave the task failed, but let
+    /// [`execute_all`](ExecutionGraph::execute_all) go on with the next
+    /// task. Tasks reading its outputs fail as poisoned.
+    Skip,
+    /// Write the output of the task's
+    /// [`fallback`](ExecutionGraphBuilder::fallback) instead, or abort if it
+    /// has none.
+    Fallback,
+    /// Return the error, stopping the run.
+    Abort,
+}
+
+/// Decides how the graph recovers from each failed task, set with
+/// [`ExecutionGraphBuilder::error_handler`]. Without one, every failure
+/// aborts.
+///
+/// [`BudgetExceeded`](ExecutionError::BudgetExceeded) always aborts without
+/// consulting the handler.
+pub trait GraphErrorHandler {
+    /// `attempt` counts the executions of the task that failed, from 1.
+    fn handle(&self, error: &ExecutionError, attempt: u32) -> Recovery;
+}
+
+impl<F: Fn(&ExecutionError, u32) -> Recovery> GraphErrorHandler for F {
+    fn handle(&self, error: &ExecutionError, attempt: u32) -> Recovery {
+        self(error, attempt)
+    }
+}
+
+type Fallback = Rc<dyn Fn() -> Box<dyn Any>>;
+
+#[derive(Clone, Default)]
+pub(crate) struct ErrorHandling {
+    handler: Option<Rc<dyn GraphErrorHandler>>,
+    fallbacks: HashMap<TypeId, Fallback>,
+    /// Set when the last failed task was skipped.
+    skipped: bool,
+}
+
+impl ErrorHandling {
+    pub(crate) fn forget(&mut self, task: TypeId) {
+        self.fallbacks.remove(&task);
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraphBuilder<Db, Ctx> {
+    pub fn error_handler(&mut self, handler: impl GraphErrorHandler + 'static) -> &mut Self {
+        self.graph.errors.handler = Some(Rc::new(handler));
+        self
+    }
+
+    /// Sets the output written in place of a failed run of `T` when the
+    /// error handler decides on [`Recovery::Fallback`].
+    pub fn fallback<T: TaskWithContext<Db, Ctx>>(
+        &mut self,
+        fallback: impl Fn() -> T::Output + 'static,
+    ) -> &mut Self {
+        self.graph
+            .errors
+            .fallbacks
+            .insert(TypeId::of::<T>(), Rc::new(move || Box::new(fallback())));
+        self
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraph<Db, Ctx> {
+    /// Asks the error handler how to recover from `error`. On
+    /// [`Recovery::Fallback`], returns the fallback output of `T` once it is
+    /// written.
+    pub(crate) fn recover<T: TaskWithContext<Db, Ctx>>(
+        &mut self,
+        error: &ExecutionError,
+        attempt: u32,
+    ) -> (Recovery, Option<T::Output>) {
+        let recovery = match (&self.errors.handler, error) {
+            (_, ExecutionError::BudgetExceeded(_)) | (None, _) => Recovery::Abort,
+            (Some(handler), _) => handler.handle(error, attempt),
+        };
+        match recovery {
+            Recovery::Skip => self.errors.skipped = true,
+            Recovery::Fallback => {
+                let Some(fallback) = self.errors.fallbacks.get(&TypeId::of::<T>()) else {
+                    return (Recovery::Abort, None);
+                };
+                let output = *fallback()
+                    .downcast::<T::Output>()
+                    .expect("fallback output type mismatch");
+                self.with_shared_db(|graph| output.to_db(&mut graph.db));
+                self.clear_outputs_poison::<T>();
+                self.failures.remove(&TypeId::of::<T>());
+                return (recovery, Some(output));
+            }
+            Recovery::Retry | Recovery::Abort => {}
+        }
+        (recovery, None)
+    }
+
+    /// Whether the task that just failed was skipped, resetting the flag.
+    pub(crate) fn take_skipped(&mut self) -> bool {
+        std::mem::take(&mut self.errors.skipped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::{DbKey, InMemoryDb, KeyType, Task, TaskInput};
+
+    thread_local! {
+        static FLAKY_CALLS: Cell<u32> = const { Cell::new(0) };
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Answer(u32);
+
+    impl DbKey for Answer {
+        type Value = Answer;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for Answer {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<Answer>(self.clone());
+        }
+    }
+
+    struct Flaky;
+
+    impl Task<InMemoryDb> for Flaky {
+        type Input = ();
+        type Output = Answer;
+
+        fn execute(_input: Self::Input) -> Self::Output {
+            let calls = FLAKY_CALLS.with(|calls| {
+                calls.set(calls.get() + 1);
+                calls.get()
+            });
+            if calls < 3 {
+                panic!("attempt {calls} failed");
+            }
+            Answer(42)
+        }
+    }
+
+    struct Broken;
+
+    impl Task<InMemoryDb> for Broken {
+        type Input = ();
+        type Output = Answer;
+
+        fn execute(_input: Self::Input) -> Self::Output {
+            panic!("always fails")
+        }
+    }
+
+    struct AnswerIn(u32);
+
+    impl DbKey for AnswerIn {
+        type Value = AnswerIn;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for AnswerIn {
+        fn from_db(db: &Db) -> Self {
+            AnswerIn(db.get::<Answer>().unwrap().0)
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Answer>()]
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Doubled(u32);
+
+    impl DbKey for Doubled {
+        type Value = Doubled;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for Doubled {
+        fn to_db(&self, _db: &mut Db) {}
+    }
+
+    struct Double;
+
+    impl Task<InMemoryDb> for Double {
+        type Input = AnswerIn;
+        type Output = Doubled;
+
+        fn execute(input: Self::Input) -> Self::Output {
+            Doubled(input.0 * 2)
+        }
+    }
+
+    #[test]
+    fn test_retry() {
+        FLAKY_CALLS.with(|calls| calls.set(0));
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .error_handler(|_: &ExecutionError, attempt| match attempt {
+                1..3 => Recovery::Retry,
+                _ => Recovery::Abort,
+            })
+            .add_task::<Flaky>()
+            .unwrap();
+        let mut graph = builder.build();
+        graph.catch_panics(true);
+        assert_eq!(graph.execute::<Flaky>().unwrap(), Answer(42));
+        assert_eq!(FLAKY_CALLS.with(Cell::get), 3);
+    }
+
+    struct AlsoBroken;
+
+    impl Task<InMemoryDb> for AlsoBroken {
+        type Input = ();
+        type Output = ();
+
+        fn execute(_input: Self::Input) -> Self::Output {
+            panic!("always fails too")
+        }
+    }
+
+    #[test]
+    fn test_skip_does_not_leak_into_next_run() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .error_handler(|error: &ExecutionError, _| {
+                match error.task() == std::any::type_name::<Broken>() {
+                    true => Recovery::Skip,
+                    false => Recovery::Abort,
+                }
+            })
+            .add_task::<AlsoBroken>()
+            .unwrap()
+            .add_task::<Broken>()
+            .unwrap();
+        let mut graph = builder.build();
+        graph.catch_panics(true);
+        assert!(graph.execute::<Broken>().is_err());
+        let Err(error) = graph.execute_all() else {
+            panic!("expected the run to abort")
+        };
+        assert_eq!(error.task(), std::any::type_name::<AlsoBroken>());
+    }
+
+    #[test]
+    fn test_fallback_and_skip() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .error_handler(|_: &ExecutionError, _| Recovery::Fallback)
+            .fallback::<Broken>(|| Answer(0))
+            .add_task::<Broken>()
+            .unwrap()
+            .add_task::<Double>()
+            .unwrap();
+        let mut graph = builder.build();
+        graph.catch_panics(true);
+        graph.execute_all().unwrap();
+        assert_eq!(graph.db().get::<Answer>(), Some(&Answer(0)));
+        assert_eq!(graph.execute::<Double>().unwrap(), Doubled(0));
+
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .error_handler(|_: &ExecutionError, _| Recovery::Skip)
+            .add_task::<Broken>()
+            .unwrap()
+            .add_task::<Double>()
+            .unwrap();
+        let mut graph = builder.build();
+        graph.catch_panics(true);
+        graph.execute_all().unwrap();
+        assert!(graph.execute::<Double>().is_err());
+    }
+}
@@ -0,0 +1,213 @@
+use std::{marker::PhantomData, rc::Rc};
+
+use crate::{
+    fingerprint::{fingerprint_all, Fingerprint},
+    DataBase, DbKey, ExecutionGraphBuilder, KeyType, MissingDependency, Task, TaskInput,
+    TaskOutput,
+};
+
+/// Items per partially reduced chunk. A changed item costs re-folding its
+/// chunk plus combining one partial per chunk.
+const CHUNK: usize = 32;
+
+/// An associative fold over items, run by [`ReduceTask`].
+pub trait Reducer: 'static {
+    type Item: Clone + Fingerprint + 'static;
+    type Acc: Clone + 'static;
+
+    /// The aggregate of no items.
+    fn empty() -> Self::Acc;
+
+    /// Folds one item into an aggregate.
+    fn fold(acc: Self::Acc, item: &Self::Item) -> Self::Acc;
+
+    /// Merges two aggregates, `a` covering the items before `b`'s.
+    fn combine(a: &Self::Acc, b: &Self::Acc) -> Self::Acc;
+}
+
+/// The chunk fingerprints and partial aggregates of the last run of a [`ReduceTask`]
+/// reducing with `R` into `Out`.
+pub struct ReduceState<R, Out>(PhantomData<fn(R) -> Out>);
+
+type Chunks<R> = Rc<Vec<(u64, <R as Reducer>::Acc)>>;
+
+impl<R: Reducer, Out: 'static> DbKey for ReduceState<R, Out> {
+    type Value = Chunks<R>;
+}
+
+pub struct ReduceInput<In, R: Reducer, Out> {
+    items: Vec<R::Item>,
+    previous: Option<Chunks<R>>,
+    _marker: PhantomData<fn(In) -> Out>,
+}
+
+impl<In, R: Reducer, Out: 'static> DbKey for ReduceInput<In, R, Out>
+where
+    In: 'static,
+{
+    type Value = Self;
+}
+
+impl<Db, In, R, Out> TaskInput<Db> for ReduceInput<In, R, Out>
+where
+    Db: DataBase,
+    R: Reducer,
+    In: DbKey<Value = Vec<R::Item>>,
+    Out: 'static,
+{
+    fn from_db(db: &Db) -> Self {
+        ReduceInput {
+            items: db.get::<In>().unwrap().clone(),
+            // Written by the task itself, so deliberately not a dependency.
+            previous: db.get::<ReduceState<R, Out>>().cloned(),
+            _marker: PhantomData,
+        }
+    }
+
+    fn dep_types() -> Vec<KeyType> {
+        vec![KeyType::of::<In>()]
+    }
+}
+
+pub struct ReduceOutput<R: Reducer, Out> {
+    acc: R::Acc,
+    chunks: Chunks<R>,
+    _marker: PhantomData<fn() -> Out>,
+}
+
+impl<R: Reducer, Out: 'static> DbKey for ReduceOutput<R, Out> {
+    type Value = Self;
+}
+
+impl<Db, R, Out> TaskOutput<Db> for ReduceOutput<R, Out>
+where
+    Db: DataBase,
+    R: Reducer,
+    Out: DbKey<Value = R::Acc>,
+{
+    fn to_db(&self, db: &mut Db) {
+        db.put::<Out>(self.acc.clone());
+        db.put::<ReduceState<R, Out>>(self.chunks.clone());
+    }
+
+    fn out_types() -> Vec<KeyType> {
+        vec![KeyType::of::<Out>(), KeyType::of::<ReduceState<R, Out>>()]
+    }
+}
+
+/// Folds the items of `In` with `R` into `Out`. Partial aggregates are kept
+/// per chunk of items between runs, so when only some items change, only
+/// their chunks are folded again. Chunks are compared by
+/// [`Fingerprint`]. Inserting or removing items shifts every
+/// chunk after them.
+pub struct ReduceTask<In, R, Out>(PhantomData<fn(In, R) -> Out>);
+
+impl<Db, In, R, Out> Task<Db> for ReduceTask<In, R, Out>
+where
+    Db: DataBase,
+    R: Reducer,
+    In: DbKey<Value = Vec<R::Item>>,
+    Out: DbKey<Value = R::Acc>,
+{
+    type Input = ReduceInput<In, R, Out>;
+    type Output = ReduceOutput<R, Out>;
+
+    fn execute(input: Self::Input) -> Self::Output {
+        let previous = input.previous.as_deref().map_or(&[][..], Vec::as_slice);
+        let chunks: Vec<_> = input
+            .items
+            .chunks(CHUNK)
+            .enumerate()
+            .map(|(i, items)| {
+                let fingerprint = fingerprint_all(items);
+                match previous.get(i) {
+                    Some((old, acc)) if *old == fingerprint => (fingerprint, acc.clone()),
+                    _ => (fingerprint, items.iter().fold(R::empty(), R::fold)),
+                }
+            })
+            .collect();
+        let acc = chunks
+            .iter()
+            .fold(R::empty(), |acc, (_, partial)| R::combine(&acc, partial));
+        ReduceOutput {
+            acc,
+            chunks: Rc::new(chunks),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraphBuilder<Db, Ctx> {
+    /// Adds a [`ReduceTask`] folding the items of `In` with `R` into `Out`.
+    pub fn add_reduce<In, R, Out>(&mut self) -> Result<&mut Self, MissingDependency>
+    where
+        R: Reducer,
+        In: DbKey<Value = Vec<R::Item>>,
+        Out: DbKey<Value = R::Acc>,
+    {
+        self.add_task::<ReduceTask<In, R, Out>>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::InMemoryDb;
+
+    static FOLDS: AtomicUsize = AtomicUsize::new(0);
+
+    struct Diagnostics;
+
+    impl DbKey for Diagnostics {
+        type Value = Vec<u32>;
+    }
+
+    struct Total;
+
+    impl DbKey for Total {
+        type Value = u32;
+    }
+
+    struct Sum;
+
+    impl Reducer for Sum {
+        type Item = u32;
+        type Acc = u32;
+
+        fn empty() -> u32 {
+            0
+        }
+
+        fn fold(acc: u32, item: &u32) -> u32 {
+            FOLDS.fetch_add(1, Ordering::Relaxed);
+            acc + item
+        }
+
+        fn combine(a: &u32, b: &u32) -> u32 {
+            a + b
+        }
+    }
+
+    #[test]
+    fn test_reduce_refolds_changed_chunks() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_input::<Diagnostics>(vec![1; 100])
+            .unwrap()
+            .add_reduce::<Diagnostics, Sum, Total>()
+            .unwrap();
+        let mut graph = builder.build();
+        graph.execute_all().unwrap();
+        assert_eq!(graph.db().get::<Total>(), Some(&100));
+        assert_eq!(FOLDS.swap(0, Ordering::Relaxed), 100);
+
+        let mut diagnostics = vec![1; 100];
+        diagnostics[40] = 5;
+        graph.set_input::<Diagnostics>(diagnostics).unwrap();
+        graph.execute_all().unwrap();
+        assert_eq!(graph.db().get::<Total>(), Some(&104));
+        assert_eq!(FOLDS.load(Ordering::Relaxed), CHUNK);
+    }
+}
@@ -0,0 +1,381 @@
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use crate::{DataBase, DbKey, ExecutionGraphBuilder, MissingDependency, TaskWithContext};
+
+type AddTask<Db, Ctx> = fn(&mut ExecutionGraphBuilder<Db, Ctx>) -> Result<(), MissingDependency>;
+type AddInput<Db, Ctx> = fn(&mut ExecutionGraphBuilder<Db, Ctx>, &str) -> Result<(), String>;
+
+/// Maps string names to tasks and input keys so that graphs can be assembled
+/// from a pipeline description with [`ExecutionGraphBuilder::from_config`].
+pub struct Registry<Db: DataBase, Ctx = ()> {
+    tasks: HashMap<String, AddTask<Db, Ctx>>,
+    inputs: HashMap<String, AddInput<Db, Ctx>>,
+}
+
+impl<Db: DataBase, Ctx> Registry<Db, Ctx> {
+    pub fn new() -> Self {
+        Registry {
+            tasks: HashMap::new(),
+            inputs: HashMap::new(),
+        }
+    }
+
+    pub fn register_task<T: TaskWithContext<Db, Ctx>>(
+        &mut self,
+        name: impl Into<String>,
+    ) -> &mut Self {
+        fn add<Db: DataBase, Ctx, T: TaskWithContext<Db, Ctx>>(
+            builder: &mut ExecutionGraphBuilder<Db, Ctx>,
+        ) -> Result<(), MissingDependency> {
+            builder.add_task::<T>().map(|_| ())
+        }
+        self.tasks.insert(name.into(), add::<Db, Ctx, T>);
+        self
+    }
+
+    /// Registers an input key whose value is parsed from the config with
+    /// [`FromStr`].
+    pub fn register_input<K: DbKey>(&mut self, name: impl Into<String>) -> &mut Self
+    where
+        K::Value: FromStr,
+        <K::Value as FromStr>::Err: fmt::Display,
+    {
+        fn add<Db: DataBase, Ctx, K: DbKey>(
+            builder: &mut ExecutionGraphBuilder<Db, Ctx>,
+            raw: &str,
+        ) -> Result<(), String>
+        where
+            K::Value: FromStr,
+            <K::Value as FromStr>::Err: fmt::Display,
+        {
+            let value = raw.parse::<K::Value>().map_err(|e| e.to_string())?;
+            builder
+                .add_input::<K>(value)
+                .map(|_| ())
+                .map_err(|e| e.message)
+        }
+        self.inputs.insert(name.into(), add::<Db, Ctx, K>);
+        self
+    }
+}
+
+impl<Db: DataBase, Ctx> Default for Registry<Db, Ctx> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ConfigError {
+    Syntax { line: usize, message: String },
+    UnknownTask(String),
+    UnknownInput(String),
+    InvalidInput { name: String, message: String },
+    MissingDependency(MissingDependency),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Syntax { line, message } => write!(f, "line {line}: {message}"),
+            ConfigError::UnknownTask(name) => write!(f, "no task registered as `{name}`"),
+            ConfigError::UnknownInput(name) => write!(f, "no input registered as `{name}`"),
+            ConfigError::InvalidInput { name, message } => {
+                write!(f, "invalid value for input `{name}`: {message}")
+            }
+            ConfigError::MissingDependency(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<MissingDependency> for ConfigError {
+    fn from(e: MissingDependency) -> Self {
+        ConfigError::MissingDependency(e)
+    }
+}
+
+#[derive(Default)]
+struct PipelineConfig {
+    inputs: Vec<(String, String)>,
+    tasks: Vec<String>,
+}
+
+/// Parses the TOML subset used for pipeline descriptions:
+///
+/// ```toml
+/// tasks = [
+///     "Parse",
+///     "Lower", # arrays may span lines
+/// ]
+///
+/// [inputs]
+/// Width = 640
+/// Title = "hello, \"world\"\n"
+/// ```
+///
+/// Strings support the `\\`, `\"`, `\n`, `\r` and `\t` escapes.
+fn parse_config(config: &str) -> Result<PipelineConfig, ConfigError> {
+    let mut parsed = PipelineConfig::default();
+    let mut in_inputs = false;
+    let mut lines = config.lines().enumerate();
+    while let Some((n, line)) = lines.next() {
+        let err = |message: &str| ConfigError::Syntax {
+            line: n + 1,
+            message: message.to_string(),
+        };
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(table) = line.strip_prefix('[') {
+            match table.strip_suffix(']').map(str::trim) {
+                Some("inputs") => in_inputs = true,
+                Some(other) => return Err(err(&format!("unknown table `{other}`"))),
+                None => return Err(err("unterminated table header")),
+            }
+            continue;
+        }
+        let &[key, _, ..] = split_outside_strings(line, '=').as_slice() else {
+            return Err(err("expected `key = value`"));
+        };
+        let value = &line[key.len() + 1..];
+        let key = parse_string(key.trim()).map_err(&err)?;
+        let mut value = value.trim().to_string();
+        if in_inputs {
+            let value = parse_string(&value).map_err(&err)?;
+            parsed.inputs.push((key, value));
+        } else if key == "tasks" {
+            while value.starts_with('[') && !value.ends_with(']') {
+                let Some((_, next)) = lines.next() else {
+                    return Err(err("unterminated array"));
+                };
+                value.push(' ');
+                value.push_str(strip_comment(next).trim());
+            }
+            let Some(items) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) else {
+                return Err(err("`tasks` must be an array of task names"));
+            };
+            for item in split_outside_strings(items, ',') {
+                let item = item.trim();
+                if !item.is_empty() {
+                    parsed.tasks.push(parse_string(item).map_err(&err)?);
+                }
+            }
+        } else {
+            return Err(err(&format!("unknown key `{key}`")));
+        }
+    }
+    Ok(parsed)
+}
+
+/// The parts of `s` between occurrences of `separator` outside strings.
+fn split_outside_strings(s: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let (mut in_string, mut escaped) = (false, false);
+    for (i, c) in s.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            c if c == separator && !in_string => {
+                parts.push(&s[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+fn strip_comment(line: &str) -> &str {
+    let (mut in_string, mut escaped) = (false, false);
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+/// A quoted string with its escapes resolved, or a bare value as is.
+fn parse_string(s: &str) -> Result<String, &'static str> {
+    let Some(quoted) = s.strip_prefix('"') else {
+        return Ok(s.to_string());
+    };
+    let mut parsed = String::with_capacity(quoted.len());
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if chars.as_str().is_empty() => return Ok(parsed),
+            '"' => return Err("unexpected characters after string"),
+            '\\' => parsed.push(match chars.next() {
+                Some('\\') => '\\',
+                Some('"') => '"',
+                Some('n') => '\n',
+                Some('r') => '\r',
+                Some('t') => '\t',
+                _ => return Err("invalid escape in string"),
+            }),
+            c => parsed.push(c),
+        }
+    }
+    Err("unterminated string")
+}
+
+impl<Db: DataBase> ExecutionGraphBuilder<Db> {
+    /// Creates a builder from a pipeline description, see
+    /// [`configure`](Self::configure).
+    pub fn from_config(db: Db, registry: &Registry<Db>, config: &str) -> Result<Self, ConfigError> {
+        let mut builder = ExecutionGraphBuilder::new(db);
+        builder.configure(registry, config)?;
+        Ok(builder)
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraphBuilder<Db, Ctx> {
+    /// Adds every input in the `[inputs]` table of a pipeline description and
+    /// then every task listed in its `tasks` array, in order.
+    pub fn configure(
+        &mut self,
+        registry: &Registry<Db, Ctx>,
+        config: &str,
+    ) -> Result<&mut Self, ConfigError> {
+        let config = parse_config(config)?;
+        for (name, raw) in &config.inputs {
+            let add = registry
+                .inputs
+                .get(name)
+                .ok_or_else(|| ConfigError::UnknownInput(name.clone()))?;
+            add(self, raw).map_err(|message| ConfigError::InvalidInput {
+                name: name.clone(),
+                message,
+            })?;
+        }
+        for name in &config.tasks {
+            let add = registry
+                .tasks
+                .get(name)
+                .ok_or_else(|| ConfigError::UnknownTask(name.clone()))?;
+            add(self)?;
+        }
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemoryDb, Task, TaskInput, TaskOutput};
+
+    struct Width;
+
+    impl DbKey for Width {
+        type Value = u32;
+    }
+
+    struct Title;
+
+    impl DbKey for Title {
+        type Value = String;
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Area(u32);
+
+    impl DbKey for Area {
+        type Value = Area;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for Area {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<Area>(Area(self.0));
+        }
+    }
+
+    struct Square(u32);
+
+    impl DbKey for Square {
+        type Value = Square;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for Square {
+        fn from_db(db: &Db) -> Self {
+            Square(*db.get::<Width>().unwrap())
+        }
+    }
+
+    struct ComputeArea;
+
+    impl Task<InMemoryDb> for ComputeArea {
+        type Input = Square;
+        type Output = Area;
+
+        fn execute(input: Self::Input) -> Self::Output {
+            Area(input.0 * input.0)
+        }
+    }
+
+    fn registry() -> Registry<InMemoryDb> {
+        let mut registry = Registry::new();
+        registry
+            .register_input::<Width>("Width")
+            .register_input::<Title>("Title")
+            .register_task::<ComputeArea>("ComputeArea");
+        registry
+    }
+
+    #[test]
+    fn test_from_config() {
+        let config = r#"
+            tasks = [
+                "ComputeArea", # the whole pipeline
+            ]
+
+            [inputs]
+            Width = 12
+            Title = "a # \"b, c\"\n"
+        "#;
+        let builder =
+            ExecutionGraphBuilder::from_config(InMemoryDb::new(), &registry(), config).unwrap();
+        let mut graph = builder.build();
+        assert_eq!(graph.execute::<ComputeArea>().unwrap(), Area(144));
+        assert_eq!(graph.db().get::<Title>().unwrap(), "a # \"b, c\"\n");
+    }
+
+    #[test]
+    fn test_from_config_errors() {
+        let err = |config: &str| {
+            ExecutionGraphBuilder::from_config(InMemoryDb::new(), &registry(), config)
+                .err()
+                .unwrap()
+        };
+        assert_eq!(
+            err("tasks = [\"Nope\"]"),
+            ConfigError::UnknownTask("Nope".to_string())
+        );
+        assert!(matches!(
+            err("[inputs]\nWidth = wide"),
+            ConfigError::InvalidInput { .. }
+        ));
+        assert!(matches!(
+            err("tasks = 3"),
+            ConfigError::Syntax { line: 1, .. }
+        ));
+        assert!(matches!(
+            err("\ntasks = [\n\"ComputeArea\","),
+            ConfigError::Syntax { line: 2, .. }
+        ));
+        assert!(matches!(
+            err("[inputs]\nTitle = \"a\\q\""),
+            ConfigError::Syntax { line: 2, .. }
+        ));
+    }
+}
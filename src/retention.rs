@@ -0,0 +1,243 @@
+use std::{any::TypeId, mem};
+
+use crate::{
+    DataBase, DbKey, ExecutionError, ExecutionGraph, ExecutionGraphBuilder, KeyType, TaskInput,
+    TaskWithContext,
+};
+
+/// Whether the graph may drop the value of a key, set with
+/// [`ExecutionGraphBuilder::retain`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum Retention {
+    /// Kept until overwritten or invalidated.
+    #[default]
+    Pinned,
+    /// Dropped by [`ExecutionGraph::evict`], and recomputed by the task
+    /// writing it when a task reading it is next executed.
+    Evictable,
+}
+
+pub(crate) struct Evictable<Db> {
+    pub(crate) key: KeyType,
+    pub(crate) remove: fn(&mut Db) -> bool,
+    /// Shallow size of the stored value, used when no estimate is registered
+    /// with [`ExecutionGraphBuilder::estimate_size`].
+    pub(crate) size: fn(&Db) -> Option<usize>,
+}
+
+impl<Db> Clone for Evictable<Db> {
+    fn clone(&self) -> Self {
+        Evictable {
+            key: self.key,
+            remove: self.remove,
+            size: self.size,
+        }
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraphBuilder<Db, Ctx> {
+    /// Sets the retention policy of `K`. Keys are pinned by default.
+    ///
+    /// Only values written by a task can be evicted: inputs are always
+    /// pinned, whatever their policy.
+    pub fn retain<K: DbKey>(&mut self, retention: Retention) -> &mut Self {
+        let id = TypeId::of::<K>();
+        self.graph.evictable.retain(|e| e.key.id != id);
+        if retention == Retention::Evictable {
+            self.graph.evictable.push(Evictable {
+                key: KeyType::of::<K>(),
+                remove: |db| db.remove::<K>().is_some(),
+                size: |db| db.get::<K>().map(mem::size_of_val),
+            });
+        }
+        self
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraph<Db, Ctx> {
+    /// Drops the value of every evictable key written by a task, e.g. under
+    /// memory pressure. Returns the keys whose value was dropped.
+    pub fn evict(&mut self) -> Vec<KeyType> {
+        self.with_shared_db(|graph| {
+            let mut evicted = Vec::new();
+            for i in 0..graph.evictable.len() {
+                let evictable = graph.evictable[i].clone();
+                if graph.writer_of(evictable.key).is_some() && graph.evict_value(&evictable) {
+                    evicted.push(evictable.key);
+                }
+            }
+            evicted
+        })
+    }
+
+    pub fn is_evicted<K: DbKey>(&self) -> bool {
+        self.evicted.contains(&TypeId::of::<K>())
+    }
+
+    /// Drops the value of `evictable`, if stored, and reports it to the
+    /// eviction observers.
+    pub(crate) fn evict_value(&mut self, evictable: &Evictable<Db>) -> bool {
+        let bytes = self.size_of(evictable);
+        if !(evictable.remove)(&mut self.db) {
+            return false;
+        }
+        self.evicted.insert(evictable.key.id);
+        self.notify_eviction(evictable.key, bytes.unwrap_or(0));
+        true
+    }
+
+    pub(crate) fn writer_of(&self, key: KeyType) -> Option<usize> {
+        self.entries
+            .iter()
+            .position(|entry| entry.writes.contains(&key))
+    }
+
+    /// Runs the writers of the evicted keys `T` reads, so that they are
+    /// stored again before `T` runs.
+    pub(crate) fn restore_evicted<T: TaskWithContext<Db, Ctx>>(
+        &mut self,
+    ) -> Result<(), ExecutionError> {
+        for key in T::Input::dep_types() {
+            if !self.evicted.contains(&key.id) {
+                continue;
+            }
+            if let Some(i) = self.writer_of(key) {
+                let (run, id) = (self.entries[i].run, self.entries[i].id);
+                run(self, id)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn clear_evicted<T: TaskWithContext<Db, Ctx>>(&mut self) {
+        for id in Self::written::<T>() {
+            self.evicted.remove(&id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::{InMemoryDb, Task, TaskOutput};
+
+    static PARSES: AtomicUsize = AtomicUsize::new(0);
+
+    struct Source;
+
+    impl DbKey for Source {
+        type Value = String;
+    }
+
+    struct Words(Vec<String>);
+
+    impl DbKey for Words {
+        type Value = Words;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for Words {
+        fn from_db(db: &Db) -> Self {
+            Words(
+                db.get::<Source>()
+                    .unwrap()
+                    .split_whitespace()
+                    .map(str::to_string)
+                    .collect(),
+            )
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Source>()]
+        }
+    }
+
+    struct Parsed;
+
+    impl DbKey for Parsed {
+        type Value = Vec<String>;
+    }
+
+    struct ParsedOut(Vec<String>);
+
+    impl DbKey for ParsedOut {
+        type Value = ParsedOut;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for ParsedOut {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<Parsed>(self.0.clone());
+        }
+
+        fn out_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Parsed>()]
+        }
+    }
+
+    struct Parse;
+
+    impl Task<InMemoryDb> for Parse {
+        type Input = Words;
+        type Output = ParsedOut;
+
+        fn execute(input: Self::Input) -> Self::Output {
+            PARSES.fetch_add(1, Ordering::Relaxed);
+            ParsedOut(input.0)
+        }
+    }
+
+    struct CountIn(usize);
+
+    impl DbKey for CountIn {
+        type Value = CountIn;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for CountIn {
+        fn from_db(db: &Db) -> Self {
+            CountIn(db.get::<Parsed>().unwrap().len())
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Parsed>()]
+        }
+    }
+
+    struct Count;
+
+    impl Task<InMemoryDb> for Count {
+        type Input = CountIn;
+        type Output = ();
+
+        fn execute(input: Self::Input) -> Self::Output {
+            assert_eq!(input.0, 3);
+        }
+    }
+
+    #[test]
+    fn test_evicted_values_are_recomputed() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_input::<Source>("a b c".to_string())
+            .unwrap()
+            .add_task::<Parse>()
+            .unwrap()
+            .add_task::<Count>()
+            .unwrap()
+            .retain::<Source>(Retention::Evictable)
+            .retain::<Parsed>(Retention::Evictable);
+        let mut graph = builder.build();
+        graph.execute_all().unwrap();
+        assert_eq!(PARSES.swap(0, Ordering::Relaxed), 1);
+
+        assert_eq!(graph.evict(), vec![KeyType::of::<Parsed>()]);
+        assert!(graph.is_evicted::<Parsed>());
+        assert!(graph.db().get::<Parsed>().is_none());
+        assert!(graph.db().get::<Source>().is_some());
+
+        graph.execute::<Count>().unwrap();
+        assert_eq!(PARSES.load(Ordering::Relaxed), 1);
+        assert!(!graph.is_evicted::<Parsed>());
+        assert_eq!(graph.db().get::<Parsed>().map(Vec::len), Some(3));
+    }
+}
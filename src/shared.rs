@@ -0,0 +1,292 @@
+use std::{
+    any::{type_name, Any, TypeId},
+    collections::HashMap,
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use crate::{DataBase, DbKey, ExecutionGraph, ExecutionGraphBuilder, KeyType};
+
+/// A database several graphs can be built over, e.g. an "ingest" graph
+/// writing values that a "serve" graph reads. The database must be
+/// [`Send`], like [`SyncDb`], so the handle can be used from other threads.
+///
+/// A graph holds the lock for the whole of [`ExecutionGraph::execute`] or
+/// [`ExecutionGraph::execute_all`], so runs of different graphs never
+/// interleave: one graph's run waits until the other's is done, even on
+/// different threads. Outside of a run, [`ExecutionGraph::db`] of a shared
+/// graph is an empty placeholder; use [`ExecutionGraph::shared_db`] or lock
+/// the shared handle to look at values.
+pub type SharedDb<Db> = Arc<Mutex<Db>>;
+
+type IntoSend = fn(Box<dyn Any>) -> Box<dyn Any + Send>;
+
+/// An in-memory database that can be sent to other threads, for sharing
+/// through a [`SharedDb`]. Only keys allowed with [`SyncDb::allow`], whose
+/// values are [`Send`], can be written; writing any other key panics.
+#[derive(Default)]
+pub struct SyncDb {
+    data: HashMap<TypeId, Box<dyn Any + Send>>,
+    allowed: HashMap<TypeId, IntoSend>,
+}
+
+impl SyncDb {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allows writing `K`.
+    pub fn allow<K: DbKey>(mut self) -> Self
+    where
+        K::Value: Send,
+    {
+        self.allowed.insert(TypeId::of::<K>(), |value| {
+            Box::new(*value.downcast::<K::Value>().unwrap())
+        });
+        self
+    }
+}
+
+impl DataBase for SyncDb {
+    fn get<K: DbKey>(&self) -> Option<&K::Value> {
+        self.data.get(&TypeId::of::<K>())?.downcast_ref()
+    }
+
+    fn put<K: DbKey>(&mut self, value: K::Value) -> Option<K::Value> {
+        let Some(into_send) = self.allowed.get(&TypeId::of::<K>()) else {
+            panic!("`{}` was not allowed in this SyncDb", type_name::<K>());
+        };
+        let previous = self
+            .data
+            .insert(TypeId::of::<K>(), into_send(Box::new(value)))?;
+        previous.downcast().ok().map(|value| *value)
+    }
+
+    fn remove<K: DbKey>(&mut self) -> Option<K::Value> {
+        let previous = self.data.remove(&TypeId::of::<K>())?;
+        previous.downcast().ok().map(|value| *value)
+    }
+
+    fn remove_key(&mut self, key: KeyType) -> Option<Box<dyn Any>> {
+        self.data.remove(&key.id).map(|value| value as Box<dyn Any>)
+    }
+
+    fn contains(&self, key: KeyType) -> Option<bool> {
+        Some(self.data.contains_key(&key.id))
+    }
+}
+
+impl<Db: DataBase + Default + Send> ExecutionGraph<Db> {
+    pub fn new_shared(db: SharedDb<Db>) -> Self {
+        ExecutionGraph::with_shared_context(db, ())
+    }
+}
+
+impl<Db: DataBase + Default + Send, Ctx> ExecutionGraph<Db, Ctx> {
+    pub fn with_shared_context(db: SharedDb<Db>, ctx: Ctx) -> Self {
+        let mut graph = ExecutionGraph::with_context(Db::default(), ctx);
+        graph.shared = Some(db);
+        graph
+    }
+}
+
+impl<Db: DataBase + Default + Send> ExecutionGraphBuilder<Db> {
+    pub fn new_shared(db: SharedDb<Db>) -> Self {
+        ExecutionGraphBuilder::with_shared_context(db, ())
+    }
+}
+
+impl<Db: DataBase + Default + Send, Ctx> ExecutionGraphBuilder<Db, Ctx> {
+    pub fn with_shared_context(db: SharedDb<Db>, ctx: Ctx) -> Self {
+        ExecutionGraphBuilder {
+            graph: ExecutionGraph::with_shared_context(db, ctx),
+        }
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraph<Db, Ctx> {
+    /// Runs `f` with the shared database, if any, moved into `self.db`.
+    /// Nested calls see no shared database and run `f` directly.
+    ///
+    /// The shared database is put back even if `f` panics.
+    pub(crate) fn with_shared_db<R>(&mut self, f: impl FnOnce(&mut Self) -> R) -> R {
+        let Some(shared) = self.shared.take() else {
+            return f(self);
+        };
+        let mut guard = shared.lock().unwrap_or_else(PoisonError::into_inner);
+        std::mem::swap(&mut self.db, &mut *guard);
+        let result = panic::catch_unwind(AssertUnwindSafe(|| f(self)));
+        std::mem::swap(&mut self.db, &mut *guard);
+        drop(guard);
+        self.shared = Some(shared);
+        result.unwrap_or_else(|payload| panic::resume_unwind(payload))
+    }
+
+    /// The shared database of a graph built over a [`SharedDb`], locked
+    /// until the guard is dropped; `None` for other graphs.
+    pub fn shared_db(&self) -> Option<MutexGuard<'_, Db>> {
+        let shared = self.shared.as_ref()?;
+        Some(shared.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    /// Runs `f` with a read-only view of the database, shared or not.
+    pub(crate) fn with_db<R>(&self, f: impl FnOnce(&Db) -> R) -> R {
+        match &self.shared {
+            Some(shared) => f(&shared.lock().unwrap_or_else(PoisonError::into_inner)),
+            None => f(&self.db),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Task, TaskInput, TaskOutput};
+
+    struct Events;
+
+    impl DbKey for Events {
+        type Value = Vec<String>;
+    }
+
+    struct Ingested(Vec<String>);
+
+    impl DbKey for Ingested {
+        type Value = Ingested;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for Ingested {
+        fn from_db(db: &Db) -> Self {
+            Ingested(db.get::<Events>().unwrap().clone())
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Events>()]
+        }
+    }
+
+    struct Index(Vec<String>);
+
+    impl DbKey for Index {
+        type Value = Index;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for Index {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<Index>(Index(self.0.clone()));
+        }
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for Index {
+        fn from_db(db: &Db) -> Self {
+            Index(db.get::<Index>().unwrap().0.clone())
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Index>()]
+        }
+    }
+
+    struct BuildIndex;
+
+    impl Task<SyncDb> for BuildIndex {
+        type Input = Ingested;
+        type Output = Index;
+
+        fn execute(mut input: Self::Input) -> Self::Output {
+            input.0.sort();
+            Index(input.0)
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Answer(Option<String>);
+
+    impl DbKey for Answer {
+        type Value = Answer;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for Answer {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<Answer>(Answer(self.0.clone()));
+        }
+    }
+
+    struct ServeFirst;
+
+    impl Task<SyncDb> for ServeFirst {
+        type Input = Index;
+        type Output = Answer;
+
+        fn execute(input: Self::Input) -> Self::Output {
+            Answer(input.0.first().cloned())
+        }
+    }
+
+    fn shared() -> SharedDb<SyncDb> {
+        let db = SyncDb::new()
+            .allow::<Events>()
+            .allow::<Index>()
+            .allow::<Answer>();
+        Arc::new(Mutex::new(db))
+    }
+
+    #[test]
+    fn test_graphs_share_database() {
+        let db = shared();
+
+        let mut ingest = ExecutionGraphBuilder::new_shared(db.clone());
+        ingest
+            .add_input::<Events>(vec!["b".to_string(), "a".to_string()])
+            .unwrap()
+            .add_task::<BuildIndex>()
+            .unwrap();
+        let mut ingest = ingest.build();
+
+        let mut serve = ExecutionGraphBuilder::new_shared(db.clone());
+        serve
+            .declare_input::<Index>()
+            .add_task::<ServeFirst>()
+            .unwrap();
+        let mut serve = serve.build();
+
+        ingest.execute_all().unwrap();
+        assert_eq!(
+            serve.execute::<ServeFirst>().unwrap(),
+            Answer(Some("a".to_string()))
+        );
+        assert_eq!(serve.db().get::<Answer>(), None);
+        assert_eq!(
+            serve.shared_db().unwrap().get::<Answer>(),
+            Some(&Answer(Some("a".to_string())))
+        );
+    }
+
+    struct Crash;
+
+    impl Task<SyncDb> for Crash {
+        type Input = Ingested;
+        type Output = ();
+
+        fn execute(_input: Self::Input) -> Self::Output {
+            panic!("crashed");
+        }
+    }
+
+    #[test]
+    fn test_shared_database_survives_panic() {
+        let db = shared();
+        let mut builder = ExecutionGraphBuilder::new_shared(db.clone());
+        builder
+            .add_input::<Events>(vec!["a".to_string()])
+            .unwrap()
+            .add_task::<Crash>()
+            .unwrap();
+        let mut graph = builder.build();
+        let result = panic::catch_unwind(AssertUnwindSafe(|| graph.execute_all()));
+        assert!(result.is_err());
+
+        let reader = std::thread::spawn(move || db.lock().unwrap().get::<Events>().cloned());
+        assert_eq!(reader.join().unwrap(), Some(vec!["a".to_string()]));
+    }
+}
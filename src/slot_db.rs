@@ -0,0 +1,287 @@
+use std::{
+    any::{type_name, Any, TypeId},
+    collections::HashMap,
+    hash::{BuildHasherDefault, Hash, Hasher},
+    marker::PhantomData,
+};
+
+use crate::{DataBase, DbKey, KeyType, TypeMismatch};
+
+/// Hashes a [`TypeId`], which is already a hash, by keeping its bits instead
+/// of running them through SipHash.
+#[derive(Default)]
+struct TypeIdHasher(u64);
+
+impl Hasher for TypeIdHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = self.0.rotate_left(8) ^ u64::from(byte);
+        }
+    }
+
+    fn write_u64(&mut self, n: u64) {
+        self.0 ^= n;
+    }
+}
+
+fn hash_of(id: TypeId) -> u64 {
+    let mut hasher = TypeIdHasher::default();
+    id.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// The position of `K` in a [`SlotDb`], for reading and writing it without
+/// looking the key up.
+pub struct Slot<K>(usize, PhantomData<fn() -> K>);
+
+impl<K> Clone for Slot<K> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K> Copy for Slot<K> {}
+
+/// A [`DataBase`] keeping its values in one contiguous vector, one slot per
+/// key, with a cheap key-to-slot index. Hot paths can resolve a [`Slot`]
+/// once and skip the lookup entirely. Unlike [`InMemoryDb`](crate::InMemoryDb),
+/// it does not stage writes in transactions.
+///
+/// Once a graph over it is built, the keys its tasks read and write are
+/// found through a collision-free table instead of the index: one modulo and
+/// one comparison per lookup.
+#[derive(Default)]
+pub struct SlotDb {
+    index: HashMap<TypeId, usize, BuildHasherDefault<TypeIdHasher>>,
+    slots: Vec<Option<Box<dyn Any>>>,
+    /// The reserved keys and their slots, each at its hash modulo the length.
+    dense: Vec<Option<(TypeId, usize)>>,
+}
+
+impl SlotDb {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A database with room for `keys` keys before it reallocates.
+    pub fn with_capacity(keys: usize) -> Self {
+        SlotDb {
+            index: HashMap::with_capacity_and_hasher(keys, Default::default()),
+            slots: Vec::with_capacity(keys),
+            dense: Vec::new(),
+        }
+    }
+
+    fn slot_index(&self, id: TypeId) -> Option<usize> {
+        if !self.dense.is_empty() {
+            let at = (hash_of(id) % self.dense.len() as u64) as usize;
+            if let Some((reserved, slot)) = self.dense[at] {
+                if reserved == id {
+                    return Some(slot);
+                }
+            }
+        }
+        self.index.get(&id).copied()
+    }
+
+    /// The slot of `K`, reserving one if `K` has none yet.
+    pub fn slot<K: DbKey>(&mut self) -> Slot<K> {
+        Slot(self.reserve_slot(TypeId::of::<K>()), PhantomData)
+    }
+
+    fn reserve_slot(&mut self, id: TypeId) -> usize {
+        if let Some(slot) = self.slot_index(id) {
+            return slot;
+        }
+        self.slots.push(None);
+        self.index.insert(id, self.slots.len() - 1);
+        self.slots.len() - 1
+    }
+
+    /// Whether `K` is found through the collision-free table.
+    pub fn is_dense<K: DbKey>(&self) -> bool {
+        let id = TypeId::of::<K>();
+        self.dense
+            .iter()
+            .flatten()
+            .any(|&(reserved, _)| reserved == id)
+    }
+
+    pub fn get_slot<K: DbKey>(&self, slot: Slot<K>) -> Option<&K::Value> {
+        self.slots[slot.0].as_ref()?.downcast_ref()
+    }
+
+    pub fn put_slot<K: DbKey>(&mut self, slot: Slot<K>, value: K::Value) -> Option<K::Value> {
+        let previous = self.slots[slot.0].replace(Box::new(value))?;
+        previous.downcast().ok().map(|value| *value)
+    }
+
+    pub fn remove_slot<K: DbKey>(&mut self, slot: Slot<K>) -> Option<K::Value> {
+        let previous = self.slots[slot.0].take()?;
+        previous.downcast().ok().map(|value| *value)
+    }
+}
+
+impl DataBase for SlotDb {
+    fn get<K: DbKey>(&self) -> Option<&K::Value> {
+        let index = self.slot_index(TypeId::of::<K>())?;
+        self.slots[index].as_ref()?.downcast_ref()
+    }
+
+    /// Stored type names are not kept, so mismatches report none.
+    fn try_get<K: DbKey>(&self) -> Result<Option<&K::Value>, TypeMismatch> {
+        let value = self
+            .slot_index(TypeId::of::<K>())
+            .and_then(|index| self.slots[index].as_ref());
+        let Some(value) = value else {
+            return Ok(None);
+        };
+        value.downcast_ref().map(Some).ok_or(TypeMismatch {
+            key: type_name::<K>(),
+            expected: type_name::<K::Value>(),
+            stored: None,
+        })
+    }
+
+    fn put<K: DbKey>(&mut self, value: K::Value) -> Option<K::Value> {
+        let slot = self.slot::<K>();
+        self.put_slot(slot, value)
+    }
+
+    fn remove<K: DbKey>(&mut self) -> Option<K::Value> {
+        let index = self.slot_index(TypeId::of::<K>())?;
+        self.remove_slot(Slot::<K>(index, PhantomData))
+    }
+
+    fn remove_key(&mut self, key: KeyType) -> Option<Box<dyn Any>> {
+        let index = self.slot_index(key.id)?;
+        self.slots[index].take()
+    }
+
+    fn contains(&self, key: KeyType) -> Option<bool> {
+        let index = self.slot_index(key.id);
+        Some(index.is_some_and(|index| self.slots[index].is_some()))
+    }
+
+    /// Gives every key a slot and finds the smallest table, up to eight
+    /// entries per key, in which no two of them collide. Keys outside it
+    /// stay in the index.
+    fn reserve(&mut self, keys: &[KeyType]) {
+        let mut reserved: Vec<(TypeId, usize)> = Vec::new();
+        for key in keys {
+            if reserved.iter().all(|&(id, _)| id != key.id) {
+                reserved.push((key.id, self.reserve_slot(key.id)));
+            }
+        }
+        let len = reserved.len();
+        self.dense = (len.max(1)..=len * 8)
+            .find_map(|size| {
+                let mut table = vec![None; size];
+                for &(id, slot) in &reserved {
+                    let at = &mut table[(hash_of(id) % size as u64) as usize];
+                    if at.is_some() {
+                        return None;
+                    }
+                    *at = Some((id, slot));
+                }
+                Some(table)
+            })
+            .unwrap_or_default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExecutionGraphBuilder, KeyType, Task, TaskInput, TaskOutput};
+
+    struct Count;
+
+    impl DbKey for Count {
+        type Value = u32;
+    }
+
+    struct Name;
+
+    impl DbKey for Name {
+        type Value = String;
+    }
+
+    #[test]
+    fn test_slot_db() {
+        let mut db = SlotDb::with_capacity(2);
+        assert_eq!(db.put::<Count>(1), None);
+        assert_eq!(db.put::<Count>(2), Some(1));
+        assert_eq!(db.get::<Name>(), None);
+
+        let slot = db.slot::<Name>();
+        db.put_slot(slot, "a".to_string());
+        assert_eq!(db.get::<Name>().unwrap(), "a");
+        assert_eq!(db.remove::<Name>().unwrap(), "a");
+        assert_eq!(db.get_slot(slot), None);
+        let count = db.slot::<Count>();
+        assert_eq!(db.get_slot(count), Some(&2));
+    }
+
+    struct CountIn(u32);
+
+    impl DbKey for CountIn {
+        type Value = CountIn;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for CountIn {
+        fn from_db(db: &Db) -> Self {
+            CountIn(*db.get::<Count>().unwrap())
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Count>()]
+        }
+    }
+
+    struct NameOut(String);
+
+    impl DbKey for NameOut {
+        type Value = NameOut;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for NameOut {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<Name>(self.0.clone());
+        }
+
+        fn out_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Name>()]
+        }
+    }
+
+    struct Describe;
+
+    impl Task<SlotDb> for Describe {
+        type Input = CountIn;
+        type Output = NameOut;
+
+        fn execute(input: Self::Input) -> Self::Output {
+            NameOut(format!("{} items", input.0))
+        }
+    }
+
+    #[test]
+    fn test_graph_on_slot_db() {
+        let mut builder = ExecutionGraphBuilder::new(SlotDb::new());
+        builder
+            .add_input::<Count>(3)
+            .unwrap()
+            .add_task::<Describe>()
+            .unwrap();
+        let mut graph = builder.build();
+        assert!(graph.db().is_dense::<Count>());
+        assert!(graph.db().is_dense::<Name>());
+        graph.execute_all().unwrap();
+        assert_eq!(graph.db().get::<Name>().unwrap(), "3 items");
+    }
+}
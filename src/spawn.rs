@@ -0,0 +1,269 @@
+use std::{
+    any::{Any, TypeId},
+    collections::{HashMap, HashSet, VecDeque},
+    hash::Hash,
+    marker::PhantomData,
+};
+
+use crate::{
+    DataBase, DbKey, ExecutionGraph, ExecutionGraphBuilder, KeyType, MissingDependency, TaskContext,
+};
+
+/// A task instance spawned while another task runs, with
+/// [`TaskContext::spawn`] or [`Spawner::spawn`]. Each instance is identified
+/// by its `Params`; its output is stored under [`Spawned<Self>`].
+///
+/// Added with [`ExecutionGraphBuilder::add_sub_task`].
+pub trait SubTask: 'static {
+    type Params: Eq + Hash + 'static;
+    type Output: 'static;
+
+    fn execute(params: &Self::Params, spawner: &mut Spawner) -> Self::Output;
+}
+
+/// The outputs of the instances of `T` spawned by the last task that spawned
+/// any, by their parameters.
+pub struct Spawned<T>(PhantomData<fn() -> T>);
+
+impl<T: SubTask> DbKey for Spawned<T> {
+    type Value = HashMap<T::Params, T::Output>;
+}
+
+struct Slot<T: SubTask> {
+    queued: HashSet<T::Params>,
+    done: HashMap<T::Params, T::Output>,
+}
+
+/// Writes the outputs of one sub-task's instances to the database.
+pub(crate) type WriteSpawned<Db> = fn(Box<dyn Any>, &mut Db);
+
+struct Job {
+    params: Box<dyn Any>,
+    run: fn(Box<dyn Any>, &mut Spawner),
+}
+
+/// The queue of sub-tasks spawned during a task's execution.
+#[derive(Default)]
+pub struct Spawner {
+    pending: VecDeque<Job>,
+    /// Each spawned sub-task's instances, with the key they are stored under.
+    slots: HashMap<TypeId, (KeyType, Box<dyn Any>)>,
+}
+
+impl Spawner {
+    /// Schedules `T` with `params` to run once the current task returns. An
+    /// instance already spawned with equal parameters is not run again.
+    pub fn spawn<T: SubTask>(&mut self, params: T::Params)
+    where
+        T::Params: Clone,
+    {
+        if self.slot::<T>().queued.insert(params.clone()) {
+            self.pending.push_back(Job {
+                params: Box::new(params),
+                run: run_job::<T>,
+            });
+        }
+    }
+
+    fn slot<T: SubTask>(&mut self) -> &mut Slot<T> {
+        self.slots
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| {
+                let slot = Slot::<T> {
+                    queued: HashSet::new(),
+                    done: HashMap::new(),
+                };
+                (KeyType::of::<Spawned<T>>(), Box::new(slot))
+            })
+            .1
+            .downcast_mut::<Slot<T>>()
+            .unwrap()
+    }
+
+    /// Runs every spawned instance, including those spawned by other
+    /// instances.
+    fn drain(&mut self) {
+        while let Some(job) = self.pending.pop_front() {
+            (job.run)(job.params, self);
+        }
+    }
+}
+
+fn run_job<T: SubTask>(params: Box<dyn Any>, spawner: &mut Spawner) {
+    let params = *params.downcast::<T::Params>().unwrap();
+    let output = T::execute(&params, spawner);
+    spawner.slot::<T>().done.insert(params, output);
+}
+
+fn write_spawned<Db: DataBase, T: SubTask>(slot: Box<dyn Any>, db: &mut Db) {
+    let slot = slot.downcast::<Slot<T>>().unwrap();
+    db.put::<Spawned<T>>(slot.done);
+}
+
+impl<Ctx> TaskContext<'_, Ctx> {
+    /// Schedules the sub-task `T` with `params`, like [`Spawner::spawn`].
+    pub fn spawn<T: SubTask>(&mut self, params: T::Params)
+    where
+        T::Params: Clone,
+    {
+        self.spawner.spawn::<T>(params);
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraphBuilder<Db, Ctx> {
+    /// Allows tasks to spawn instances of `T`, and tasks added afterwards to
+    /// depend on [`Spawned<T>`].
+    pub fn add_sub_task<T: SubTask>(&mut self) -> &mut Self {
+        let key = KeyType::of::<Spawned<T>>();
+        if self.graph.contains_node(&key.id).is_none() {
+            self.graph.register(key);
+            self.graph.producers.insert(key.id, "add_sub_task");
+        }
+        self.graph.spawned.insert(key.id);
+        self.graph
+            .sub_tasks
+            .insert(TypeId::of::<T>(), write_spawned::<Db, T>);
+        self
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraph<Db, Ctx> {
+    /// Runs what `task` spawned, within the same run, and writes each
+    /// sub-task's outputs to the database. Fails without writing any if a
+    /// spawned sub-task was never added.
+    pub(crate) fn run_spawned(
+        &mut self,
+        task: &'static str,
+        mut spawner: Spawner,
+    ) -> Result<(), MissingDependency> {
+        spawner.drain();
+        for (id, (key, _)) in &spawner.slots {
+            if !self.sub_tasks.contains_key(id) {
+                return Err(self.missing_dependency(*key, task));
+            }
+        }
+        for (id, (_, slot)) in spawner.slots {
+            self.sub_tasks[&id](slot, &mut self.db);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::any::type_name;
+
+    use super::*;
+    use crate::{ExecutionError, InMemoryDb, TaskInput, TaskWithContext};
+
+    fn submodules(module: &str) -> &'static [&'static str] {
+        match module {
+            "crate" => &["crate::a", "crate::b"],
+            "crate::a" => &["crate::util"],
+            "crate::b" => &["crate::util"],
+            _ => &[],
+        }
+    }
+
+    struct Visit;
+
+    impl SubTask for Visit {
+        type Params = &'static str;
+        type Output = usize;
+
+        fn execute(params: &Self::Params, spawner: &mut Spawner) -> Self::Output {
+            let children = submodules(params);
+            for child in children {
+                spawner.spawn::<Visit>(child);
+            }
+            children.len()
+        }
+    }
+
+    struct Root;
+
+    impl DbKey for Root {
+        type Value = &'static str;
+    }
+
+    struct RootIn(&'static str);
+
+    impl DbKey for RootIn {
+        type Value = RootIn;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for RootIn {
+        fn from_db(db: &Db) -> Self {
+            RootIn(db.get::<Root>().unwrap())
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Root>()]
+        }
+    }
+
+    struct Crawl;
+
+    impl TaskWithContext<InMemoryDb, ()> for Crawl {
+        type Input = RootIn;
+        type Output = ();
+
+        fn execute(input: Self::Input, ctx: &mut TaskContext<'_, ()>) -> Self::Output {
+            ctx.spawn::<Visit>(input.0);
+        }
+    }
+
+    #[test]
+    fn test_spawned_sub_tasks() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_input::<Root>("crate")
+            .unwrap()
+            .add_sub_task::<Visit>()
+            .add_task::<Crawl>()
+            .unwrap();
+        let mut graph = builder.build();
+        graph.execute_all().unwrap();
+        let visited = graph.db().get::<Spawned<Visit>>().unwrap();
+        assert_eq!(visited.len(), 4);
+        assert_eq!(visited["crate"], 2);
+        assert_eq!(visited["crate::util"], 0);
+
+        graph.set_input::<Root>("crate::b").unwrap();
+        graph.execute_all().unwrap();
+        assert_eq!(graph.db().get::<Spawned<Visit>>().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_spawning_task_reruns_with_strict_writes() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_input::<Root>("crate")
+            .unwrap()
+            .add_sub_task::<Visit>()
+            .add_task::<Crawl>()
+            .unwrap();
+        let mut graph = builder.build();
+        graph.strict_writes(true);
+        graph.execute::<Crawl>().unwrap();
+        graph.execute::<Crawl>().unwrap();
+        assert_eq!(graph.db().get::<Spawned<Visit>>().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_unregistered_sub_task() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_input::<Root>("crate")
+            .unwrap()
+            .add_task::<Crawl>()
+            .unwrap();
+        let mut graph = builder.build();
+        let Err(ExecutionError::MissingDependency(err)) = graph.execute_all() else {
+            panic!("expected the sub-task to be missing")
+        };
+        assert_eq!(err.key, type_name::<Spawned<Visit>>());
+        assert_eq!(err.task, type_name::<Crawl>());
+        assert!(graph.db().get::<Spawned<Visit>>().is_none());
+    }
+}
@@ -0,0 +1,445 @@
+use std::{
+    any::{Any, TypeId},
+    cell::{OnceCell, RefCell},
+    collections::HashMap,
+    fs, io,
+    path::PathBuf,
+    process,
+    rc::Rc,
+    sync::atomic::{AtomicU64, Ordering},
+    thread,
+};
+
+use crate::{lazy::Lazy, DataBase, DbKey, InMemoryDb, KeyType};
+
+static INSTANCES: AtomicU64 = AtomicU64::new(0);
+
+type Encode = Box<dyn Fn(&dyn Any) -> Vec<u8>>;
+type Decode = Rc<dyn Fn(&[u8]) -> Result<Box<dyn Any>, String>>;
+
+struct Codec {
+    encode: Encode,
+    decode: Decode,
+}
+
+/// A file holding a spilled value, deleted once neither the database nor a
+/// [`Lazy`] handle to the value needs it.
+struct SpillFile {
+    path: PathBuf,
+}
+
+impl SpillFile {
+    fn read(&self) -> Result<Vec<u8>, String> {
+        fs::read(&self.path).map_err(|e| {
+            format!(
+                "failed to read spilled value from {}: {e}",
+                self.path.display()
+            )
+        })
+    }
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+struct Spilled {
+    file: Rc<SpillFile>,
+    loaded: OnceCell<Box<dyn Any>>,
+}
+
+/// A database that keeps values in memory, except for values of keys
+/// registered with [`SpillDb::spill`] whose encoding is larger than the
+/// threshold: those are written to a file and read back on `get`.
+///
+/// A spilled value read back, or prefetched, stays in memory until its key
+/// is written or removed, or until [`SpillDb::release`]. Files are deleted
+/// when their value is replaced or removed, and when the database is
+/// dropped, unless a handle from [`get_lazy`](DataBase::get_lazy) still
+/// refers to them.
+///
+/// A spilled value that cannot be read or decoded is reported missing by
+/// `get`, and the error kept for [`SpillDb::take_errors`].
+pub struct SpillDb {
+    memory: InMemoryDb,
+    spilled: HashMap<TypeId, Spilled>,
+    errors: RefCell<Vec<String>>,
+    codecs: HashMap<TypeId, Codec>,
+    threshold: usize,
+    dir: PathBuf,
+    instance: u64,
+    files: u64,
+    prefetch_threads: usize,
+}
+
+impl SpillDb {
+    /// Spills values whose encoding is larger than `threshold` bytes to the
+    /// system temporary directory.
+    pub fn new(threshold: usize) -> Self {
+        SpillDb {
+            memory: InMemoryDb::new(),
+            spilled: HashMap::new(),
+            errors: RefCell::new(Vec::new()),
+            codecs: HashMap::new(),
+            threshold,
+            dir: std::env::temp_dir(),
+            instance: INSTANCES.fetch_add(1, Ordering::Relaxed),
+            files: 0,
+            prefetch_threads: 4,
+        }
+    }
+
+    /// Spills to `dir` instead of the system temporary directory.
+    pub fn in_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.dir = dir.into();
+        self
+    }
+
+    /// Reads at most `threads` files at once when prefetching. Defaults to 4.
+    pub fn prefetch_threads(mut self, threads: usize) -> Self {
+        self.prefetch_threads = threads.max(1);
+        self
+    }
+
+    /// Lets values of `K` be spilled, using `encode` and `decode` to write
+    /// and read them back.
+    pub fn spill<K: DbKey>(
+        mut self,
+        encode: fn(&K::Value) -> Vec<u8>,
+        decode: fn(&[u8]) -> Result<K::Value, String>,
+    ) -> Self {
+        self.codecs.insert(
+            TypeId::of::<K>(),
+            Codec {
+                encode: Box::new(move |value| encode(value.downcast_ref::<K::Value>().unwrap())),
+                decode: Rc::new(move |bytes| Ok(Box::new(decode(bytes)?))),
+            },
+        );
+        self
+    }
+
+    /// Whether the value of `K` is currently stored in a file.
+    pub fn is_spilled<K: DbKey>(&self) -> bool {
+        self.spilled.contains_key(&TypeId::of::<K>())
+    }
+
+    /// The errors met reading spilled values back since the last call.
+    pub fn take_errors(&self) -> Vec<String> {
+        self.errors.take()
+    }
+
+    /// Reads the value of `id` back, or records why it cannot be.
+    fn load(&self, id: TypeId, spilled: &Spilled) -> Option<Box<dyn Any>> {
+        let loaded = spilled
+            .file
+            .read()
+            .and_then(|bytes| (self.codecs[&id].decode)(&bytes));
+        loaded.map_err(|e| self.errors.borrow_mut().push(e)).ok()
+    }
+
+    /// Drops the in-memory copies of spilled values read back so far.
+    pub fn release(&mut self) {
+        for spilled in self.spilled.values_mut() {
+            spilled.loaded.take();
+        }
+    }
+
+    fn take<K: DbKey>(&mut self) -> Option<K::Value> {
+        let value = self.take_key(KeyType::of::<K>())?;
+        value.downcast::<K::Value>().ok().map(|v| *v)
+    }
+
+    fn take_key(&mut self, key: KeyType) -> Option<Box<dyn Any>> {
+        let Some(mut spilled) = self.spilled.remove(&key.id) else {
+            return self.memory.remove_key(key);
+        };
+        match spilled.loaded.take() {
+            Some(value) => Some(value),
+            None => self.load(key.id, &spilled),
+        }
+    }
+}
+
+impl DataBase for SpillDb {
+    fn get<K: DbKey>(&self) -> Option<&K::Value> {
+        let id = TypeId::of::<K>();
+        let Some(spilled) = self.spilled.get(&id) else {
+            return self.memory.get::<K>();
+        };
+        if spilled.loaded.get().is_none() {
+            let _ = spilled.loaded.set(self.load(id, spilled)?);
+        }
+        spilled.loaded.get()?.downcast_ref::<K::Value>()
+    }
+
+    /// A spilled value not read back yet is read from its file when the
+    /// handle is first dereferenced. The handle keeps the file, even once
+    /// `K` is written or removed; an error reading it is reported by
+    /// [`Lazy::try_get`].
+    fn get_lazy<K: DbKey>(&self) -> Option<Lazy<K::Value>>
+    where
+        K::Value: Clone,
+    {
+        let id = TypeId::of::<K>();
+        let Some(spilled) = self.spilled.get(&id) else {
+            return self.memory.get_lazy::<K>();
+        };
+        if let Some(value) = spilled.loaded.get() {
+            return value.downcast_ref::<K::Value>().cloned().map(Lazy::ready);
+        }
+        let decode = self.codecs[&id].decode.clone();
+        let file = spilled.file.clone();
+        Some(Lazy::try_new(move || {
+            let value = decode(&file.read()?)?;
+            Ok(*value.downcast::<K::Value>().unwrap())
+        }))
+    }
+
+    fn put<K: DbKey>(&mut self, value: K::Value) -> Option<K::Value> {
+        let previous = self.take::<K>();
+        let id = TypeId::of::<K>();
+        if let Some(codec) = self.codecs.get(&id) {
+            let bytes = (codec.encode)(&value);
+            if bytes.len() > self.threshold {
+                self.files += 1;
+                let path = self.dir.join(format!(
+                    "cg-spill-{}-{}-{}",
+                    process::id(),
+                    self.instance,
+                    self.files
+                ));
+                // A value that cannot be written out is kept in memory.
+                if fs::write(&path, bytes).is_ok() {
+                    self.spilled.insert(
+                        id,
+                        Spilled {
+                            file: Rc::new(SpillFile { path }),
+                            loaded: OnceCell::new(),
+                        },
+                    );
+                    return previous;
+                }
+            }
+        }
+        self.memory.put::<K>(value);
+        previous
+    }
+
+    fn remove<K: DbKey>(&mut self) -> Option<K::Value> {
+        self.take::<K>()
+    }
+
+    fn contains(&self, key: KeyType) -> Option<bool> {
+        match self.spilled.contains_key(&key.id) {
+            true => Some(true),
+            false => self.memory.contains(key),
+        }
+    }
+
+    fn remove_key(&mut self, key: KeyType) -> Option<Box<dyn Any>> {
+        self.take_key(key)
+    }
+
+    /// Reads the files of the spilled values of `keys` concurrently, then
+    /// decodes them.
+    fn prefetch(&mut self, keys: &[KeyType]) {
+        let pending: Vec<(TypeId, PathBuf)> = keys
+            .iter()
+            .filter_map(|key| {
+                let spilled = self.spilled.get(&key.id)?;
+                match spilled.loaded.get() {
+                    Some(_) => None,
+                    None => Some((key.id, spilled.file.path.clone())),
+                }
+            })
+            .collect();
+        if pending.is_empty() {
+            return;
+        }
+        let per_thread = pending.len().div_ceil(self.prefetch_threads);
+        let read: Vec<(TypeId, io::Result<Vec<u8>>)> = thread::scope(|scope| {
+            let handles: Vec<_> = pending
+                .chunks(per_thread)
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        chunk
+                            .iter()
+                            .map(|(id, path)| (*id, fs::read(path)))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect()
+        });
+        for (id, bytes) in read {
+            // A value that cannot be read or decoded now is read again, and
+            // the error reported, on `get`.
+            if let Some(value) = bytes.ok().and_then(|b| (self.codecs[&id].decode)(&b).ok()) {
+                let _ = self.spilled[&id].loaded.set(value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExecutionGraphBuilder, Task, TaskInput, TaskOutput};
+
+    struct Image;
+
+    impl DbKey for Image {
+        type Value = Vec<u8>;
+    }
+
+    struct Thumbnail;
+
+    impl DbKey for Thumbnail {
+        type Value = Vec<u8>;
+    }
+
+    struct ImageIn(Vec<u8>);
+
+    impl DbKey for ImageIn {
+        type Value = ImageIn;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for ImageIn {
+        fn from_db(db: &Db) -> Self {
+            ImageIn(db.get::<Image>().unwrap().clone())
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Image>()]
+        }
+    }
+
+    struct ThumbnailOut(Vec<u8>);
+
+    impl DbKey for ThumbnailOut {
+        type Value = ThumbnailOut;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for ThumbnailOut {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<Thumbnail>(self.0.clone());
+        }
+
+        fn out_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Thumbnail>()]
+        }
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Vec<u8>, String> {
+        Ok(bytes.to_vec())
+    }
+
+    struct Shrink;
+
+    impl Task<SpillDb> for Shrink {
+        type Input = ImageIn;
+        type Output = ThumbnailOut;
+
+        fn execute(input: Self::Input) -> Self::Output {
+            ThumbnailOut(input.0.iter().step_by(100).copied().collect())
+        }
+    }
+
+    #[test]
+    fn test_large_values_are_spilled() {
+        let db = SpillDb::new(64)
+            .spill::<Image>(Vec::clone, decode)
+            .spill::<Thumbnail>(Vec::clone, decode);
+        let mut builder = ExecutionGraphBuilder::new(db);
+        builder
+            .add_input::<Image>(vec![7; 1000])
+            .unwrap()
+            .add_task::<Shrink>()
+            .unwrap();
+        let mut graph = builder.build();
+        graph.execute_all().unwrap();
+
+        let db = graph.db();
+        assert!(db.is_spilled::<Image>());
+        assert!(!db.is_spilled::<Thumbnail>());
+        assert_eq!(db.get::<Thumbnail>(), Some(&vec![7; 10]));
+        assert_eq!(db.get::<Image>().map(Vec::len), Some(1000));
+    }
+
+    #[test]
+    fn test_spill_files_are_removed() {
+        let mut db = SpillDb::new(0).spill::<Image>(Vec::clone, decode);
+        db.put::<Image>(vec![1, 2, 3]);
+        let path = db.spilled[&TypeId::of::<Image>()].file.path.clone();
+        assert!(path.exists());
+
+        assert_eq!(db.put::<Image>(vec![4]), Some(vec![1, 2, 3]));
+        assert!(!path.exists());
+        let path = db.spilled[&TypeId::of::<Image>()].file.path.clone();
+        drop(db);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_prefetch_loads_spilled_values() {
+        let mut db = SpillDb::new(0)
+            .prefetch_threads(2)
+            .spill::<Image>(Vec::clone, decode)
+            .spill::<Thumbnail>(Vec::clone, decode);
+        db.put::<Image>(vec![1; 10]);
+        db.put::<Thumbnail>(vec![2; 3]);
+        db.prefetch(&[KeyType::of::<Image>(), KeyType::of::<Thumbnail>()]);
+        assert!(db.spilled.values().all(|s| s.loaded.get().is_some()));
+
+        // Reads are served from memory, even once the files are gone.
+        for spilled in db.spilled.values() {
+            fs::remove_file(&spilled.file.path).unwrap();
+        }
+        assert_eq!(db.get::<Image>(), Some(&vec![1; 10]));
+        assert_eq!(db.get::<Thumbnail>(), Some(&vec![2; 3]));
+    }
+
+    #[test]
+    fn test_get_lazy_defers_reading() {
+        let mut db = SpillDb::new(0).spill::<Image>(Vec::clone, decode);
+        db.put::<Image>(vec![5; 100]);
+        let image = db.get_lazy::<Image>().unwrap();
+        assert!(!image.is_loaded());
+        assert_eq!(image.len(), 100);
+        assert!(db.spilled[&TypeId::of::<Image>()].loaded.get().is_none());
+    }
+
+    #[test]
+    fn test_lazy_handle_keeps_its_file() {
+        let mut db = SpillDb::new(0).spill::<Image>(Vec::clone, decode);
+        db.put::<Image>(vec![1; 10]);
+        let path = db.spilled[&TypeId::of::<Image>()].file.path.clone();
+        let image = db.get_lazy::<Image>().unwrap();
+
+        db.put::<Image>(vec![2; 10]);
+        assert!(path.exists());
+        assert_eq!(image.try_get(), Ok(&vec![1; 10]));
+        drop(image);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_unreadable_value_is_reported() {
+        let mut db = SpillDb::new(0).spill::<Image>(Vec::clone, decode);
+        db.put::<Image>(vec![1; 10]);
+        let image = db.get_lazy::<Image>().unwrap();
+        fs::remove_file(&db.spilled[&TypeId::of::<Image>()].file.path).unwrap();
+
+        assert_eq!(db.get::<Image>(), None);
+        let errors = db.take_errors();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("failed to read spilled value"));
+        assert!(image.try_get().is_err());
+        assert!(!image.is_loaded());
+    }
+}
@@ -0,0 +1,217 @@
+use petgraph::{
+    algo::{condensation, connected_components, toposort},
+    graph::DiGraph,
+    visit::Bfs,
+    Direction,
+};
+
+use crate::{DataBase, ExecutionGraph};
+
+/// Size and shape of a graph's value nodes, returned by
+/// [`ExecutionGraph::stats`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct GraphStats {
+    pub nodes: usize,
+    /// Dependency edges, plus one from each task's input node to its output
+    /// node.
+    pub edges: usize,
+    /// Number of nodes on the longest path.
+    pub depth: usize,
+    /// Largest number of nodes none of which reaches another (the maximum
+    /// antichain), i.e. how much of the graph could run side by side.
+    pub width: usize,
+    /// Nodes with no incoming edge.
+    pub inputs: usize,
+    /// Nodes with no outgoing edge.
+    pub outputs: usize,
+    /// Weakly connected components.
+    pub components: usize,
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraph<Db, Ctx> {
+    /// The graph's nodes and edges, plus an edge from each task's input node
+    /// to its output node, so that paths follow values through tasks.
+    pub(crate) fn flow_graph(&self) -> DiGraph<(), ()> {
+        let mut graph = self.tasks.map(|_, _| (), |_, _| ());
+        for entry in &self.entries {
+            graph.add_edge(entry.input_node, entry.output_node, ());
+        }
+        graph
+    }
+
+    /// Computes the graph's [`GraphStats`]. Finding the width takes time
+    /// cubic in the number of nodes in the worst case.
+    pub fn stats(&self) -> GraphStats {
+        let graph = self.flow_graph();
+        let count = |direction| {
+            graph
+                .node_indices()
+                .filter(|&n| graph.neighbors_directed(n, direction).next().is_none())
+                .count()
+        };
+        // Edges discovered at run time may close a cycle; a cycle counts as
+        // a single node for depth and width.
+        let dag = condensation(graph.clone(), true).map(|_, _| (), |_, _| ());
+        GraphStats {
+            nodes: graph.node_count(),
+            edges: graph.edge_count(),
+            depth: depth(&dag),
+            width: width(&dag),
+            inputs: count(Direction::Incoming),
+            outputs: count(Direction::Outgoing),
+            components: connected_components(&graph),
+        }
+    }
+}
+
+fn depth(dag: &DiGraph<(), ()>) -> usize {
+    let order = toposort(dag, None).expect("condensation is acyclic");
+    let mut longest = vec![0; dag.node_count()];
+    for n in order {
+        let here = longest[n.index()] + 1;
+        longest[n.index()] = here;
+        for next in dag.neighbors(n) {
+            longest[next.index()] = longest[next.index()].max(here);
+        }
+    }
+    longest.into_iter().max().unwrap_or(0)
+}
+
+/// By Dilworth's theorem, the maximum antichain is the node count minus a
+/// maximum matching between the nodes and those they reach.
+fn width(dag: &DiGraph<(), ()>) -> usize {
+    let reaches: Vec<Vec<usize>> = dag
+        .node_indices()
+        .map(|n| {
+            let mut reached = Vec::new();
+            let mut bfs = Bfs::new(dag, n);
+            while let Some(m) = bfs.next(dag) {
+                if m != n {
+                    reached.push(m.index());
+                }
+            }
+            reached
+        })
+        .collect();
+    let mut matched: Vec<Option<usize>> = vec![None; dag.node_count()];
+    let mut matching = 0;
+    for n in 0..dag.node_count() {
+        let mut seen = vec![false; dag.node_count()];
+        if augment(n, &reaches, &mut matched, &mut seen) {
+            matching += 1;
+        }
+    }
+    dag.node_count() - matching
+}
+
+fn augment(
+    n: usize,
+    reaches: &[Vec<usize>],
+    matched: &mut [Option<usize>],
+    seen: &mut [bool],
+) -> bool {
+    for &m in &reaches[n] {
+        if seen[m] {
+            continue;
+        }
+        seen[m] = true;
+        if matched[m].is_none_or(|other| augment(other, reaches, matched, seen)) {
+            matched[m] = Some(n);
+            return true;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DbKey, ExecutionGraphBuilder, InMemoryDb, KeyType, Task, TaskInput, TaskOutput};
+
+    struct Source;
+
+    impl DbKey for Source {
+        type Value = String;
+    }
+
+    struct Lines;
+
+    impl DbKey for Lines {
+        type Value = usize;
+    }
+
+    struct SourceIn(String);
+
+    impl DbKey for SourceIn {
+        type Value = SourceIn;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for SourceIn {
+        fn from_db(db: &Db) -> Self {
+            SourceIn(db.get::<Source>().unwrap().clone())
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Source>()]
+        }
+    }
+
+    struct LinesOut(usize);
+
+    impl DbKey for LinesOut {
+        type Value = LinesOut;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for LinesOut {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<Lines>(self.0);
+        }
+
+        fn out_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Lines>()]
+        }
+    }
+
+    struct CountLines;
+
+    impl Task<InMemoryDb> for CountLines {
+        type Input = SourceIn;
+        type Output = LinesOut;
+
+        fn execute(input: Self::Input) -> Self::Output {
+            LinesOut(input.0.lines().count())
+        }
+    }
+
+    struct Unrelated;
+
+    impl DbKey for Unrelated {
+        type Value = ();
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_input::<Source>(String::new())
+            .unwrap()
+            .add_input::<Unrelated>(())
+            .unwrap()
+            .add_task::<CountLines>()
+            .unwrap();
+        let stats = builder.build().stats();
+        // Source -> SourceIn -> LinesOut -> Lines, and Unrelated on its own.
+        assert_eq!(
+            stats,
+            GraphStats {
+                nodes: 5,
+                edges: 3,
+                depth: 4,
+                width: 2,
+                inputs: 2,
+                outputs: 2,
+                components: 2,
+            }
+        );
+    }
+}
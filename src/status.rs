@@ -0,0 +1,89 @@
+use std::{
+    any::TypeId,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{DataBase, ExecutionGraph, TaskWithContext};
+
+/// What happened to a task in the most recent
+/// [`execute_all`](ExecutionGraph::execute_all).
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TaskStatus {
+    /// Not run, but its output from an earlier run is still stored.
+    Cached,
+    /// Run successfully.
+    Recomputed,
+    /// Run, or refused to run, and failed.
+    Failed,
+    /// Not run, and no output is stored.
+    NeverRun,
+}
+
+pub(crate) struct TaskRecord {
+    pub(crate) run: u64,
+    pub(crate) ok: bool,
+    pub(crate) started: Instant,
+    pub(crate) duration: Duration,
+    pub(crate) thread: String,
+    /// The database's [revision](DataBase::revision) once the task finished.
+    pub(crate) revision: Option<u64>,
+}
+
+fn thread_label() -> String {
+    let thread = thread::current();
+    match thread.name() {
+        Some(name) => name.to_string(),
+        None => format!("{:?}", thread.id()),
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraph<Db, Ctx> {
+    pub(crate) fn record_run<T: TaskWithContext<Db, Ctx>>(&mut self, started: Instant, ok: bool) {
+        let duration = started.elapsed();
+        #[cfg(feature = "metrics")]
+        self.metrics.record_task(T::name(), duration, ok);
+        self.records.insert(
+            TypeId::of::<T>(),
+            TaskRecord {
+                run: self.run,
+                ok,
+                started,
+                duration,
+                thread: thread_label(),
+                revision: self.db.revision(),
+            },
+        );
+        if ok {
+            self.record_provenance::<T>();
+        }
+    }
+
+    pub(crate) fn status_of(&self, task: TypeId, has_output: bool) -> TaskStatus {
+        match self.records.get(&task) {
+            Some(record) if record.run == self.run => match record.ok {
+                true => TaskStatus::Recomputed,
+                false => TaskStatus::Failed,
+            },
+            _ if has_output => TaskStatus::Cached,
+            _ => TaskStatus::NeverRun,
+        }
+    }
+
+    pub fn task_status<T: TaskWithContext<Db, Ctx>>(&self) -> TaskStatus {
+        let id = TypeId::of::<T>();
+        let has_output = self
+            .entries
+            .iter()
+            .find(|e| e.id == id)
+            .is_some_and(|entry| self.with_db(|db| entry.output_stored(db)));
+        self.status_of(id, has_output)
+    }
+
+    /// How long the last execution of `T` took, whichever run it was in.
+    pub fn last_duration<T: TaskWithContext<Db, Ctx>>(&self) -> Option<Duration> {
+        self.records
+            .get(&TypeId::of::<T>())
+            .map(|record| record.duration)
+    }
+}
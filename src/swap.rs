@@ -0,0 +1,221 @@
+use std::{
+    any::{type_name, Any, TypeId},
+    marker::PhantomData,
+    rc::Rc,
+};
+
+use crate::{DataBase, ExecutionGraph, KeyType, TaskEditError, TaskInput, TaskWithContext};
+
+/// A type-erased task implementation, swapped in for a task of a running
+/// graph with [`ExecutionGraph::swap_task`].
+pub trait DynTask<Db> {
+    /// Computes the task's output from `db`. The returned value must be of
+    /// the swapped task's `Output` type.
+    fn execute(&self, db: &Db) -> Box<dyn Any>;
+
+    /// The type of the values `execute` returns.
+    fn output_type(&self) -> TypeId;
+
+    /// Keys read by the last `execute` beyond the task's declared
+    /// dependencies.
+    fn discovered_deps(&self) -> Vec<KeyType> {
+        Vec::new()
+    }
+}
+
+/// A [`DynTask`] made from a closure over the task's input.
+pub struct FnTask<I, O> {
+    f: Box<dyn Fn(I) -> O>,
+    _marker: PhantomData<fn(I) -> O>,
+}
+
+impl<I, O> FnTask<I, O> {
+    pub fn new(f: impl Fn(I) -> O + 'static) -> Self {
+        FnTask {
+            f: Box::new(f),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<Db: DataBase, I: TaskInput<Db>, O: 'static> DynTask<Db> for FnTask<I, O> {
+    fn execute(&self, db: &Db) -> Box<dyn Any> {
+        Box::new((self.f)(I::from_db(db)))
+    }
+
+    fn output_type(&self) -> TypeId {
+        TypeId::of::<O>()
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraph<Db, Ctx> {
+    /// Runs `new_impl` instead of `T`'s own `execute` from now on, and
+    /// invalidates `T`'s output and everything depending on it so the next
+    /// run recomputes them. Fails if `new_impl` does not return `T`'s
+    /// `Output`.
+    pub fn swap_task<T: TaskWithContext<Db, Ctx>>(
+        &mut self,
+        new_impl: Box<dyn DynTask<Db>>,
+    ) -> Result<&mut Self, TaskEditError> {
+        if new_impl.output_type() != TypeId::of::<T::Output>() {
+            return Err(TaskEditError::OutputMismatch {
+                task: type_name::<T>(),
+                expected: type_name::<T::Output>(),
+            });
+        }
+        self.invalidate_task::<T>()?;
+        self.swapped.insert(TypeId::of::<T>(), Rc::from(new_impl));
+        Ok(self)
+    }
+
+    /// Goes back to `T`'s own implementation after
+    /// [`swap_task`](Self::swap_task), invalidating its outputs again.
+    pub fn restore_task<T: TaskWithContext<Db, Ctx>>(
+        &mut self,
+    ) -> Result<&mut Self, TaskEditError> {
+        self.invalidate_task::<T>()?;
+        self.swapped.remove(&TypeId::of::<T>());
+        Ok(self)
+    }
+
+    fn invalidate_task<T: TaskWithContext<Db, Ctx>>(&mut self) -> Result<(), TaskEditError> {
+        let entry = self
+            .entries
+            .iter()
+            .find(|e| e.id == TypeId::of::<T>())
+            .ok_or(TaskEditError::UnknownTask {
+                task: type_name::<T>(),
+            })?;
+        let (invalidate, writes) = (entry.invalidate, entry.writes.clone());
+        self.with_shared_db(|graph| {
+            invalidate(&mut graph.db, &writes);
+            graph.invalidate_dependents(&writes);
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        DbKey, ExecutionError, ExecutionGraphBuilder, InMemoryDb, KeyType, OutputTypeMismatch,
+        Task, TaskOutput,
+    };
+
+    struct Price;
+
+    impl DbKey for Price {
+        type Value = u32;
+    }
+
+    struct PriceIn(u32);
+
+    impl DbKey for PriceIn {
+        type Value = PriceIn;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for PriceIn {
+        fn from_db(db: &Db) -> Self {
+            PriceIn(*db.get::<Price>().unwrap())
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Price>()]
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Tax(u32);
+
+    impl DbKey for Tax {
+        type Value = Tax;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for Tax {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<Tax>(Tax(self.0));
+        }
+    }
+
+    struct ComputeTax;
+
+    impl Task<InMemoryDb> for ComputeTax {
+        type Input = PriceIn;
+        type Output = Tax;
+
+        fn execute(input: Self::Input) -> Self::Output {
+            // Off by a factor of ten.
+            Tax(input.0 * 2)
+        }
+    }
+
+    #[test]
+    fn test_swap_task() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_input::<Price>(100)
+            .unwrap()
+            .add_task::<ComputeTax>()
+            .unwrap();
+        let mut graph = builder.build();
+        graph.execute_all().unwrap();
+        assert_eq!(graph.db().get::<Tax>(), Some(&Tax(200)));
+
+        graph
+            .swap_task::<ComputeTax>(Box::new(FnTask::new(|input: PriceIn| Tax(input.0 / 5))))
+            .unwrap();
+        assert_eq!(graph.db().get::<Tax>(), None);
+        graph.execute_all().unwrap();
+        assert_eq!(graph.db().get::<Tax>(), Some(&Tax(20)));
+
+        graph.restore_task::<ComputeTax>().unwrap();
+        graph.execute_all().unwrap();
+        assert_eq!(graph.db().get::<Tax>(), Some(&Tax(200)));
+
+        let wrong = graph.swap_task::<ComputeTax>(Box::new(FnTask::new(|input: PriceIn| input.0)));
+        assert_eq!(
+            wrong.err(),
+            Some(TaskEditError::OutputMismatch {
+                task: type_name::<ComputeTax>(),
+                expected: type_name::<Tax>(),
+            })
+        );
+        assert_eq!(graph.db().get::<Tax>(), Some(&Tax(200)));
+    }
+
+    /// Claims to return a [`Tax`], but returns the price.
+    struct Misdeclared;
+
+    impl DynTask<InMemoryDb> for Misdeclared {
+        fn execute(&self, db: &InMemoryDb) -> Box<dyn Any> {
+            Box::new(PriceIn::from_db(db).0)
+        }
+
+        fn output_type(&self) -> TypeId {
+            TypeId::of::<Tax>()
+        }
+    }
+
+    #[test]
+    fn test_swapped_output_type_mismatch() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_input::<Price>(100)
+            .unwrap()
+            .add_task::<ComputeTax>()
+            .unwrap();
+        let mut graph = builder.build();
+        graph
+            .swap_task::<ComputeTax>(Box::new(Misdeclared))
+            .unwrap();
+        assert_eq!(
+            graph.execute::<ComputeTax>().err(),
+            Some(ExecutionError::OutputTypeMismatch(OutputTypeMismatch {
+                task: type_name::<ComputeTax>(),
+                expected: type_name::<Tax>(),
+            }))
+        );
+        assert_eq!(graph.db().get::<Tax>(), None);
+    }
+}
@@ -0,0 +1,401 @@
+use std::{
+    any::{type_name, Any, TypeId},
+    cell::RefCell,
+    fmt::Debug,
+    fs,
+    path::Path,
+    rc::Rc,
+};
+
+use crate::{
+    DataBase, DbKey, ExecutionGraph, InMemoryDb, KeyType, TaskWithContext, ValueId, Write,
+};
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum AccessKind {
+    Get,
+    Put,
+    Remove,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct DbAccess {
+    pub kind: AccessKind,
+    pub key: ValueId,
+}
+
+/// A database that records every `get`/`put` it sees. Values set up with
+/// [`MockDb::stub`] are not recorded.
+pub struct MockDb {
+    data: InMemoryDb,
+    log: RefCell<Vec<DbAccess>>,
+}
+
+impl MockDb {
+    pub fn new() -> Self {
+        MockDb {
+            data: InMemoryDb::new(),
+            log: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub fn stub<K: DbKey>(&mut self, value: K::Value) -> &mut Self {
+        self.data.put::<K>(value);
+        self
+    }
+
+    pub fn accesses(&self) -> Vec<DbAccess> {
+        self.log.borrow().clone()
+    }
+
+    pub fn clear_accesses(&self) {
+        self.log.borrow_mut().clear();
+    }
+
+    pub fn was_read<K: DbKey>(&self) -> bool {
+        self.recorded::<K>(AccessKind::Get)
+    }
+
+    pub fn was_written<K: DbKey>(&self) -> bool {
+        self.recorded::<K>(AccessKind::Put)
+    }
+
+    fn recorded<K: DbKey>(&self, kind: AccessKind) -> bool {
+        self.log
+            .borrow()
+            .iter()
+            .any(|a| a.kind == kind && a.key == KeyType::of::<K>())
+    }
+
+    fn record<K: DbKey>(&self, kind: AccessKind) {
+        self.log.borrow_mut().push(DbAccess {
+            kind,
+            key: KeyType::of::<K>(),
+        });
+    }
+}
+
+impl Default for MockDb {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DataBase for MockDb {
+    fn get<K: DbKey>(&self) -> Option<&K::Value> {
+        self.record::<K>(AccessKind::Get);
+        self.data.get::<K>()
+    }
+
+    fn put<K: DbKey>(&mut self, value: K::Value) -> Option<K::Value> {
+        self.record::<K>(AccessKind::Put);
+        self.data.put::<K>(value)
+    }
+
+    fn remove<K: DbKey>(&mut self) -> Option<K::Value> {
+        self.record::<K>(AccessKind::Remove);
+        self.data.remove::<K>()
+    }
+
+    fn contains(&self, key: KeyType) -> Option<bool> {
+        self.data.contains(key)
+    }
+
+    fn remove_key(&mut self, key: KeyType) -> Option<Box<dyn Any>> {
+        self.log.borrow_mut().push(DbAccess {
+            kind: AccessKind::Remove,
+            key,
+        });
+        self.data.remove_key(key)
+    }
+
+    fn begin(&mut self) {
+        self.data.begin();
+    }
+
+    fn commit(&mut self) {
+        self.data.commit();
+    }
+
+    fn rollback(&mut self) {
+        self.data.rollback();
+    }
+
+    fn record_writes(&mut self) {
+        self.data.record_writes();
+    }
+
+    fn take_writes(&mut self) -> Option<Vec<Write>> {
+        self.data.take_writes()
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraph<Db, Ctx> {
+    /// Replaces the implementation of `T` with one that always returns
+    /// `output`, without reading its input from the database.
+    pub fn stub_task<T: TaskWithContext<Db, Ctx>>(&mut self, output: T::Output) -> &mut Self
+    where
+        T::Output: Clone,
+    {
+        self.stubs
+            .insert(TypeId::of::<T>(), Rc::new(move || Box::new(output.clone())));
+        self
+    }
+
+    pub fn unstub_task<T: TaskWithContext<Db, Ctx>>(&mut self) -> &mut Self {
+        self.stubs.remove(&TypeId::of::<T>());
+        self
+    }
+}
+
+/// Environment variable that, when set to anything but `0`, makes
+/// [`ExecutionGraph::assert_matches_golden`] rewrite golden files instead of
+/// comparing against them.
+pub const BLESS_ENV: &str = "CG_BLESS";
+
+type Serializer<Db> = Box<dyn Fn(&Db) -> Option<String>>;
+
+/// The set of keys captured by [`ExecutionGraph::capture_outputs`], each with
+/// the function used to turn its value into text.
+pub struct OutputCapture<Db: DataBase> {
+    entries: Vec<(&'static str, Serializer<Db>)>,
+}
+
+impl<Db: DataBase> OutputCapture<Db> {
+    pub fn new() -> Self {
+        OutputCapture {
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn key<K: DbKey>(mut self, serialize: fn(&K::Value) -> String) -> Self {
+        self.entries.push((
+            type_name::<K>(),
+            Box::new(move |db| db.get::<K>().map(serialize)),
+        ));
+        self
+    }
+
+    pub fn debug<K: DbKey>(self) -> Self
+    where
+        K::Value: Debug,
+    {
+        self.key::<K>(|v| format!("{v:#?}"))
+    }
+}
+
+impl<Db: DataBase> Default for OutputCapture<Db> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraph<Db, Ctx> {
+    pub fn capture_outputs(&self, capture: &OutputCapture<Db>) -> String {
+        let mut out = String::new();
+        for (name, serialize) in &capture.entries {
+            out.push_str("== ");
+            out.push_str(name);
+            out.push_str(" ==\n");
+            match self.with_db(serialize) {
+                Some(text) => out.push_str(&text),
+                None => out.push_str("<missing>"),
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    pub fn assert_matches_golden(&self, capture: &OutputCapture<Db>, path: impl AsRef<Path>) {
+        let bless = std::env::var(BLESS_ENV).is_ok_and(|v| v != "0");
+        self.check_golden(capture, path.as_ref(), bless);
+    }
+
+    fn check_golden(&self, capture: &OutputCapture<Db>, path: &Path, bless: bool) {
+        let actual = self.capture_outputs(capture);
+        if bless {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).expect("failed to create golden directory");
+            }
+            fs::write(path, actual).expect("failed to write golden file");
+            return;
+        }
+        let expected = match fs::read_to_string(path) {
+            Ok(expected) => expected,
+            Err(e) => panic!(
+                "Cannot read golden file {}: {e} (run with {BLESS_ENV}=1 to create it)",
+                path.display()
+            ),
+        };
+        if expected != actual {
+            panic!(
+                "Outputs do not match golden file {} (run with {BLESS_ENV}=1 to update it)\n\
+                 --- expected\n{expected}\n--- actual\n{actual}",
+                path.display()
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExecutionGraphBuilder, Task, TaskInput, TaskOutput};
+
+    #[derive(Copy, Clone, PartialEq, Debug)]
+    struct Raw(i32);
+
+    impl DbKey for Raw {
+        type Value = Raw;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for Raw {
+        fn from_db(db: &Db) -> Self {
+            db.get_cloned::<Raw>().unwrap()
+        }
+    }
+
+    #[derive(Copy, Clone, PartialEq, Debug)]
+    struct Doubled(i32);
+
+    impl DbKey for Doubled {
+        type Value = Doubled;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for Doubled {
+        fn from_db(db: &Db) -> Self {
+            db.get_cloned::<Doubled>().unwrap()
+        }
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for Doubled {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<Doubled>(*self);
+        }
+    }
+
+    #[derive(Copy, Clone, PartialEq, Debug)]
+    struct Incremented(i32);
+
+    impl DbKey for Incremented {
+        type Value = Incremented;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for Incremented {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<Incremented>(*self);
+        }
+    }
+
+    struct Double;
+
+    impl Task<MockDb> for Double {
+        type Input = Raw;
+        type Output = Doubled;
+
+        fn execute(input: Self::Input) -> Self::Output {
+            Doubled(input.0 * 2)
+        }
+    }
+
+    struct Increment;
+
+    impl Task<MockDb> for Increment {
+        type Input = Doubled;
+        type Output = Incremented;
+
+        fn execute(input: Self::Input) -> Self::Output {
+            Incremented(input.0 + 1)
+        }
+    }
+
+    #[test]
+    fn test_mock_db_records_accesses() {
+        let mut db = MockDb::new();
+        db.stub::<Raw>(Raw(1));
+        assert!(db.accesses().is_empty());
+
+        let mut graph = ExecutionGraphBuilder::new(db).build();
+        graph.execute::<Double>().unwrap();
+        assert!(graph.db().was_read::<Raw>());
+        assert!(graph.db().was_written::<Doubled>());
+        assert!(!graph.db().was_written::<Raw>());
+    }
+
+    #[test]
+    fn test_stubbed_task() {
+        let mut builder = ExecutionGraphBuilder::new(MockDb::new());
+        builder
+            .add_task::<Double>()
+            .unwrap()
+            .add_task::<Increment>()
+            .unwrap();
+        let mut graph = builder.build();
+        graph.stub_task::<Double>(Doubled(10));
+
+        assert_eq!(graph.execute::<Double>().unwrap(), Doubled(10));
+        assert_eq!(graph.execute::<Increment>().unwrap(), Incremented(11));
+        assert!(!graph.db().was_read::<Raw>());
+    }
+
+    #[test]
+    fn test_golden_outputs() {
+        let mut db = MockDb::new();
+        db.stub::<Raw>(Raw(4));
+        let mut graph = ExecutionGraphBuilder::new(db).build();
+        graph.execute::<Double>().unwrap();
+
+        let capture = OutputCapture::new()
+            .key::<Doubled>(|v| v.0.to_string())
+            .debug::<Incremented>();
+        let text = graph.capture_outputs(&capture);
+        assert!(text.contains("Doubled ==\n8\n"));
+        assert!(text.contains("Incremented ==\n<missing>\n"));
+
+        let path = std::env::temp_dir().join(format!("cg-golden-{}.txt", std::process::id()));
+        fs::write(&path, &text).unwrap();
+        graph.assert_matches_golden(&capture, &path);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "Outputs do not match golden file")]
+    fn test_golden_mismatch() {
+        let mut db = MockDb::new();
+        db.stub::<Raw>(Raw(4));
+        let mut graph = ExecutionGraphBuilder::new(db).build();
+        graph.execute::<Double>().unwrap();
+
+        let capture = OutputCapture::new().key::<Doubled>(|v| v.0.to_string());
+        let path = std::env::temp_dir().join(format!("cg-mismatch-{}.txt", std::process::id()));
+        fs::write(&path, "== stale ==\n7\n").unwrap();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            graph.assert_matches_golden(&capture, &path)
+        }));
+        fs::remove_file(&path).unwrap();
+        std::panic::resume_unwind(result.unwrap_err());
+    }
+
+    #[test]
+    fn test_golden_bless() {
+        let mut db = MockDb::new();
+        db.stub::<Raw>(Raw(4));
+        let mut graph = ExecutionGraphBuilder::new(db).build();
+        graph.execute::<Double>().unwrap();
+
+        let capture = OutputCapture::new().key::<Doubled>(|v| v.0.to_string());
+        let dir = std::env::temp_dir().join(format!("cg-bless-{}", std::process::id()));
+        let path = dir.join("outputs.txt");
+        graph.check_golden(&capture, &path, true);
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            graph.capture_outputs(&capture)
+        );
+
+        // Blessing again overwrites a stale file, which then matches.
+        fs::write(&path, "stale").unwrap();
+        graph.check_golden(&capture, &path, true);
+        graph.check_golden(&capture, &path, false);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,95 @@
+use std::{collections::BTreeMap, fmt::Write as _};
+
+use crate::{json::push_json_str, DataBase, ExecutionGraph};
+
+impl<Db: DataBase, Ctx> ExecutionGraph<Db, Ctx> {
+    /// The tasks of the last [`execute_all`](Self::execute_all) as a Plotly
+    /// figure: one horizontal bar trace per thread, each bar spanning a
+    /// task's start and end in milliseconds since the start of the run. The
+    /// result can be passed as is to `Plotly.newPlot`.
+    pub fn timeline_json(&self) -> String {
+        let mut threads: BTreeMap<&str, Vec<(&str, f64, f64, bool)>> = BTreeMap::new();
+        if let Some(run_started) = self.run_started {
+            for entry in &self.entries {
+                let Some(record) = self.records.get(&entry.id) else {
+                    continue;
+                };
+                if record.run != self.run {
+                    continue;
+                }
+                let start = record.started.duration_since(run_started).as_secs_f64() * 1000.0;
+                let duration = record.duration.as_secs_f64() * 1000.0;
+                threads
+                    .entry(&record.thread)
+                    .or_default()
+                    .push((entry.name, start, duration, record.ok));
+            }
+        }
+
+        let mut out = String::from("{\"data\":[");
+        for (i, (thread, tasks)) in threads.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            out.push_str("{\"type\":\"bar\",\"orientation\":\"h\",\"name\":");
+            push_json_str(&mut out, thread);
+            let quoted = |s: &str| {
+                let mut quoted = String::new();
+                push_json_str(&mut quoted, s);
+                quoted
+            };
+            let fields: [(&str, Vec<String>); 4] = [
+                ("y", tasks.iter().map(|_| quoted(thread)).collect()),
+                (
+                    "base",
+                    tasks.iter().map(|t| format!("{:.3}", t.1)).collect(),
+                ),
+                ("x", tasks.iter().map(|t| format!("{:.3}", t.2)).collect()),
+                ("text", tasks.iter().map(|t| quoted(t.0)).collect()),
+            ];
+            for (key, values) in fields {
+                let _ = write!(out, ",\"{key}\":[{}]", values.join(","));
+            }
+            let colors: Vec<String> = tasks
+                .iter()
+                .map(|t| quoted(if t.3 { "steelblue" } else { "firebrick" }))
+                .collect();
+            let _ = write!(out, ",\"marker\":{{\"color\":[{}]}}", colors.join(","));
+            out.push('}');
+        }
+        out.push_str(
+            "],\"layout\":{\"barmode\":\"overlay\",\"xaxis\":{\"title\":{\"text\":\"ms since run start\"}}}}",
+        );
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ExecutionGraphBuilder, InMemoryDb, Task};
+
+    struct Fetch;
+
+    impl Task<InMemoryDb> for Fetch {
+        type Input = ();
+        type Output = ();
+
+        fn execute(_input: Self::Input) -> Self::Output {}
+    }
+
+    #[test]
+    fn test_timeline_json() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder.add_task::<Fetch>().unwrap();
+        let mut graph = builder.build();
+        assert_eq!(
+            graph.timeline_json(),
+            "{\"data\":[],\"layout\":{\"barmode\":\"overlay\",\"xaxis\":{\"title\":{\"text\":\"ms since run start\"}}}}"
+        );
+
+        graph.execute_all().unwrap();
+        let json = graph.timeline_json();
+        assert!(json.contains("\"text\":[\"Fetch\"]"), "{json}");
+        assert!(json.contains("\"orientation\":\"h\""));
+    }
+}
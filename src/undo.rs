@@ -0,0 +1,238 @@
+use std::{
+    any::{Any, TypeId},
+    collections::{HashMap, VecDeque},
+};
+
+use crate::{DataBase, DbKey, ExecutionGraph, ExecutionGraphBuilder, KeyType};
+
+type Value = Option<Box<dyn Any>>;
+type CloneValue = fn(&dyn Any) -> Box<dyn Any>;
+
+/// Stores a value of a key, or removes it if `None`, returning the value it
+/// replaces.
+type Swap<Db> = fn(&mut Db, Value) -> Value;
+
+struct Change<Db> {
+    key: KeyType,
+    /// The value to store when the change is undone or redone.
+    value: Value,
+    swap: Swap<Db>,
+}
+
+struct Undoable<Db> {
+    clone: CloneValue,
+    swap: Swap<Db>,
+}
+
+pub(crate) struct UndoHistory<Db> {
+    limit: usize,
+    undoable: HashMap<TypeId, Undoable<Db>>,
+    undo: VecDeque<Change<Db>>,
+    redo: Vec<Change<Db>>,
+}
+
+impl<Db> Default for UndoHistory<Db> {
+    fn default() -> Self {
+        UndoHistory {
+            limit: 64,
+            undoable: HashMap::new(),
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+        }
+    }
+}
+
+impl<Db> UndoHistory<Db> {
+    /// Copies which keys are undoable and the limit, but not the history.
+    pub(crate) fn without_history(&self) -> Self {
+        UndoHistory {
+            limit: self.limit,
+            undoable: self
+                .undoable
+                .iter()
+                .map(|(id, undoable)| {
+                    let (clone, swap) = (undoable.clone, undoable.swap);
+                    (*id, Undoable { clone, swap })
+                })
+                .collect(),
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+        }
+    }
+}
+
+fn swap<Db: DataBase, K: DbKey>(db: &mut Db, value: Value) -> Value {
+    let replaced = match value {
+        Some(value) => db.put::<K>(*value.downcast::<K::Value>().unwrap()),
+        None => db.remove::<K>(),
+    };
+    replaced.map(|value| Box::new(value) as Box<dyn Any>)
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraphBuilder<Db, Ctx> {
+    /// Records every [`set_input`](ExecutionGraph::set_input) of `K`, so it
+    /// can be undone with [`ExecutionGraph::undo_input_change`].
+    pub fn undoable_input<K: DbKey>(&mut self) -> &mut Self
+    where
+        K::Value: Clone,
+    {
+        self.graph.undo.undoable.insert(
+            TypeId::of::<K>(),
+            Undoable {
+                clone: |value| Box::new(value.downcast_ref::<K::Value>().unwrap().clone()),
+                swap: swap::<Db, K>,
+            },
+        );
+        self
+    }
+
+    /// Sets how many input changes can be undone. Defaults to 64.
+    pub fn undo_limit(&mut self, limit: usize) -> &mut Self {
+        self.graph.undo.limit = limit;
+        self
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraph<Db, Ctx> {
+    /// Records that `K` was set, replacing `previous`, if `K` is undoable.
+    /// Clears the changes that could be redone.
+    pub(crate) fn record_input_change<K: DbKey>(&mut self, previous: Option<&K::Value>) {
+        let history = &mut self.undo;
+        let Some(undoable) = history.undoable.get(&TypeId::of::<K>()) else {
+            return;
+        };
+        let change = Change {
+            key: KeyType::of::<K>(),
+            value: previous.map(|value| (undoable.clone)(value)),
+            swap: undoable.swap,
+        };
+        history.redo.clear();
+        history.undo.push_back(change);
+        while history.undo.len() > history.limit {
+            history.undo.pop_front();
+        }
+    }
+
+    /// Restores the value the last recorded input change replaced, dropping
+    /// the outputs of the tasks reading it so the next run recomputes them.
+    /// Returns the key restored, or `None` if there is nothing to undo.
+    pub fn undo_input_change(&mut self) -> Option<KeyType> {
+        let change = self.undo.undo.pop_back()?;
+        let change = self.apply_change(change);
+        let key = change.key;
+        self.undo.redo.push(change);
+        Some(key)
+    }
+
+    /// Applies again the last input change undone, like
+    /// [`undo_input_change`](Self::undo_input_change).
+    pub fn redo_input_change(&mut self) -> Option<KeyType> {
+        let change = self.undo.redo.pop()?;
+        let change = self.apply_change(change);
+        let key = change.key;
+        self.undo.undo.push_back(change);
+        Some(key)
+    }
+
+    /// Stores the value of `change`, returning the change restoring the value
+    /// it replaced.
+    fn apply_change(&mut self, change: Change<Db>) -> Change<Db> {
+        let Change { key, value, swap } = change;
+        let replaced = self.with_shared_db(|graph| {
+            let replaced = swap(&mut graph.db, value);
+            graph.invalidate_dependents(&[key]);
+            replaced
+        });
+        self.bump_revision(key);
+        Change {
+            key,
+            value: replaced,
+            swap,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemoryDb, Task, TaskInput, TaskOutput};
+
+    struct Text;
+
+    impl DbKey for Text {
+        type Value = String;
+    }
+
+    struct TextIn(String);
+
+    impl DbKey for TextIn {
+        type Value = TextIn;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for TextIn {
+        fn from_db(db: &Db) -> Self {
+            TextIn(db.get::<Text>().unwrap().clone())
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Text>()]
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Words(usize);
+
+    impl DbKey for Words {
+        type Value = Words;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for Words {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<Words>(Words(self.0));
+        }
+    }
+
+    struct CountWords;
+
+    impl Task<InMemoryDb> for CountWords {
+        type Input = TextIn;
+        type Output = Words;
+
+        fn execute(input: Self::Input) -> Self::Output {
+            Words(input.0.split_whitespace().count())
+        }
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .undoable_input::<Text>()
+            .undo_limit(2)
+            .add_input::<Text>("a".to_string())
+            .unwrap()
+            .add_task::<CountWords>()
+            .unwrap();
+        let mut graph = builder.build();
+        for text in ["a b", "a b c", "a b c d"] {
+            graph.set_input::<Text>(text.to_string()).unwrap();
+        }
+        graph.execute_all().unwrap();
+        assert_eq!(graph.db().get::<Words>(), Some(&Words(4)));
+
+        assert_eq!(graph.undo_input_change(), Some(KeyType::of::<Text>()));
+        assert_eq!(graph.db().get::<Words>(), None);
+        graph.execute_all().unwrap();
+        assert_eq!(graph.db().get::<Words>(), Some(&Words(3)));
+
+        // Only two changes are kept.
+        assert!(graph.undo_input_change().is_some());
+        assert!(graph.undo_input_change().is_none());
+        assert_eq!(graph.db().get::<Text>().unwrap(), "a b");
+
+        assert!(graph.redo_input_change().is_some());
+        assert!(graph.redo_input_change().is_some());
+        assert!(graph.redo_input_change().is_none());
+        assert_eq!(graph.db().get::<Text>().unwrap(), "a b c d");
+    }
+}
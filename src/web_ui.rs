@@ -0,0 +1,470 @@
+use std::{
+    any::{type_name, TypeId},
+    collections::BTreeMap,
+    fmt::Write as _,
+    io::{self, BufRead, BufReader, Write},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    describe::GraphDescription, json::push_json_str, DataBase, ExecutionGraph, KeyType,
+    TaskWithContext,
+};
+
+pub(crate) type SharedState = Arc<Mutex<UiState>>;
+
+/// How long a client may take to send its request or read the response.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Default)]
+pub(crate) struct UiState {
+    topology: GraphDescription,
+    /// The [generation](crate::reach::ReachabilityCache::generation) of the
+    /// graph `topology` was taken from.
+    generation: Option<u64>,
+    /// The state of each node of `topology`, by index.
+    values: Vec<ValueState>,
+    tasks: BTreeMap<&'static str, TaskState>,
+}
+
+struct ValueState {
+    /// Type name of the stored value, if one is stored.
+    cached: Option<&'static str>,
+    /// Whether the value is written by a task and needs to be computed
+    /// again: it is missing, poisoned or computed from an input that changed
+    /// since.
+    dirty: bool,
+}
+
+#[derive(Default)]
+struct TaskState {
+    running: bool,
+    runs: u64,
+    last_started_ms: Option<u128>,
+    last_duration_ms: Option<f64>,
+}
+
+/// A small HTTP server exposing the state of an attached graph. `/` serves an
+/// HTML page that polls `/graph.json` for updates.
+///
+/// The server thread runs until the `WebUi` is dropped.
+pub struct WebUi {
+    state: SharedState,
+    addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+    server: Option<JoinHandle<()>>,
+}
+
+impl WebUi {
+    pub fn serve(addr: impl ToSocketAddrs) -> io::Result<WebUi> {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        let state = SharedState::default();
+        let stop = Arc::new(AtomicBool::new(false));
+        let (server_state, server_stop) = (state.clone(), stop.clone());
+        let server = thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                if server_stop.load(Ordering::Acquire) {
+                    break;
+                }
+                let state = server_state.clone();
+                // A misbehaving client must not take the server down, nor
+                // hold up other clients.
+                thread::spawn(move || handle(stream, &state));
+            }
+        });
+        Ok(WebUi {
+            state,
+            addr,
+            stop,
+            server: Some(server),
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+impl Drop for WebUi {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+        // Wake the server thread up from `accept` so that it sees the flag.
+        let mut wake = self.addr;
+        if wake.ip().is_unspecified() {
+            wake.set_ip(match wake.ip() {
+                IpAddr::V4(_) => Ipv4Addr::LOCALHOST.into(),
+                IpAddr::V6(_) => Ipv6Addr::LOCALHOST.into(),
+            });
+        }
+        if TcpStream::connect(wake).is_ok() {
+            if let Some(server) = self.server.take() {
+                let _ = server.join();
+            }
+        }
+    }
+}
+
+/// A task shown as running in an attached web UI. Dropping it, even while
+/// unwinding from a panic, shows the task as stopped.
+pub(crate) struct TaskRun {
+    state: SharedState,
+    task: &'static str,
+    started: Instant,
+}
+
+impl Drop for TaskRun {
+    fn drop(&mut self) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let task = state.tasks.entry(self.task).or_default();
+        task.running = false;
+        task.runs += 1;
+        task.last_duration_ms = Some(self.started.elapsed().as_secs_f64() * 1000.0);
+    }
+}
+
+impl<Db: DataBase, Ctx> ExecutionGraph<Db, Ctx> {
+    pub fn attach_web_ui(&mut self, ui: &WebUi) -> &mut Self {
+        self.web_ui = Some(ui.state.clone());
+        self.publish_web_ui();
+        self
+    }
+
+    pub(crate) fn web_ui_task_started<T: TaskWithContext<Db, Ctx>>(&self) -> Option<TaskRun> {
+        let state = self.web_ui.as_ref()?;
+        let task = type_name::<T>();
+        let mut locked = state.lock().unwrap();
+        let shown = locked.tasks.entry(task).or_default();
+        shown.running = true;
+        shown.last_started_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .ok()
+            .map(|d| d.as_millis());
+        Some(TaskRun {
+            state: state.clone(),
+            task,
+            started: Instant::now(),
+        })
+    }
+
+    pub(crate) fn web_ui_task_finished(&self, run: Option<TaskRun>) {
+        drop(run);
+        self.publish_web_ui();
+    }
+
+    /// Updates the attached web UI with the state of every value, and with
+    /// the topology if the graph changed since it was last shown.
+    pub(crate) fn publish_web_ui(&self) {
+        let Some(state) = &self.web_ui else {
+            return;
+        };
+        let generation = self.reachability.generation();
+        let topology = {
+            let state = state.lock().unwrap();
+            (state.generation != Some(generation)).then(|| self.describe())
+        };
+        let values = self
+            .tasks
+            .node_indices()
+            .map(|i| self.value_state(self.tasks[i]))
+            .collect();
+        let mut state = state.lock().unwrap();
+        if let Some(topology) = topology {
+            state.topology = topology;
+            state.generation = Some(generation);
+        }
+        state.values = values;
+    }
+
+    fn value_state(&self, id: TypeId) -> ValueState {
+        let key = KeyType {
+            id,
+            name: self.names[&id],
+        };
+        let stored = self.with_db(|db| db.contains(key)).unwrap_or(false);
+        // Keys other than inputs, like task outputs, hold values of their
+        // own type.
+        let value_type = self.value_types.get(&id).copied().unwrap_or(key.name);
+        ValueState {
+            cached: stored.then_some(value_type),
+            dirty: self.writer_of(key).is_some()
+                && (!stored || self.poisoned.contains(&id) || self.is_outdated(id)),
+        }
+    }
+}
+
+fn handle(stream: TcpStream, state: &SharedState) -> io::Result<()> {
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+
+    let (status, content_type, body) = match path {
+        "/" => ("200 OK", "text/html; charset=utf-8", INDEX_HTML.to_string()),
+        "/graph.json" => (
+            "200 OK",
+            "application/json",
+            to_json(&state.lock().unwrap()),
+        ),
+        _ => ("404 Not Found", "text/plain", "not found".to_string()),
+    };
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()
+}
+
+fn to_json(state: &UiState) -> String {
+    let mut out = String::from("{\"nodes\":[");
+    for (i, node) in state.topology.nodes.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        push_json_str(&mut out, node);
+    }
+    out.push_str("],\"edges\":[");
+    for (i, (from, to)) in state.topology.edges.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        let _ = write!(out, "[{from},{to}]");
+    }
+    out.push_str("],\"values\":[");
+    for (i, value) in state.values.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str("{\"cached\":");
+        match value.cached {
+            Some(value_type) => push_json_str(&mut out, value_type),
+            None => out.push_str("null"),
+        }
+        let _ = write!(out, ",\"dirty\":{}}}", value.dirty);
+    }
+    out.push_str("],\"annotations\":[");
+    for (i, (node, key, value)) in state.topology.annotations.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        let _ = write!(out, "[{node},");
+        push_json_str(&mut out, key);
+        out.push(',');
+        push_json_str(&mut out, value);
+        out.push(']');
+    }
+    out.push_str("],\"tasks\":[");
+    for (i, (name, task)) in state.tasks.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str("{\"name\":");
+        push_json_str(&mut out, name);
+        let _ = write!(
+            out,
+            ",\"running\":{},\"runs\":{},\"last_started_ms\":{},\"last_duration_ms\":{}}}",
+            task.running,
+            task.runs,
+            json_opt(task.last_started_ms),
+            json_opt(task.last_duration_ms),
+        );
+    }
+    out.push_str("]}");
+    out
+}
+
+fn json_opt<T: ToString>(value: Option<T>) -> String {
+    value.map_or_else(|| "null".to_string(), |v| v.to_string())
+}
+
+const INDEX_HTML: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<title>computation-graph</title>
+<style>
+body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; margin-bottom: 2em; }
+td, th { border: 1px solid #ccc; padding: 4px 8px; text-align: left; }
+.running { background: #cde; }
+</style>
+</head>
+<body>
+<h2>Tasks</h2>
+<table id="tasks"></table>
+<h2>Nodes</h2>
+<table id="nodes"></table>
+<script>
+function esc(s) {
+  return String(s).replace(/[&<>]/g, c => ({'&': '&amp;', '<': '&lt;', '>': '&gt;'})[c]);
+}
+async function refresh() {
+  const g = await (await fetch('/graph.json')).json();
+  document.getElementById('tasks').innerHTML =
+    '<tr><th>task</th><th>runs</th><th>last run</th><th>duration (ms)</th></tr>' +
+    g.tasks.map(t => `<tr class="${t.running ? 'running' : ''}"><td>${esc(t.name)}</td>` +
+      `<td>${t.runs}</td>` +
+      `<td>${t.last_started_ms === null ? '' : new Date(t.last_started_ms).toLocaleTimeString()}</td>` +
+      `<td>${t.last_duration_ms === null ? '' : t.last_duration_ms.toFixed(3)}</td></tr>`).join('');
+  document.getElementById('nodes').innerHTML =
+    '<tr><th>#</th><th>key</th><th>cached value</th><th>dirty</th><th>feeds</th><th>annotations</th></tr>' +
+    g.nodes.map((n, i) => `<tr><td>${i}</td><td>${esc(n)}</td>` +
+      `<td>${g.values[i].cached === null ? '' : esc(g.values[i].cached)}</td>` +
+      `<td>${g.values[i].dirty ? 'yes' : ''}</td>` +
+      `<td>${g.edges.filter(e => e[0] === i).map(e => e[1]).join(', ')}</td>` +
+      `<td>${g.annotations.filter(a => a[0] === i).map(a => esc(a[1] + '=' + a[2])).join(', ')}</td></tr>`).join('');
+}
+refresh();
+setInterval(refresh, 1000);
+</script>
+</body>
+</html>
+"#;
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+    use crate::{DbKey, ExecutionGraphBuilder, InMemoryDb, Task, TaskInput, TaskOutput};
+
+    struct Noop;
+
+    impl Task<InMemoryDb> for Noop {
+        type Input = ();
+        type Output = ();
+
+        fn execute(_input: Self::Input) -> Self::Output {}
+    }
+
+    struct Celsius;
+
+    impl DbKey for Celsius {
+        type Value = i32;
+    }
+
+    struct Reading(i32);
+
+    impl DbKey for Reading {
+        type Value = Reading;
+    }
+
+    impl<Db: DataBase> TaskInput<Db> for Reading {
+        fn from_db(db: &Db) -> Self {
+            Reading(*db.get::<Celsius>().unwrap())
+        }
+
+        fn dep_types() -> Vec<KeyType> {
+            vec![KeyType::of::<Celsius>()]
+        }
+    }
+
+    struct Fahrenheit(i32);
+
+    impl DbKey for Fahrenheit {
+        type Value = Fahrenheit;
+    }
+
+    impl<Db: DataBase> TaskOutput<Db> for Fahrenheit {
+        fn to_db(&self, db: &mut Db) {
+            db.put::<Fahrenheit>(Fahrenheit(self.0));
+        }
+    }
+
+    struct Convert;
+
+    impl Task<InMemoryDb> for Convert {
+        type Input = Reading;
+        type Output = Fahrenheit;
+
+        fn execute(input: Self::Input) -> Self::Output {
+            assert!(input.0 > -274, "below absolute zero");
+            Fahrenheit(input.0 * 9 / 5 + 32)
+        }
+    }
+
+    fn fetch(addr: SocketAddr, path: &str) -> String {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(stream, "GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn test_web_ui_reports_runs() {
+        let ui = WebUi::serve("127.0.0.1:0").unwrap();
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder.add_task::<Noop>().unwrap();
+        let mut graph = builder.build();
+        graph.attach_web_ui(&ui);
+        graph.execute::<Noop>().unwrap();
+
+        let json = fetch(ui.local_addr(), "/graph.json");
+        assert!(json.starts_with("HTTP/1.1 200 OK"));
+        assert!(json.contains("Noop\",\"running\":false,\"runs\":1"));
+        assert!(json.contains(type_name::<<() as DbKey>::Value>()));
+        assert!(fetch(ui.local_addr(), "/missing").starts_with("HTTP/1.1 404"));
+    }
+
+    #[test]
+    fn test_silent_client_does_not_block_others() {
+        let ui = WebUi::serve("127.0.0.1:0").unwrap();
+        let _silent = TcpStream::connect(ui.local_addr()).unwrap();
+        assert!(fetch(ui.local_addr(), "/").starts_with("HTTP/1.1 200 OK"));
+    }
+
+    #[test]
+    fn test_web_ui_reports_values() {
+        let ui = WebUi::serve("127.0.0.1:0").unwrap();
+        let mut builder = ExecutionGraphBuilder::new(InMemoryDb::new());
+        builder
+            .add_input::<Celsius>(100)
+            .unwrap()
+            .add_task::<Convert>()
+            .unwrap();
+        let mut graph = builder.build();
+        graph.attach_web_ui(&ui);
+        let celsius = format!("{{\"cached\":\"{}\",\"dirty\":false}}", type_name::<i32>());
+        let json = fetch(ui.local_addr(), "/graph.json");
+        assert!(json.contains(&celsius), "{json}");
+        assert!(json.contains("{\"cached\":null,\"dirty\":true}"), "{json}");
+
+        graph.execute_all().unwrap();
+        let fahrenheit = format!(
+            "{{\"cached\":\"{}\",\"dirty\":false}}",
+            type_name::<Fahrenheit>()
+        );
+        assert!(fetch(ui.local_addr(), "/graph.json").contains(&fahrenheit));
+
+        graph.set_input::<Celsius>(-300).unwrap();
+        let json = fetch(ui.local_addr(), "/graph.json");
+        assert!(
+            json.contains(&fahrenheit.replace("false", "true")),
+            "{json}"
+        );
+
+        let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _ = graph.execute::<Convert>();
+        }));
+        assert!(panicked.is_err());
+        let json = fetch(ui.local_addr(), "/graph.json");
+        assert!(
+            json.contains("Convert\",\"running\":false,\"runs\":2"),
+            "{json}"
+        );
+
+        let addr = ui.local_addr();
+        drop(ui);
+        assert!(TcpStream::connect(addr).is_err());
+    }
+}